@@ -66,7 +66,11 @@ def parse_emulator_addresses(firebase_output_file, timeout=timedelta(minutes=1))
 def create_config(emulator_hosts):
   """Creates and writes config of the app to .env file."""
   config = json.dumps({
+    # The emulator accepts any API key.
+    'api_key': 'fake-api-key',
     'auth_host': f"http://{emulator_hosts['Authentication']}/",
+    'database_host': f"http://{emulator_hosts['Database']}/",
+    'database_namespace': 'packing-checklist-3879',
   })
   with open('.env', 'w') as env_file:
     env_file.write(config)
@@ -0,0 +1,62 @@
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub label: String,
+    // Emitted with the trimmed text; never with an empty one.
+    pub on_add: Callback<String>,
+}
+
+// Faded "Add new ..." panel block, which turns into an input with an "Add" button when clicked.
+#[function_component(AddInput)]
+pub fn add_input(props: &Props) -> Html {
+    let expanded = use_state(|| false);
+    let text = use_state(String::new);
+
+    let on_expand = {
+        let expanded = expanded.clone();
+        Callback::from(move |_: MouseEvent| expanded.set(true))
+    };
+    let on_input = {
+        let text = text.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            text.set(input.value());
+        })
+    };
+    let on_submit = {
+        let (expanded, text) = (expanded.clone(), text.clone());
+        let on_add = props.on_add.clone();
+        Callback::from(move |e: FocusEvent| {
+            e.prevent_default();
+            let new_text = text.trim().to_string();
+            if !new_text.is_empty() {
+                on_add.emit(new_text);
+            }
+            text.set(String::new());
+            expanded.set(false);
+        })
+    };
+
+    if *expanded {
+        html! {
+          <form class="panel-block" onsubmit={on_submit}>
+            <input class="input is-primary" type="text" placeholder={props.label.clone()}
+                value={(*text).clone()} oninput={on_input}/>
+            <button class="button is-primary ml-1" type="submit">
+              { "Add" }
+            </button>
+          </form>
+        }
+    } else {
+        html! {
+          <a class="panel-block add-new-item is-justify-content-center" onclick={on_expand}>
+            <span class="panel-icon">
+              <i class="fas fa-plus"></i>
+            </span>
+            { props.label.clone() }
+          </a>
+        }
+    }
+}
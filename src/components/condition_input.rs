@@ -0,0 +1,123 @@
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+
+use crate::types::{Condition, Season};
+
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub on_add: Callback<Condition>,
+}
+
+// Kinds of conditions that can be picked, with the hint shown in the value input.
+const KINDS: [(&str, &str); 6] = [
+    ("hasTag", "Tag, e.g. beach"),
+    ("destinationIn", "Countries, comma separated"),
+    ("destinationNotIn", "Countries, comma separated"),
+    ("minDays", "Number of days"),
+    ("maxDays", "Number of days"),
+    ("seasonIn", "Seasons, comma separated"),
+];
+
+fn kind_label(kind: &str) -> &'static str {
+    match kind {
+        "hasTag" => "Tagged",
+        "destinationIn" => "Destination in",
+        "destinationNotIn" => "Destination not in",
+        "minDays" => "At least days",
+        "maxDays" => "At most days",
+        _ => "Season in",
+    }
+}
+
+// Select of the condition's kind and an input for its value, e.g. "Destination not in" "Poland".
+#[function_component(ConditionInput)]
+pub fn condition_input(props: &Props) -> Html {
+    let kind = use_state(|| KINDS[0].0.to_string());
+    let value = use_state(String::new);
+    let error = use_state(|| None::<String>);
+
+    let on_kind_change = {
+        let kind = kind.clone();
+        Callback::from(move |e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            kind.set(select.value());
+        })
+    };
+    let on_input = {
+        let value = value.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            value.set(input.value());
+        })
+    };
+    let on_submit = {
+        let (kind, value, error) = (kind.clone(), value.clone(), error.clone());
+        let on_add = props.on_add.clone();
+        Callback::from(move |e: FocusEvent| {
+            e.prevent_default();
+            match parse_condition(&kind, &value) {
+                Ok(condition) => {
+                    on_add.emit(condition);
+                    value.set(String::new());
+                    error.set(None);
+                },
+                Err(message) => error.set(Some(message)),
+            }
+        })
+    };
+
+    let kind_options = KINDS.iter().map(|(option, _)| html! {
+        <option value={*option} selected={*option == kind.as_str()}>{ kind_label(option) }</option>
+    }).collect::<Html>();
+    let placeholder = KINDS.iter()
+        .find(|(option, _)| *option == kind.as_str())
+        .map(|(_, hint)| *hint)
+        .unwrap_or_default();
+    html! {
+      <form onsubmit={on_submit}>
+        <div class="field has-addons">
+          <div class="control">
+            <div class="select is-small">
+              <select onchange={on_kind_change}>{ kind_options }</select>
+            </div>
+          </div>
+          <div class="control is-expanded">
+            <input class="input is-small" type="text" {placeholder}
+                value={(*value).clone()} oninput={on_input}/>
+          </div>
+          <div class="control">
+            <button class="button is-info is-light is-small" type="submit">{ "Add" }</button>
+          </div>
+        </div>
+        if let Some(error) = &*error {
+          <p class="help is-danger">{ error }</p>
+        }
+      </form>
+    }
+}
+
+fn parse_condition(kind: &str, value: &str) -> Result<Condition, String> {
+    let list = value.split(',')
+        .map(|element| element.trim().to_string())
+        .filter(|element| !element.is_empty())
+        .collect::<Vec<_>>();
+    if list.is_empty() {
+        return Err("Value is required.".to_string());
+    }
+    let days = || value.trim().parse::<u32>()
+        .map_err(|_| format!("'{}' is not a number of days.", value.trim()));
+    match kind {
+        "hasTag" => Ok(Condition::HasTag(value.trim().to_string())),
+        "destinationIn" => Ok(Condition::DestinationIn(list)),
+        "destinationNotIn" => Ok(Condition::DestinationNotIn(list)),
+        "minDays" => days().map(Condition::MinDays),
+        "maxDays" => days().map(Condition::MaxDays),
+        _ => list.iter()
+            .map(|name| Season::ALL.iter()
+                .find(|season| season.to_string().eq_ignore_ascii_case(name))
+                .copied()
+                .ok_or(format!("'{}' is not a season.", name)))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Condition::SeasonIn),
+    }
+}
@@ -0,0 +1,78 @@
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub value: String,
+    // Emitted with the new, trimmed value; only if it is non-empty and differs from the old one.
+    pub on_change: Callback<String>,
+}
+
+// Text with an edit button next to it, which turns it into an input for renaming in place.
+#[function_component(EditableText)]
+pub fn editable_text(props: &Props) -> Html {
+    let editing = use_state(|| false);
+    let draft = use_state(|| props.value.clone());
+
+    let on_edit = {
+        let (editing, draft) = (editing.clone(), draft.clone());
+        let value = props.value.clone();
+        Callback::from(move |_: MouseEvent| {
+            draft.set(value.clone());
+            editing.set(true);
+        })
+    };
+    let on_input = {
+        let draft = draft.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            draft.set(input.value());
+        })
+    };
+    let on_submit = {
+        let (editing, draft) = (editing.clone(), draft.clone());
+        let value = props.value.clone();
+        let on_change = props.on_change.clone();
+        Callback::from(move |e: FocusEvent| {
+            e.prevent_default();
+            let new_value = draft.trim().to_string();
+            if !new_value.is_empty() && new_value != value {
+                on_change.emit(new_value);
+            }
+            editing.set(false);
+        })
+    };
+    let on_cancel = {
+        let editing = editing.clone();
+        Callback::from(move |_: MouseEvent| editing.set(false))
+    };
+
+    if *editing {
+        html! {
+          <form class="field has-addons is-flex-grow-1" onsubmit={on_submit}>
+            <div class="control is-expanded">
+              <input class="input is-small" type="text" value={(*draft).clone()} oninput={on_input}/>
+            </div>
+            <div class="control">
+              <button class="button is-primary is-small" type="submit">
+                <span class="icon is-small"><i class="fas fa-check"></i></span>
+              </button>
+            </div>
+            <div class="control">
+              <button class="button is-small" type="button" onclick={on_cancel}>
+                <span class="icon is-small"><i class="fas fa-xmark"></i></span>
+              </button>
+            </div>
+          </form>
+        }
+    } else {
+        html! {
+          <span class="is-flex is-align-items-center">
+            <span>{ props.value.clone() }</span>
+            <button class="button is-primary is-light is-small ml-2" onclick={on_edit}>
+              <span class="icon is-small"><i class="fas fa-edit"></i></span>
+            </button>
+          </span>
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::types::{QuantityFormula};
+
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub value: Option<String>,
+    // Emitted with the trimmed formula, or None once it is cleared; only if the formula parses and
+    // differs from the old one.
+    pub on_change: Callback<Option<String>>,
+}
+
+// Small input for an item's quantity formula. The formula is checked as it is typed and the error,
+// if any, is shown right below it; invalid formulas are never emitted.
+#[function_component(FormulaInput)]
+pub fn formula_input(props: &Props) -> Html {
+    let draft = use_state(|| props.value.clone().unwrap_or_default());
+
+    {
+        // The draft follows the value whenever it changes from the outside, e.g. after a revert.
+        let draft = draft.clone();
+        use_effect_with_deps(move |value: &Option<String>| {
+            draft.set(value.clone().unwrap_or_default());
+            || ()
+        }, props.value.clone());
+    }
+
+    let error = match draft.trim() {
+        "" => None,
+        formula => QuantityFormula::parse(formula).err(),
+    };
+
+    let on_input = {
+        let draft = draft.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            draft.set(input.value());
+        })
+    };
+    let on_submit = {
+        let draft = draft.clone();
+        let value = props.value.clone();
+        let on_change = props.on_change.clone();
+        let valid = error.is_none();
+        Callback::from(move |e: FocusEvent| {
+            e.prevent_default();
+            let new_value = Some(draft.trim().to_string()).filter(|formula| !formula.is_empty());
+            if valid && new_value != value {
+                on_change.emit(new_value);
+            }
+        })
+    };
+
+    let mut input_classes = classes!("input", "is-small");
+    if error.is_some() {
+        input_classes.push("is-danger");
+    }
+    html! {
+      <form class="field" onsubmit={on_submit}>
+        <div class="control">
+          <input class={input_classes} type="text" placeholder="Quantity, e.g. nights + 1"
+              value={(*draft).clone()} oninput={on_input}/>
+        </div>
+        if let Some(error) = error {
+          <p class="help is-danger">{ error.to_string() }</p>
+        }
+      </form>
+    }
+}
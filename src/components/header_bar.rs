@@ -1,6 +1,7 @@
 use yew::prelude::*;
 use yew_router::prelude::*;
 
+use crate::components::user_context_provider::{AuthAction, AuthDispatcher, AuthResult};
 use crate::types::{UserAuthStatus};
 use crate::routes::{Route};
 
@@ -11,6 +12,41 @@ pub fn header_bar() -> Html {
     } else {
         UserAuthStatus::new_empty()
     };
+    let dispatcher = use_context::<AuthDispatcher>();
+
+    let verification_banner = if user_auth.email_verified() == Some(false) {
+        html! { <VerificationBanner/> }
+    } else {
+        html! {}
+    };
+
+    let sign_out_button = match &user_auth.user_info {
+        Some(user_info) => {
+            let is_anonymous = user_info.is_anonymous;
+            let on_sign_out = Callback::from(move |_: MouseEvent| {
+                // Nobody can sign back in to a guest account.
+                if is_anonymous && !confirm(
+                    "Your trips and templates will be lost, unless you create an account on the \
+                     profile page first. Leave anyway?")
+                {
+                    return;
+                }
+                if let Some(dispatcher) = &dispatcher {
+                    dispatcher.emit(AuthAction::SignOut);
+                }
+            });
+            html! {
+              <p class="control">
+                <button class="bd-tw-button button" title="Log out" onclick={on_sign_out}>
+                  <span class="icon">
+                    <i class="fas fa-sign-out"></i>
+                  </span>
+                </button>
+              </p>
+            }
+        },
+        None => html! {},
+    };
 
     let profile_button = if let Some(user_info) = user_auth.user_info {
         html! {
@@ -37,6 +73,7 @@ pub fn header_bar() -> Html {
     };
 
     html! {
+      <>
       <nav class="navbar has-shadow is-primary" role="navigation" aria-label="main navigation">
 
         <div class="navbar-brand px-2">
@@ -56,10 +93,61 @@ pub fn header_bar() -> Html {
               <p class="control">
                 { profile_button }
               </p>
+              { sign_out_button }
             </div>
           </div>
         </div>
 
       </nav>
+      { verification_banner }
+      </>
+    }
+}
+
+// Reminds users with an unverified email to verify it, and lets them have the email sent again.
+#[function_component(VerificationBanner)]
+fn verification_banner() -> Html {
+    let dispatcher = use_context::<AuthDispatcher>();
+    let sending = use_state(|| false);
+    // Result of the last resend, if there was one.
+    let resent = use_state(|| None::<AuthResult>);
+
+    let on_resend = {
+        let (sending, resent) = (sending.clone(), resent.clone());
+        Callback::from(move |_: MouseEvent| {
+            if let Some(dispatcher) = &dispatcher {
+                sending.set(true);
+                let (sending, resent) = (sending.clone(), resent.clone());
+                let on_done = Callback::from(move |result: AuthResult| {
+                    sending.set(false);
+                    resent.set(Some(result));
+                });
+                dispatcher.emit(AuthAction::SendVerificationEmail { on_done });
+            }
+        })
+    };
+    let mut resend_classes = classes!("button", "is-small", "is-warning", "is-light", "ml-2");
+    if *sending {
+        resend_classes.push("is-loading");
     }
+
+    html! {
+      <div class="notification is-warning is-light is-radiusless mb-0 py-2 has-text-centered">
+        { "Your email address is not verified yet. Follow the link in the email we sent you." }
+        <button class={resend_classes} onclick={on_resend}>{ "Resend" }</button>
+        {
+            match &*resent {
+                Some(Ok(())) => html! { <span class="ml-2">{ "Sent." }</span> },
+                Some(Err(err)) => html! { <span class="ml-2 has-text-danger">{ err.to_string() }</span> },
+                None => html! {},
+            }
+        }
+      </div>
+    }
+}
+
+fn confirm(message: &str) -> bool {
+    web_sys::window()
+        .and_then(|window| window.confirm_with_message(message).ok())
+        .unwrap_or(false)
 }
@@ -1,15 +1,33 @@
+pub mod add_input;
 pub mod breadcrumbs;
+pub mod condition_input;
 pub mod config_provider;
+pub mod editable_text;
+pub mod formula_input;
 pub mod header_bar;
 pub mod incorrect_config;
+pub mod pagination;
 pub mod recent_widget;
+pub mod require_auth;
+pub mod session_scope;
+pub mod templates_widget;
+pub mod trip_creation_form;
 pub mod trips_widget;
 pub mod user_context_provider;
 
+pub use add_input::{AddInput};
 pub use breadcrumbs::*;
+pub use condition_input::{ConditionInput};
+pub use editable_text::{EditableText};
+pub use formula_input::{FormulaInput};
 pub use header_bar::HeaderBar;
 pub use recent_widget::{RecentWidget};
+pub use require_auth::{RequireAuth};
+pub use session_scope::{SessionScope};
+pub use templates_widget::{TemplatesWidget};
+pub use trip_creation_form::{TripCreationForm};
 pub use trips_widget::{TripsWidget};
-pub use user_context_provider::{UserContextProvider};
+pub use user_context_provider::{
+    AccountChange, AuthAction, AuthDispatcher, AuthResult, UserContextProvider};
 pub use config_provider::{ConfigProvider};
 pub use incorrect_config::{IncorrectConfig};
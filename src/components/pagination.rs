@@ -0,0 +1,90 @@
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub current_page: u32,
+    pub last_page: u32,
+    pub on_page_change: Callback<u32>,
+}
+
+// Pagination controls for panels listing trips or templates. Pages start at 1.
+pub struct Pagination;
+
+impl Component for Pagination {
+    type Message = ();
+    type Properties = Props;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let Props { current_page, last_page, on_page_change } = ctx.props();
+        let (current_page, last_page) = (*current_page, *last_page);
+        let first_page = 1;
+
+        let mut previous_classes = classes!("pagination-previous");
+        let mut next_classes = classes!("pagination-next");
+        let mut first_page_classes = classes!("pagination-link");
+        let mut last_page_classes = classes!("pagination-link");
+
+        if current_page == first_page {
+            previous_classes.push("is-disabled");
+            first_page_classes.push("is-disabled");
+        }
+        if current_page == last_page {
+            next_classes.push("is-disabled");
+            last_page_classes.push("is-disabled");
+        }
+
+        let create_page_callback = |dest_page: u32| {
+            let on_page_change = on_page_change.clone();
+            Callback::from(move |_: MouseEvent| {
+                if dest_page < first_page || dest_page > last_page {
+                    log::error!("Trying to open page {} - not in the range [{}, {}]", dest_page, first_page, last_page);
+                } else {
+                    on_page_change.emit(dest_page);
+                }
+            })
+        };
+
+        html! {
+          <nav class="panel-block pagination is-centered" role="navigation" aria-label="pagination">
+            <button class={previous_classes} onclick={create_page_callback(current_page.saturating_sub(1))}>
+              { "Previous" }
+            </button>
+            <button class={next_classes} onclick={create_page_callback(current_page + 1)}>
+              { "Next page" }
+            </button>
+            <ul class="pagination-list">
+              if current_page > first_page {
+                <li>
+                  <button class={first_page_classes} aria-label="Goto page 1" onclick={create_page_callback(first_page)}>
+                    { first_page }
+                  </button>
+                </li>
+                <li><span class="pagination-ellipsis">{ "…" }</span></li>
+              }
+              <li>
+                <button class="pagination-link is-current" aria-label={format!("Page {}", current_page)} aria-current="page">
+                  { current_page }
+                </button>
+              </li>
+              if current_page < last_page {
+               <li><span class="pagination-ellipsis">{ "…" }</span></li>
+               <li>
+                 <button class={last_page_classes} aria-label={format!("Goto page {}", last_page)} onclick={create_page_callback(last_page)}>
+                   { last_page }
+                 </button>
+               </li>
+              }
+            </ul>
+          </nav>
+        }
+    }
+}
+
+// Number of pages needed to show all the elements; at least one, even if there are none.
+pub fn pages_needed(num_of_elements: u32, per_page: u32) -> u32 {
+    std::cmp::max(1, num_of_elements.div_ceil(per_page))
+}
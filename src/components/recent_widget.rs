@@ -1,4 +1,10 @@
 use yew::prelude::*;
+use yew_router::prelude::*;
+
+use crate::components::{TripCreationForm};
+use crate::routes::{Route};
+use crate::store::{Store, StoreError};
+use crate::types::{TemplateId, TripAttributes, TripId};
 
 #[derive(Clone, PartialEq)]
 pub enum ElementType {
@@ -9,7 +15,8 @@ pub enum ElementType {
 #[derive(Clone, PartialEq)]
 pub struct Element {
     pub name: String,
-    // Probably a relevant id in future
+    // TripId or TemplateId, depending on el_type.
+    pub id: u64,
     pub el_type: ElementType,
 }
 
@@ -19,47 +26,130 @@ pub struct Props {
     pub elements: Vec<Element>,
 }
 
-pub struct RecentWidget;
+pub enum Msg {
+    OpenCreationForm(TemplateId, String),
+    CloseCreationForm,
+    // Stack of templates, name and attributes of the trip.
+    CreateTrip(Vec<TemplateId>, String, TripAttributes),
+    TripCreated(Result<TripId, StoreError>),
+}
+
+pub struct RecentWidget {
+    // Template (and its name) the trip creation form is open for.
+    creating_from: Option<(TemplateId, String)>,
+    error: Option<String>,
+}
 
 impl Component for RecentWidget {
-    type Message = ();
+    type Message = Msg;
     type Properties = Props;
 
     fn create(_ctx: &Context<Self>) -> Self {
-        RecentWidget
+        RecentWidget {
+            creating_from: None,
+            error: None,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::OpenCreationForm(template_id, template_name) => {
+                self.creating_from = Some((template_id, template_name));
+                true
+            },
+            Msg::CloseCreationForm => {
+                self.creating_from = None;
+                true
+            },
+            Msg::CreateTrip(stack, name, attributes) => {
+                self.creating_from = None;
+                match Store::from_context(ctx.link()) {
+                    Ok(mut store) => {
+                        ctx.link().send_future(async move {
+                            Msg::TripCreated(
+                                store.create_trip_from_templates(&stack, &name, &attributes).await)
+                        });
+                        true
+                    },
+                    Err(err) => {
+                        self.error = Some(format!("{}", err));
+                        true
+                    },
+                }
+            },
+            Msg::TripCreated(Ok(trip_id)) => {
+                if let Some(history) = ctx.link().history() {
+                    history.push(Route::TripEdit { id: trip_id });
+                }
+                false
+            },
+            Msg::TripCreated(Err(err)) => {
+                log::error!("Failed to create a trip: {}", err);
+                self.error = Some(format!("{}", err));
+                true
+            },
+        }
     }
 
     fn view(&self, ctx: &Context<Self>) -> Html {
         let Props { title, elements } = ctx.props();
-        let html_els = elements.iter().map(|element| {
-            let mut tag_name = "a";
-            let mut class = classes!("panel-block");
-            let mut href = Some("link.html");
-            if element.el_type == ElementType::Template {
-                tag_name = "div";
-                class.push("is-justify-content-space-between");
-                href = None; 
-            }
-            html! {
-                <@{tag_name} {class} {href} >
-                    <div> {element.name.to_string()} </div>
-                    if element.el_type == ElementType::Template {
+        let html_els = elements.iter().map(|element| match element.el_type {
+            ElementType::Trip => html! {
+                <Link<Route> to={Route::Trip { id: element.id }} classes={classes!("panel-block")}
+                    key={element.id}>
+                    { element.name.clone() }
+                </Link<Route>>
+            },
+            ElementType::Template => {
+                let (template_id, template_name) = (element.id, element.name.clone());
+                html! {
+                    <div class="panel-block is-justify-content-space-between" key={element.id}>
+                      <Link<Route> to={Route::Template { template_id }}>{ element.name.clone() }</Link<Route>>
                       <div>
-                        <button class="button is-link is-light is-small">
+                        <button class="button is-link is-light is-small"
+                            onclick={ctx.link().callback(move |_: MouseEvent| Msg::OpenCreationForm(template_id, template_name.clone()))}>
                             { "Create trip" }
                         </button>
                       </div>
-                    }
-                </@>
-            }
+                    </div>
+                }
+            },
         }).collect::<Html>();
+        let creation_form = if let Some((template_id, template_name)) = &self.creating_from {
+            let template_id = *template_id;
+            let other_templates = elements.iter()
+                .filter(|element| element.el_type == ElementType::Template && element.id != template_id)
+                .map(|element| (element.id, element.name.clone()))
+                .collect::<Vec<_>>();
+            let on_submit = ctx.link().callback(
+                move |(name, stacked, attributes): (String, Vec<TemplateId>, TripAttributes)| {
+                    let mut stack = vec![template_id];
+                    stack.extend(stacked);
+                    Msg::CreateTrip(stack, name, attributes)
+                });
+            html! {
+              <TripCreationForm
+                  template_name={template_name.clone()}
+                  {other_templates}
+                  {on_submit}
+                  on_cancel={ctx.link().callback(|_: ()| Msg::CloseCreationForm)}/>
+            }
+        } else {
+            html! {}
+        };
         html! {
           <div class="panel">
 
             <p class="panel-heading">{title}</p>
 
+            if let Some(error) = &self.error {
+              <p class="panel-block has-text-danger">{ error }</p>
+            }
+
             { html_els }
 
+            { creation_form }
+
           </div>
         }
     }
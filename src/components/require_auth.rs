@@ -0,0 +1,43 @@
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+use crate::routes::{LoginQuery, Route};
+use crate::types::{UserAuthStatus};
+
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub children: Children,
+}
+
+// Shows the children only to authenticated users. Anybody else is sent to the login page, which
+// brings them back to the current route once they log in.
+#[function_component(RequireAuth)]
+pub fn require_auth(props: &Props) -> Html {
+    let user_auth = use_context::<UserAuthStatus>().unwrap_or_else(UserAuthStatus::new_empty);
+    let history = use_history();
+    let route = use_route::<Route>();
+    let must_log_in = user_auth.user_info.is_none() && !user_auth.restoring;
+
+    use_effect_with_deps(move |must_log_in: &bool| {
+        if let (true, Some(history)) = (*must_log_in, history) {
+            let query = LoginQuery { redirect: route.map(|route| route.to_path()) };
+            if let Err(err) = history.push_with_query(Route::Login, query) {
+                log::error!("Failed to redirect to login: {}", err);
+                history.push(Route::Login);
+            }
+        }
+        || ()
+    }, must_log_in);
+
+    if user_auth.user_info.is_some() {
+        html! { <>{ for props.children.iter() }</> }
+    } else {
+        html! {
+            <section class="section">
+              <div class="container">
+                <progress class="progress is-small is-primary"/>
+              </div>
+            </section>
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use yew::prelude::*;
+
+use crate::types::{UserAuthStatus, UserId};
+
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub children: Children,
+}
+
+// Wraps everything that shows a user's data. Whenever a user signs out (or another one signs in in
+// their place), the children are created anew, so that nothing loaded for - or still loading for -
+// the previous user survives. Pages that need authentication then send the user to log in, see
+// RequireAuth.
+#[function_component(SessionScope)]
+pub fn session_scope(props: &Props) -> Html {
+    let user_id = use_context::<UserAuthStatus>()
+        .and_then(|user_auth| user_auth.user_info)
+        .map(|user_info| user_info.user_id);
+    // The user rendered for last time, and how many times a user left since. Signing in does not
+    // count, so that e.g. the login page lives on to take the user where they wanted to go.
+    let last_session = use_mut_ref(|| (None::<UserId>, 0usize));
+    let generation = {
+        let mut last_session = last_session.borrow_mut();
+        if last_session.0.is_some() && last_session.0 != user_id {
+            last_session.1 += 1;
+        }
+        last_session.0 = user_id;
+        last_session.1
+    };
+
+    html! {
+        <div key={generation}>
+          { for props.children.iter() }
+        </div>
+    }
+}
@@ -0,0 +1,278 @@
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+use crate::components::pagination::{pages_needed, Pagination};
+use crate::components::{TripCreationForm};
+use crate::routes::{Route};
+use crate::store::{Store, StoreError};
+use crate::types::composition::{Templates};
+use crate::types::{Template, TemplateId, TripAttributes, TripId};
+
+pub enum Msg {
+    Loaded(Result<Templates, StoreError>),
+    Search(String),
+    GoToPage(u32),
+    Add,
+    Duplicate(TemplateId),
+    Delete(TemplateId),
+    // Result of adding, duplicating or deleting a template.
+    Modified(Result<(), StoreError>),
+    Created(Result<TemplateId, StoreError>),
+    OpenCreationForm(TemplateId),
+    CloseCreationForm,
+    // Stack of templates, name and attributes of the trip.
+    CreateTrip(Vec<TemplateId>, String, TripAttributes),
+    TripCreated(Result<TripId, StoreError>),
+}
+
+// Searchable, paginated list of all the user's templates.
+pub struct TemplatesWidget {
+    templates: Option<Templates>,
+    search: String,
+    current_page: u32,
+    // Template the trip creation form is open for.
+    creating_from: Option<TemplateId>,
+    error: Option<String>,
+}
+
+fn templates_per_page() -> u32 { 10 }
+
+impl Component for TemplatesWidget {
+    type Message = Msg;
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let widget = Self {
+            templates: None,
+            search: String::new(),
+            current_page: 1,
+            creating_from: None,
+            error: None,
+        };
+        widget.with_store(ctx, |mut store| async move { Msg::Loaded(store.get_templates().await) });
+        widget
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::Loaded(Ok(templates)) => {
+                self.templates = Some(templates);
+                self.current_page = std::cmp::min(self.current_page, self.last_page());
+                true
+            },
+            Msg::Search(search) => {
+                self.search = search;
+                self.current_page = 1;
+                true
+            },
+            Msg::GoToPage(dest_page) => {
+                self.current_page = dest_page;
+                true
+            },
+            Msg::Add => {
+                self.with_store(ctx, |mut store| async move {
+                    Msg::Created(store.create_template("New template").await)
+                });
+                false
+            },
+            Msg::Duplicate(template_id) => {
+                self.with_store(ctx, move |mut store| async move {
+                    Msg::Modified(store.duplicate_template(template_id).await.map(|_| ()))
+                });
+                false
+            },
+            Msg::Delete(template_id) => {
+                let name = self.template_name(template_id);
+                let confirmed = web_sys::window()
+                    .and_then(|window| window.confirm_with_message(
+                        &format!("Delete template \"{}\"?", name)).ok())
+                    .unwrap_or(false);
+                if confirmed {
+                    self.with_store(ctx, move |mut store| async move {
+                        Msg::Modified(store.delete_template(template_id).await)
+                    });
+                }
+                false
+            },
+            Msg::Modified(Ok(())) => {
+                self.error = None;
+                self.with_store(ctx, |mut store| async move { Msg::Loaded(store.get_templates().await) });
+                false
+            },
+            Msg::Created(Ok(template_id)) => {
+                if let Some(history) = ctx.link().history() {
+                    history.push(Route::TemplateEdit { template_id });
+                }
+                false
+            },
+            Msg::OpenCreationForm(template_id) => {
+                self.creating_from = Some(template_id);
+                true
+            },
+            Msg::CloseCreationForm => {
+                self.creating_from = None;
+                true
+            },
+            Msg::CreateTrip(stack, name, attributes) => {
+                self.creating_from = None;
+                self.with_store(ctx, move |mut store| async move {
+                    Msg::TripCreated(store.create_trip_from_templates(&stack, &name, &attributes).await)
+                });
+                true
+            },
+            Msg::TripCreated(Ok(trip_id)) => {
+                if let Some(history) = ctx.link().history() {
+                    history.push(Route::TripEdit { id: trip_id });
+                }
+                false
+            },
+            Msg::Loaded(Err(err)) | Msg::Modified(Err(err)) | Msg::Created(Err(err))
+                | Msg::TripCreated(Err(err)) =>
+            {
+                log::error!("Templates operation failed: {}", err);
+                self.error = Some(format!("{}", err));
+                true
+            },
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let link = ctx.link();
+        let on_search = link.callback(|e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            Msg::Search(input.value())
+        });
+
+        html!{
+            <div class="panel">
+
+              <div class="panel-block">
+                <p class="control has-icons-left">
+                  <input class="input" type="text" placeholder="Search" value={self.search.clone()} oninput={on_search}/>
+                  <span class="icon is-left">
+                  <i class="fas fa-search" aria-hidden="true"></i>
+                  </span>
+                </p>
+              </div>
+
+              <div class="panel-block">
+                <button class="button is-link is-outlined is-fullwidth" onclick={link.callback(|_: MouseEvent| Msg::Add)}>
+                    { "+ Add new template" }
+                </button>
+              </div>
+
+              if let Some(error) = &self.error {
+                <p class="panel-block has-text-danger">{ error }</p>
+              }
+
+              if self.templates.is_none() && self.error.is_none() {
+                <div class="panel-block"><progress class="progress is-small is-primary"/></div>
+              }
+
+              { self.wrap_templates(ctx) }
+
+              <Pagination
+                  current_page={self.current_page}
+                  last_page={self.last_page()}
+                  on_page_change={link.callback(Msg::GoToPage)}/>
+
+              { self.creation_form(ctx) }
+
+            </div>
+        }
+    }
+}
+
+impl TemplatesWidget {
+
+    fn with_store<F, Fut>(&self, ctx: &Context<Self>, make_future: F)
+        where F: FnOnce(Store) -> Fut, Fut: std::future::Future<Output = Msg> + 'static
+    {
+        match Store::from_context(ctx.link()) {
+            Ok(store) => ctx.link().send_future(make_future(store)),
+            Err(err) => ctx.link().send_message(Msg::Loaded(Err(err))),
+        }
+    }
+
+    // Templates matching the search, sorted by name.
+    fn matching_templates(&self) -> Vec<(TemplateId, &Template)> {
+        let search = self.search.trim().to_lowercase();
+        let mut matching = self.templates.iter()
+            .flat_map(|templates| templates.iter())
+            .filter(|(_, template)| template.name.to_lowercase().contains(&search))
+            .map(|(template_id, template)| (*template_id, template))
+            .collect::<Vec<_>>();
+        matching.sort_by_key(|(_, template)| template.name.to_lowercase());
+        matching
+    }
+
+    fn template_name(&self, template_id: TemplateId) -> String {
+        self.templates.as_ref()
+            .and_then(|templates| templates.get(&template_id))
+            .map(|template| template.name.clone())
+            .unwrap_or_default()
+    }
+
+    fn last_page(&self) -> u32 {
+        pages_needed(self.matching_templates().len() as u32, templates_per_page())
+    }
+
+    fn wrap_templates(&self, ctx: &Context<Self>) -> Html {
+        let link = ctx.link();
+        let start = ((self.current_page - 1) * templates_per_page()) as usize;
+        self.matching_templates().into_iter()
+            .skip(start)
+            .take(templates_per_page() as usize)
+            .map(|(template_id, template)| html! {
+                <div class="panel-block is-justify-content-space-between" key={template_id}>
+                  <Link<Route> to={Route::Template { template_id }}>
+                    { template.name.clone() }
+                  </Link<Route>>
+                  <div class="buttons">
+                    <Link<Route> to={Route::TemplateEdit { template_id }}
+                        classes={classes!("button", "is-primary", "is-light", "is-small")}>
+                      { "Edit" }
+                    </Link<Route>>
+                    <button class="button is-light is-small"
+                        onclick={link.callback(move |_: MouseEvent| Msg::Duplicate(template_id))}>
+                      { "Duplicate" }
+                    </button>
+                    <button class="button is-danger is-light is-small"
+                        onclick={link.callback(move |_: MouseEvent| Msg::Delete(template_id))}>
+                      { "Delete" }
+                    </button>
+                    <button class="button is-link is-light is-small"
+                        onclick={link.callback(move |_: MouseEvent| Msg::OpenCreationForm(template_id))}>
+                      { "Create trip" }
+                    </button>
+                  </div>
+                </div>
+            })
+            .collect::<Html>()
+    }
+
+    fn creation_form(&self, ctx: &Context<Self>) -> Html {
+        let template_id = match self.creating_from {
+            Some(template_id) => template_id,
+            None => return html! {},
+        };
+        let other_templates = self.matching_templates().into_iter()
+            .filter(|(other_id, _)| *other_id != template_id)
+            .map(|(other_id, template)| (other_id, template.name.clone()))
+            .collect::<Vec<_>>();
+        let on_submit = ctx.link().callback(
+            move |(name, stacked, attributes): (String, Vec<TemplateId>, TripAttributes)| {
+                let mut stack = vec![template_id];
+                stack.extend(stacked);
+                Msg::CreateTrip(stack, name, attributes)
+            });
+        html! {
+          <TripCreationForm
+              template_name={self.template_name(template_id)}
+              {other_templates}
+              {on_submit}
+              on_cancel={ctx.link().callback(|_: ()| Msg::CloseCreationForm)}/>
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+
+use crate::types::{Season, TemplateId, TripAttributes};
+
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub template_name: String,
+    // Templates that can be stacked on top of the main one.
+    #[prop_or_default]
+    pub other_templates: Vec<(TemplateId, String)>,
+    // Emits the name, ids of the additionally stacked templates and the attributes.
+    pub on_submit: Callback<(String, Vec<TemplateId>, TripAttributes)>,
+    pub on_cancel: Callback<()>,
+}
+
+// Modal asking for the trip's name, other templates to stack on top of the main one and the
+// attributes that decide which of the templates' items are included.
+#[function_component(TripCreationForm)]
+pub fn trip_creation_form(props: &Props) -> Html {
+    let name = use_state(|| props.template_name.clone());
+    let tags = use_state(String::new);
+    let destination = use_state(String::new);
+    let days = use_state(|| "1".to_string());
+    let travellers = use_state(|| "1".to_string());
+    let season = use_state(String::new);
+    let stacked = use_state(Vec::<TemplateId>::new);
+    let error = use_state(|| Option::<String>::None);
+
+    let text_input = |state: UseStateHandle<String>| {
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            state.set(input.value());
+        })
+    };
+    let on_season_change = {
+        let season = season.clone();
+        Callback::from(move |e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            season.set(select.value());
+        })
+    };
+    let on_submit = {
+        let (name, tags, destination, days, travellers, season, stacked, error) = (
+            name.clone(), tags.clone(), destination.clone(), days.clone(), travellers.clone(),
+            season.clone(), stacked.clone(), error.clone());
+        let on_submit = props.on_submit.clone();
+        Callback::from(move |e: FocusEvent| {
+            e.prevent_default();
+            let (days, travellers) = match (parse_positive(&days), parse_positive(&travellers)) {
+                (Some(days), Some(travellers)) => (days, travellers),
+                _ => {
+                    error.set(Some(
+                        "Numbers of days and travellers have to be positive numbers.".to_string()));
+                    return;
+                },
+            };
+            let attributes = TripAttributes {
+                tags: parse_tags(&tags),
+                destination_country: non_empty(&destination),
+                days,
+                travellers,
+                season: Season::ALL.iter().copied()
+                    .find(|candidate| candidate.to_string() == *season),
+            };
+            on_submit.emit(((*name).clone(), (*stacked).clone(), attributes));
+        })
+    };
+    let on_cancel = {
+        let on_cancel = props.on_cancel.clone();
+        Callback::from(move |_: MouseEvent| on_cancel.emit(()))
+    };
+
+    let stacked_checkboxes = props.other_templates.iter().map(|(template_id, template_name)| {
+        let template_id = *template_id;
+        let on_toggle = {
+            let stacked = stacked.clone();
+            Callback::from(move |_: Event| {
+                let mut new_stacked = (*stacked).clone();
+                if let Some(position) = new_stacked.iter().position(|id| *id == template_id) {
+                    new_stacked.remove(position);
+                } else {
+                    new_stacked.push(template_id);
+                }
+                stacked.set(new_stacked);
+            })
+        };
+        html! {
+          <label class="b-checkbox checkbox" key={template_id}>
+            <input type="checkbox" checked={stacked.contains(&template_id)} onchange={on_toggle}/>
+            <span class="check"></span>
+            <span class="control-label">{ template_name }</span>
+          </label>
+        }
+    }).collect::<Html>();
+
+    let season_options = Season::ALL.iter().map(|season| {
+        html! { <option value={season.to_string()}>{ season.to_string() }</option> }
+    }).collect::<Html>();
+
+    html! {
+      <div class="modal is-active">
+        <div class="modal-background" onclick={on_cancel.clone()}></div>
+        <form class="modal-card" onsubmit={on_submit}>
+          <header class="modal-card-head">
+            <p class="modal-card-title">{ format!("New trip from \"{}\"", props.template_name) }</p>
+          </header>
+          <section class="modal-card-body">
+            <div class="field">
+              <label class="label">{ "Name" }</label>
+              <div class="control">
+                <input class="input" type="text" value={(*name).clone()} oninput={text_input(name.clone())}/>
+              </div>
+            </div>
+            if !props.other_templates.is_empty() {
+              <div class="field">
+                <label class="label">{ "Also include" }</label>
+                <div class="control">{ stacked_checkboxes }</div>
+              </div>
+            }
+            <div class="field">
+              <label class="label">{ "Tags" }</label>
+              <div class="control">
+                <input class="input" type="text" placeholder="beach, business"
+                    value={(*tags).clone()} oninput={text_input(tags.clone())}/>
+              </div>
+              <p class="help">{ "Separated with commas." }</p>
+            </div>
+            <div class="field">
+              <label class="label">{ "Destination country" }</label>
+              <div class="control">
+                <input class="input" type="text"
+                    value={(*destination).clone()} oninput={text_input(destination.clone())}/>
+              </div>
+            </div>
+            <div class="field is-grouped">
+              <div class="control">
+                <label class="label">{ "Days" }</label>
+                <input class="input" type="number" min="1"
+                    value={(*days).clone()} oninput={text_input(days.clone())}/>
+              </div>
+              <div class="control">
+                <label class="label">{ "Travellers" }</label>
+                <input class="input" type="number" min="1"
+                    value={(*travellers).clone()} oninput={text_input(travellers.clone())}/>
+              </div>
+              <div class="control">
+                <label class="label">{ "Season" }</label>
+                <div class="select">
+                  <select onchange={on_season_change}>
+                    <option value="" selected={season.is_empty()}>{ "Any" }</option>
+                    { season_options }
+                  </select>
+                </div>
+              </div>
+            </div>
+            if let Some(error) = &*error {
+              <p class="help is-danger">{ error }</p>
+            }
+          </section>
+          <footer class="modal-card-foot">
+            <button class="button is-link" type="submit">{ "Create trip" }</button>
+            <button class="button" type="button" onclick={on_cancel}>{ "Cancel" }</button>
+          </footer>
+        </form>
+      </div>
+    }
+}
+
+fn parse_tags(text: &str) -> Vec<String> {
+    text.split(',')
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+fn parse_positive(text: &str) -> Option<u32> {
+    text.trim().parse::<u32>().ok().filter(|value| *value > 0)
+}
+
+fn non_empty(text: &str) -> Option<String> {
+    let text = text.trim();
+    if text.is_empty() { None } else { Some(text.to_string()) }
+}
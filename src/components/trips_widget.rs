@@ -1,15 +1,10 @@
 use yew::prelude::*;
 use yew_router::prelude::*;
 
-use crate::types::{TripId};
+use crate::components::pagination::{pages_needed, Pagination};
+use crate::types::{Trip, TripId};
 use crate::routes::{Route};
 
-#[derive(Clone, PartialEq)]
-pub struct Trip {
-    pub name: String,
-    pub id: TripId,
-}
-
 pub struct TripsWidget {
     current_page: u32,
 }
@@ -28,21 +23,21 @@ pub enum Msg {
 // This all should be from DB.
 fn num_of_trips() -> u32 { 151 }
 fn trips_per_page() -> u32 { 10 }
-fn get_trips_in_range(range: &Range) -> Vec<Trip> {
-    let mut trips = Vec::<Trip>::new();
+fn get_trips_in_range(range: &Range) -> Vec<(TripId, Trip)> {
+    let mut trips = Vec::<(TripId, Trip)>::new();
     let end = std::cmp::min(range.end, num_of_trips());
     for i in range.start..end {
         trips.push(
-            Trip { name: format!("Trip {}", i), id: TripId::from(i)}
+            (TripId::from(i), Trip::new(&format!("Trip {}", i), &"FakeLocalUser".to_string()))
         );
     }
     return trips;
 }
 
-fn wrap_trips(trips: &[Trip]) -> Html {
-    trips.iter().map(|trip| {
+fn wrap_trips(trips: &[(TripId, Trip)]) -> Html {
+    trips.iter().map(|(id, trip)| {
         html! {
-            <Link<Route> to={Route::Trip {id: trip.id}} classes={classes!("panel-block")} key={trip.id}>
+            <Link<Route> to={Route::Trip {id: *id}} classes={classes!("panel-block")} key={*id}>
                 { trip.name.clone() }
             </Link<Route>>
         }
@@ -95,7 +90,10 @@ impl Component for TripsWidget {
 
               { wrap_trips(&get_trips_in_range(&self.range_for_page(self.current_page))) }
 
-              { self.gen_pagination_controls(ctx) }
+              <Pagination
+                  current_page={self.current_page}
+                  last_page={self.last_page()}
+                  on_page_change={ctx.link().callback(Msg::GoToPage)}/>
 
             </div>
         }
@@ -109,8 +107,7 @@ impl TripsWidget {
     }
 
     fn last_page(&self) -> u32 {
-        num_of_trips() / trips_per_page()
-            + if num_of_trips() % trips_per_page() > 0 { 1 } else { 0 }
+        pages_needed(num_of_trips(), trips_per_page())
     }
 
     fn range_for_page(&self, page: u32) -> Range {
@@ -119,62 +116,4 @@ impl TripsWidget {
             end: page * trips_per_page(),
         }
     }
-
-    fn gen_pagination_controls(&self, ctx: &Context<Self>) -> Html {
-        let first_page = self.first_page();
-        let last_page = self.last_page();
-
-        let mut previous_classes = classes!("pagination-previous");
-        let mut next_classes = classes!("pagination-next");
-        let mut first_page_classes = classes!("pagination-link");
-        let mut last_page_classes = classes!("pagination-link");
-
-        if self.current_page == first_page {
-            previous_classes.push("is-disabled");
-            first_page_classes.push("is-disabled");
-        }
-        if self.current_page == last_page {
-            next_classes.push("is-disabled");
-            last_page_classes.push("is-disabled");
-        }
-
-        let link = ctx.link();
-        let create_page_callback = |dest_page: u32| {
-            link.callback(move |_: MouseEvent| Msg::GoToPage(dest_page))
-        };
-
-        html! {
-          <nav class="panel-block pagination is-centered" role="navigation" aria-label="pagination">
-            <button class={previous_classes} onclick={create_page_callback(self.current_page - 1)}>
-              { "Previous" }
-            </button>
-            <button class={next_classes} onclick={create_page_callback(self.current_page + 1)}>
-              { "Next page" }
-            </button>
-            <ul class="pagination-list">
-              if self.current_page > first_page {
-                <li>
-                  <button class={first_page_classes} aria-label="Goto page 1" onclick={create_page_callback(first_page)}>
-                    { first_page }
-                  </button>
-                </li>
-                <li><span class="pagination-ellipsis">{ "…" }</span></li>
-              }
-              <li>
-                <button class="pagination-link is-current" aria-label={format!("Page {}", self.current_page)} aria-current="page">
-                  { self.current_page }
-                </button>
-              </li>
-              if self.current_page < last_page {
-               <li><span class="pagination-ellipsis">{ "…" }</span></li>
-               <li>
-                 <button class={last_page_classes} aria-label={format!("Goto page {}", last_page)} onclick={create_page_callback(last_page)}>
-                   { last_page }
-                 </button>
-               </li>
-              }
-            </ul>
-          </nav>
-        }
-    }
 }
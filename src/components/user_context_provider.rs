@@ -1,39 +1,394 @@
+use std::future::Future;
+
 use yew::prelude::*;
 
-use crate::types::{UserAuthStatus, UserInfo};
+use crate::config::{Config};
+use crate::firebase::auth::{FirebaseAuthError, ProfileAttribute, ProfileUpdate};
+use crate::firebase::auth_backend::{self, AuthBackend};
+use crate::session::{self, StoredSession};
+use crate::store::SessionHooks;
+use crate::types::{UserAuthStatus, UserId, UserInfo};
 
 #[derive(Properties, Clone, PartialEq)]
 pub struct Props {
     pub children: Children,
 }
 
-pub struct UserContextProvider;
+pub type AuthResult = Result<(), FirebaseAuthError>;
+
+// Things components can ask the provider to do with the user's authentication. The result is
+// reported through on_done, after UserAuthStatus in the context has been updated.
+pub enum AuthAction {
+    SignIn { email: String, password: String, on_done: Callback<AuthResult> },
+    SignUp { email: String, password: String, on_done: Callback<AuthResult> },
+    // Signs in as a new anonymous user, so that the app can be tried without registering.
+    SignInAsGuest { on_done: Callback<AuthResult> },
+    // Upgrades the current guest to a regular user. The user id, and so all their data, stays the
+    // same.
+    LinkEmailPassword { email: String, password: String, on_done: Callback<AuthResult> },
+    // Exchanges the refresh token for a fresh id token.
+    Refresh { on_done: Callback<AuthResult> },
+    // Changes the current user's account.
+    UpdateAccount { change: AccountChange, on_done: Callback<AuthResult> },
+    // Sends an email with a link that verifies the current user's email.
+    SendVerificationEmail { on_done: Callback<AuthResult> },
+    // Tokens of the current user were refreshed elsewhere (e.g. by Store, through SessionHooks) and
+    // should replace the ones in the context.
+    UpdateTokens(UserInfo),
+    SignOut,
+    // The refresh token of the given user was found to no longer work elsewhere (e.g. by Store,
+    // through SessionHooks).
+    // Signs them out, unless somebody else signed in since.
+    SessionExpired(UserId),
+}
+
+pub enum AccountChange {
+    Profile(ProfileUpdate),
+    Email(String),
+    Password(String),
+}
+
+// Provided next to UserAuthStatus, to dispatch AuthActions to the provider.
+pub type AuthDispatcher = Callback<AuthAction>;
+
+pub enum Msg {
+    Action(AuthAction),
+    Finished(Outcome),
+}
+
+pub struct Outcome {
+    // Session the action was started in.
+    session: u64,
+    // Whether the action relied on the current user's refresh token, so that failing because of
+    // it means the session is over.
+    uses_session: bool,
+    // The auth backend after the action finished.
+    auth: Box<dyn AuthBackend>,
+    result: Result<UserInfo, FirebaseAuthError>,
+    on_done: Callback<AuthResult>,
+}
+
+// Owns the auth backend of the app (see auth_backend::from_config). Provides the current
+// UserAuthStatus, derived from its tokens, and an AuthDispatcher to sign in, sign out etc. The
+// session is persisted (see session) and restored on startup.
+pub struct UserContextProvider {
+    // None only if there is no config to create it from.
+    auth: Option<Box<dyn AuthBackend>>,
+    user_auth: UserAuthStatus,
+    // Incremented on every sign out, so that actions that were still running at that point do not
+    // sign the user back in when they finish.
+    session: u64,
+    // Created once, so that the context does not change with every render.
+    dispatcher: AuthDispatcher,
+    session_hooks: SessionHooks,
+}
 
 impl Component for UserContextProvider {
-    type Message = ();
+    type Message = Msg;
     type Properties = Props;
 
-    fn create(_ctx: &Context<Self>) -> Self {
-        Self
+    fn create(ctx: &Context<Self>) -> Self {
+        let auth = ctx.link().context::<Config>(Callback::noop())
+            .and_then(|(config, _)| {
+                auth_backend::from_config(&config)
+                    .map_err(|err| log::error!("Failed to create the auth backend: {}", err))
+                    .ok()
+            });
+        let mut provider = Self {
+            auth,
+            user_auth: UserAuthStatus::new_empty(),
+            session: 0,
+            dispatcher: ctx.link().callback(Msg::Action),
+            session_hooks: SessionHooks {
+                on_tokens_refreshed: ctx.link().callback(|user_info|
+                    Msg::Action(AuthAction::UpdateTokens(user_info))),
+                on_session_expired: ctx.link().callback(|user_id|
+                    Msg::Action(AuthAction::SessionExpired(user_id))),
+            },
+        };
+        if let Some(stored) = session::load() {
+            provider.user_auth = UserAuthStatus::new_restoring();
+            provider.restore(ctx, stored);
+        }
+        provider
     }
 
-    fn update(&mut self, _ctx: &Context<Self>, _msg: Self::Message) -> bool {
-        false
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::Action(AuthAction::SignIn { email, password, on_done }) => {
+                self.start(ctx, on_done, false, |mut auth, _| async move {
+                    let result = match auth.sign_in(&email, &password).await {
+                        Ok(_) => signed_in_user(auth.as_mut()).await,
+                        Err(err) => Err(err),
+                    };
+                    (auth, result)
+                });
+                false
+            },
+            Msg::Action(AuthAction::SignUp { email, password, on_done }) => {
+                self.start(ctx, on_done, false, |mut auth, _| async move {
+                    let result = match auth.sign_up(&email, &password).await {
+                        Ok(_) => signed_in_user(auth.as_mut()).await,
+                        Err(err) => Err(err),
+                    };
+                    (auth, result)
+                });
+                false
+            },
+            Msg::Action(AuthAction::SignInAsGuest { on_done }) => {
+                self.start(ctx, on_done, false, |mut auth, _| async move {
+                    let result = match auth.sign_in_anonymously().await {
+                        Ok(_) => user_info_from(auth.as_ref(), None),
+                        Err(err) => Err(err),
+                    };
+                    (auth, result)
+                });
+                false
+            },
+            Msg::Action(AuthAction::LinkEmailPassword { email, password, on_done }) => {
+                self.start(ctx, on_done, true, |mut auth, current_user| async move {
+                    let result = match current_user {
+                        Some(user) if user.is_anonymous => {
+                            match auth.link_email_password(&email, &password).await {
+                                Ok(()) => user_info_from(auth.as_ref(), None),
+                                Err(err) => Err(err),
+                            }
+                        },
+                        Some(_) => Err(FirebaseAuthError::Internal(
+                            "Only guests can link an email and password.".to_string())),
+                        None => Err(FirebaseAuthError::AuthDataMissing),
+                    };
+                    (auth, result)
+                });
+                false
+            },
+            Msg::Action(AuthAction::Refresh { on_done }) => {
+                self.start(ctx, on_done, true, |mut auth, current_user| async move {
+                    let result = match current_user {
+                        Some(user) => match auth.refresh_id_token().await {
+                            Ok(()) => user_info_from(auth.as_ref(), Some(user.display_name)),
+                            Err(err) => Err(err),
+                        },
+                        None => Err(FirebaseAuthError::AuthDataMissing),
+                    };
+                    (auth, result)
+                });
+                false
+            },
+            Msg::Action(AuthAction::UpdateAccount { change, on_done }) => {
+                self.start(ctx, on_done, true, |mut auth, current_user| async move {
+                    let user = match current_user {
+                        Some(user) => user,
+                        None => return (auth, Err(FirebaseAuthError::AuthDataMissing)),
+                    };
+                    // Only the profile update changes the display name, and it is not part of the
+                    // id token.
+                    let display_name = match &change {
+                        AccountChange::Profile(update) if update.delete.contains(&ProfileAttribute::DisplayName) =>
+                            None,
+                        AccountChange::Profile(ProfileUpdate { display_name: Some(display_name), .. }) =>
+                            Some(display_name.clone()),
+                        // Display name that fell back to the email follows the email.
+                        _ if user.display_name == user.email => None,
+                        _ => Some(user.display_name),
+                    };
+                    let changed = match &change {
+                        AccountChange::Profile(update) => auth.update_profile(update).await,
+                        AccountChange::Email(email) => auth.change_email(email).await,
+                        AccountChange::Password(password) => auth.change_password(password).await,
+                    };
+                    let result = match changed {
+                        Ok(()) => user_info_from(auth.as_ref(), display_name),
+                        Err(err) => Err(err),
+                    };
+                    (auth, result)
+                });
+                false
+            },
+            Msg::Action(AuthAction::SendVerificationEmail { on_done }) => {
+                self.start(ctx, on_done, true, |mut auth, current_user| async move {
+                    let result = match current_user {
+                        Some(user) => match auth.send_email_verification().await {
+                            // The id token might have been refreshed on the way.
+                            Ok(()) => user_info_from(auth.as_ref(), Some(user.display_name)),
+                            Err(err) => Err(err),
+                        },
+                        None => Err(FirebaseAuthError::AuthDataMissing),
+                    };
+                    (auth, result)
+                });
+                false
+            },
+            Msg::Action(AuthAction::UpdateTokens(user_info)) => {
+                let current_user = self.user_auth.user_info.as_ref().map(|user| &user.user_id);
+                if current_user != Some(&user_info.user_id) {
+                    return false;
+                }
+                if let Some(auth) = self.auth.as_mut() {
+                    auth.set_refresh_token(Some(user_info.refresh_token.clone()));
+                    auth.set_id_token(user_info.id_token.clone(), user_info.id_token_expires_at);
+                }
+                session::save(&StoredSession::from(&user_info));
+                self.user_auth = UserAuthStatus::new_authenticated(user_info);
+                true
+            },
+            Msg::Action(AuthAction::SignOut) => {
+                self.sign_out();
+                true
+            },
+            Msg::Action(AuthAction::SessionExpired(user_id)) => {
+                let current_user = self.user_auth.user_info.as_ref().map(|user| &user.user_id);
+                if current_user != Some(&user_id) {
+                    return false;
+                }
+                log::warn!("Session of user {} expired", user_id);
+                self.sign_out();
+                true
+            },
+            Msg::Finished(outcome) if outcome.session != self.session => {
+                outcome.on_done.emit(outcome.result.and(Err(FirebaseAuthError::AuthDataMissing)));
+                false
+            },
+            Msg::Finished(Outcome { auth, result: Ok(user_info), on_done, .. }) => {
+                session::save(&StoredSession::from(&user_info));
+                self.auth = Some(auth);
+                self.user_auth = UserAuthStatus::new_authenticated(user_info);
+                on_done.emit(Ok(()));
+                true
+            },
+            Msg::Finished(Outcome { uses_session, result: Err(err), on_done, .. }) => {
+                log::warn!("Authentication action failed: {}", err);
+                // The user has to log in again; the stored refresh token will not work either.
+                let session_over = uses_session && err.ends_session();
+                if session_over {
+                    self.sign_out();
+                }
+                // Restoring failed, if that is what it was; the user stays unauthenticated.
+                let was_restoring = std::mem::replace(&mut self.user_auth.restoring, false);
+                on_done.emit(Err(err));
+                session_over || was_restoring
+            },
+        }
     }
 
     fn view(&self, ctx: &Context<Self>) -> Html {
-        let user_auth = UserAuthStatus {
-            user_info: Some(UserInfo {
-                display_name: "FakeLocalUser".to_string(),
-                email: "fakeLocal@email.com".to_string(),
-                id_token: "fakeLocalIdToken".to_string(),
-                refresh_token: "fakeLocalRefreshToken".to_string(),
-            }),
-        };
         html!{
-            <ContextProvider<UserAuthStatus> context={user_auth}>
-              { for ctx.props().children.iter() }
-            </ContextProvider<UserAuthStatus>>
+            <ContextProvider<AuthDispatcher> context={self.dispatcher.clone()}>
+              <ContextProvider<SessionHooks> context={self.session_hooks.clone()}>
+                <ContextProvider<UserAuthStatus> context={self.user_auth.clone()}>
+                  { for ctx.props().children.iter() }
+                </ContextProvider<UserAuthStatus>>
+              </ContextProvider<SessionHooks>>
+            </ContextProvider<AuthDispatcher>>
         }
     }
 }
+
+impl UserContextProvider {
+
+    fn sign_out(&mut self) {
+        if let Some(auth) = self.auth.as_mut() {
+            auth.sign_out();
+        }
+        session::clear();
+        self.user_auth = UserAuthStatus::new_empty();
+        self.session += 1;
+    }
+
+    // Gets a fresh id token for the session stored by an earlier visit.
+    fn restore(&self, ctx: &Context<Self>, stored: StoredSession) {
+        self.start(ctx, Callback::noop(), true, |mut auth, _| async move {
+            auth.set_refresh_token(Some(stored.refresh_token));
+            let result = match auth.refresh_id_token().await {
+                Ok(()) => user_info_from(auth.as_ref(), Some(stored.display_name)),
+                Err(err) => Err(err),
+            };
+            (auth, result)
+        });
+    }
+
+    // Runs the action on a copy of the auth backend, which comes back in Msg::Finished together with
+    // the user it ended up authenticated as.
+    fn start<F, Fut>(
+        &self, ctx: &Context<Self>, on_done: Callback<AuthResult>, uses_session: bool, action: F)
+        where F: FnOnce(Box<dyn AuthBackend>, Option<UserInfo>) -> Fut,
+              Fut: Future<Output = (Box<dyn AuthBackend>, Result<UserInfo, FirebaseAuthError>)> + 'static
+    {
+        let auth = match &self.auth {
+            Some(auth) => auth.clone(),
+            None => {
+                on_done.emit(Err(FirebaseAuthError::Internal("No config to authenticate with.".to_string())));
+                return;
+            },
+        };
+        let session = self.session;
+        let future = action(auth, self.user_auth.user_info.clone());
+        ctx.link().send_future(async move {
+            let (auth, result) = future.await;
+            Msg::Finished(Outcome { session, uses_session, auth, result, on_done })
+        });
+    }
+}
+
+async fn signed_in_user(auth: &mut dyn AuthBackend) -> Result<UserInfo, FirebaseAuthError> {
+    let display_name = auth.get_account_info().await?.display_name;
+    user_info_from(auth, display_name)
+}
+
+// UserInfo based on the tokens the backend currently holds.
+fn user_info_from(auth: &dyn AuthBackend, display_name: Option<String>)
+    -> Result<UserInfo, FirebaseAuthError>
+{
+    let claims = auth.claims()?;
+    let is_anonymous = claims.is_anonymous();
+    let email = claims.email.unwrap_or_default();
+    // New users have no display name yet; the email is the next best thing to show.
+    let display_name = match display_name {
+        Some(display_name) if !display_name.is_empty() => display_name,
+        _ if is_anonymous => "Guest".to_string(),
+        _ => email.clone(),
+    };
+    Ok(UserInfo {
+        user_id: claims.user_id,
+        display_name,
+        email,
+        email_verified: claims.email_verified,
+        is_anonymous,
+        id_token: auth.id_token().cloned().ok_or(FirebaseAuthError::AuthDataMissing)?,
+        id_token_expires_at: auth.id_token_expires_at().ok_or(FirebaseAuthError::AuthDataMissing)?,
+        refresh_token: auth.refresh_token().cloned().ok_or(FirebaseAuthError::AuthDataMissing)?,
+    })
+}
+
+#[cfg(test)]
+mod user_context_provider_tests {
+    use super::*;
+    use crate::firebase::in_memory_auth::InMemoryAuth;
+
+    #[test]
+    fn test_signed_in_user() {
+        let mut auth = InMemoryAuth::new();
+        let user_id = tokio_test::block_on(auth.sign_up("user@example.com", "password")).unwrap();
+        let user_info = tokio_test::block_on(signed_in_user(&mut auth)).unwrap();
+        assert_eq!(user_info.user_id, user_id);
+        assert_eq!(user_info.display_name, "user@example.com");
+        assert!(!user_info.is_anonymous);
+
+        assert!(tokio_test::block_on(auth.update_profile(&ProfileUpdate {
+            display_name: Some("Name".to_string()),
+            ..ProfileUpdate::default()
+        })).is_ok());
+        let user_info = tokio_test::block_on(signed_in_user(&mut auth)).unwrap();
+        assert_eq!(user_info.display_name, "Name");
+    }
+
+    #[test]
+    fn test_signed_in_guest() {
+        let mut auth = InMemoryAuth::new();
+        assert!(tokio_test::block_on(auth.sign_in_anonymously()).is_ok());
+        let user_info = tokio_test::block_on(signed_in_user(&mut auth)).unwrap();
+        assert_eq!(user_info.display_name, "Guest");
+        assert_eq!(user_info.email, "");
+        assert!(user_info.is_anonymous);
+    }
+}
@@ -2,7 +2,16 @@ use serde::{Deserialize};
 
 #[derive(Deserialize, Debug, Clone, PartialEq)]
 pub struct Config {
+    pub api_key: String,
     pub auth_host: String,
+    pub database_host: String,
+    pub database_namespace: String,
+    // Authenticates against an in-memory fake (see InMemoryAuth) instead of Firebase Auth, for
+    // working on the app without any emulator. Accounts last until the page is reloaded.
+    // Only the auth pages (logging in, the profile) work this way: the database would reject the
+    // fake's unsigned id tokens, so Store fails every database request with StoreError::OfflineAuth.
+    #[serde(default)]
+    pub offline_auth: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -2,33 +2,43 @@
 #![allow(dead_code)]
 #![allow(unused_variables)]
 
+use std::future::Future;
+
 use url::Url;
 use serde::{Deserialize, Serialize};
 
+use super::auth_backend;
+use super::id_token::{decode_claims, IdTokenClaims};
+use super::retry::{Idempotency, RetryPolicy};
 use super::types::*;
+use crate::types::{now, Timestamp};
 
-pub use super::auth_errors::FirebaseAuthError;
+pub use super::auth_errors::{ErrorClass, FirebaseApiError, FirebaseAuthError, TransportError};
 
 pub type UserId = String;
 
 static IDENTITYTOOLKIT_URL_SUFFIX: &'static str = "identitytoolkit.googleapis.com/v1/";
 static SECURETOKEN_URL_SUFFIX: &'static str = "securetoken.googleapis.com/v1/";
 static DEFAULT_URL_BASE: &'static str = "https://";
+// Id tokens are refreshed when they have less than this much time left, so that they do not expire
+// while a request is on its way.
+const REFRESH_MARGIN_MS: Timestamp = 5 * 60 * 1000;
+// Firebase refuses sensitive operations, such as deleting the account, when the user signed in
+// longer ago than this.
+const RECENT_SIGN_IN_MS: Timestamp = 5 * 60 * 1000;
 
 
 // Implemented most methods from https://firebase.google.com/docs/reference/rest/auth, ignoring
 // anything related to third party identity provider.
 #[derive(Clone, Debug)]
 pub struct FirebaseAuth {
-    // wasm version of reqwest's Client does not seem to do any connection pooling, since it should
-    // be handled by the browser. Still, this struct will try to use the same (potentially cloned
-    // since it is behind an Arc) client wherever possible.
-    client: reqwest::Client,
+    requester: Requester,
     identitytoolkit_endpoint: String,
     securetoken_endpoint: String,
 
     api_key: String,
     id_token: Option<String>,
+    id_token_expires_at: Option<Timestamp>,
     refresh_token: Option<String>,
 }
 
@@ -41,16 +51,100 @@ impl FirebaseAuth {
 
     pub fn new_custom_url_base(api_key: &str, url_base: &str) -> Result<Self> {
         Ok(Self {
-            client: reqwest::Client::new(),
+            requester: Requester {
+                client: reqwest::Client::new(),
+                retry_policy: RetryPolicy::default(),
+            },
             identitytoolkit_endpoint: format!("{}{}", url_base, IDENTITYTOOLKIT_URL_SUFFIX),
             securetoken_endpoint: format!("{}{}", url_base, SECURETOKEN_URL_SUFFIX),
 
             api_key: api_key.to_owned(),
             id_token: None,
+            id_token_expires_at: None,
             refresh_token: None,
         })
     }
 
+    // The id token of the currently authenticated user, if any. Other Firebase services (e.g.
+    // FirebaseDatabase) use it to authenticate their requests.
+    pub fn id_token(&self) -> Option<&String> {
+        self.id_token.as_ref()
+    }
+
+    // Claims about the user read from the id token, see id_token.
+    pub fn claims(&self) -> Result<IdTokenClaims> {
+        decode_claims(self.id_token.as_ref().ok_or(FirebaseAuthError::AuthDataMissing)?)
+    }
+
+    // When the id token stops being accepted, if there is one.
+    pub fn id_token_expires_at(&self) -> Option<Timestamp> {
+        self.id_token_expires_at
+    }
+
+    // The refresh token of the currently authenticated user, if any. Can be stored to restore the
+    // session later.
+    pub fn refresh_token(&self) -> Option<&String> {
+        self.refresh_token.as_ref()
+    }
+
+    // Restores a session from a refresh token stored earlier. Call refresh_id_token afterwards to
+    // get an id token.
+    pub fn set_refresh_token(&mut self, refresh_token: Option<String>) {
+        self.refresh_token = refresh_token;
+        self.id_token = None;
+        self.id_token_expires_at = None;
+    }
+
+    // Restores an id token obtained earlier, e.g. by another instance of FirebaseAuth. Should be
+    // accompanied by set_refresh_token, so that the id token can be refreshed once it expires.
+    pub fn set_id_token(&mut self, id_token: String, expires_at: Timestamp) {
+        self.id_token = Some(id_token);
+        self.id_token_expires_at = Some(expires_at);
+    }
+
+    // How requests failing with transient errors (network errors, Firebase asking to slow down)
+    // are retried. Only requests that can safely be repeated are retried, e.g. signing in but not
+    // signing up.
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.requester.retry_policy = retry_policy;
+    }
+
+    // Forgets the tokens of the current user. Firebase keeps no sessions, so there is nothing to
+    // tell the backend.
+    pub fn sign_out(&mut self) {
+        self.id_token = None;
+        self.id_token_expires_at = None;
+        self.refresh_token = None;
+    }
+
+    // Whether the id token is missing or about to expire, so that it should be refreshed before
+    // it is used.
+    pub fn id_token_expires_soon(&self) -> bool {
+        match self.id_token_expires_at {
+            Some(expires_at) if self.id_token.is_some() => now() + REFRESH_MARGIN_MS >= expires_at,
+            _ => true,
+        }
+    }
+
+    // Whether Firebase would reject sensitive operations with CredentialTooOldLoginAgain, so that
+    // the user can be asked to sign in again before starting anything that cannot be undone.
+    pub fn sign_in_too_old(&self) -> bool {
+        match self.claims() {
+            Ok(claims) => now() > claims.auth_time * 1000 + RECENT_SIGN_IN_MS,
+            Err(_) => true,
+        }
+    }
+
+    // Runs a request authenticated with the id token, see auth_backend::with_fresh_id_token.
+    pub async fn with_fresh_id_token<T, E, F, Fut>(&mut self, request: F)
+        -> std::result::Result<T, E>
+        where F: Fn(String) -> Fut,
+              Fut: Future<Output = std::result::Result<T, E>>,
+              E: From<FirebaseAuthError> + TokenRejection
+    {
+        auth_backend::with_fresh_id_token(self, request).await
+    }
+
     // Exchange a refresh token for an ID token
     // https://firebase.google.com/docs/reference/rest/auth#section-refresh-token
     pub async fn refresh_id_token(&mut self) -> Result<()> {
@@ -59,55 +153,107 @@ impl FirebaseAuth {
             &[("key", &self.api_key)]
         )?;
         let refresh_token = self.refresh_token.as_ref().ok_or(FirebaseAuthError::AuthDataMissing)?;
-        let request = self.client.post(url.as_str())
+        let request = |client: &reqwest::Client| client.post(url.as_str())
             .form(&[
                 ("grant_type", "refresh_token"),
                 ("refresh_token", &refresh_token),
             ]);
-        let response = make_request::<RefreshIdTokenResponse>(request, |err| {
+        let response = self.requester.send::<RefreshIdTokenResponse, _>(Idempotency::Idempotent, request, |err| {
             match err.message {
                 FirebaseErrorMessage::TokenExpired => FirebaseAuthError::TokenExpired,
                 FirebaseErrorMessage::UserDisabled => FirebaseAuthError::UserDisabled,
                 FirebaseErrorMessage::UserNotFound => FirebaseAuthError::UserNotFound,
-                FirebaseErrorMessage::ApiKeyInvalid => FirebaseAuthError::ApiKeyInvalid,
                 our_err @ (FirebaseErrorMessage::InvalidRefreshToken |
                     FirebaseErrorMessage::InvalidGrantType |
                     FirebaseErrorMessage::MissingRefreshToken) =>
                     FirebaseAuthError::Internal(format!("{}", our_err)),
-                unexpected_err =>
-                    FirebaseAuthError::FirebaseUnexpectedError(format!("{}", unexpected_err)),
+                _ => FirebaseAuthError::UnexpectedResponse(err.into()),
             }
         }).await?;
-        self.refresh_token = Some(response.refresh_token);
-        self.id_token = Some(response.id_token);
-        Ok(())
+        self.set_tokens(response.id_token, response.refresh_token, &response.expires_in)
     }
 
     // Sign up with email / password
     // https://firebase.google.com/docs/reference/rest/auth#section-create-email-password
-    pub async fn sign_up(&mut self, email: &str, password: &str) -> Result<()> {
+    pub async fn sign_up(&mut self, email: &str, password: &str) -> Result<UserId> {
         let url = Url::parse_with_params(
             &format!("{}{}", self.identitytoolkit_endpoint, "accounts:signUp"),
             &[("key", &self.api_key)]
         )?;
-        let request = self.client.post(url.as_str())
+        let request = |client: &reqwest::Client| client.post(url.as_str())
             .json(&SignUpRequest {
                 email: email.to_string(),
                 password: password.to_string(),
                 return_secure_token: true,
             });
-        let response = make_request::<SignUpResponse>(request, |err| {
+        let response = self.requester.send::<SignUpResponse, _>(Idempotency::NotIdempotent, request, |err| {
             match err.message {
                 FirebaseErrorMessage::EmailExists => FirebaseAuthError::EmailExists,
+                FirebaseErrorMessage::InvalidEmail => FirebaseAuthError::InvalidEmail,
+                FirebaseErrorMessage::WeakPassword => FirebaseAuthError::WeakPassword,
                 FirebaseErrorMessage::OperationNotAllowed => FirebaseAuthError::OperationNotAllowed,
                 FirebaseErrorMessage::TooManyAttemptsTryLater => FirebaseAuthError::TooManyAttemptsTryLater,
-                unexpected_err =>
-                    FirebaseAuthError::FirebaseUnexpectedError(format!("{}", unexpected_err)),
+                _ => FirebaseAuthError::UnexpectedResponse(err.into()),
             }
         }).await?;
-        self.refresh_token = Some(response.refresh_token);
-        self.id_token = Some(response.id_token);
-        Ok(())
+        self.set_tokens(response.id_token, response.refresh_token, &response.expires_in)?;
+        Ok(response.local_id)
+    }
+
+    // Sign in anonymously. The anonymous user is a regular user with an id, just without an email
+    // and password, which can be linked to it later (see link_email_password).
+    // https://firebase.google.com/docs/reference/rest/auth#section-sign-in-anonymously
+    pub async fn sign_in_anonymously(&mut self) -> Result<UserId> {
+        let url = Url::parse_with_params(
+            &format!("{}{}", self.identitytoolkit_endpoint, "accounts:signUp"),
+            &[("key", &self.api_key)]
+        )?;
+        let request = |client: &reqwest::Client| client.post(url.as_str())
+            .json(&SignInAnonymouslyRequest {
+                return_secure_token: true,
+            });
+        let response = self.requester.send::<SignUpResponse, _>(Idempotency::NotIdempotent, request, |err| {
+            match err.message {
+                FirebaseErrorMessage::OperationNotAllowed => FirebaseAuthError::OperationNotAllowed,
+                FirebaseErrorMessage::TooManyAttemptsTryLater => FirebaseAuthError::TooManyAttemptsTryLater,
+                _ => FirebaseAuthError::UnexpectedResponse(err.into()),
+            }
+        }).await?;
+        self.set_tokens(response.id_token, response.refresh_token, &response.expires_in)?;
+        Ok(response.local_id)
+    }
+
+    // Link with email / password. Turns the current anonymous user into a regular one, keeping
+    // their user id.
+    // https://firebase.google.com/docs/reference/rest/auth#section-link-with-email-password
+    pub async fn link_email_password(&mut self, email: &str, password: &str) -> Result<()> {
+        let url = Url::parse_with_params(
+            &format!("{}{}", self.identitytoolkit_endpoint, "accounts:update"),
+            &[("key", &self.api_key)]
+        )?;
+        let requester = self.requester.clone();
+        let response = self.with_fresh_id_token(|id_token| {
+            let url = url.as_str();
+            let body = LinkEmailPasswordRequest {
+                id_token,
+                email: email.to_string(),
+                password: password.to_string(),
+                return_secure_token: true,
+            };
+            let request = move |client: &reqwest::Client| client.post(url).json(&body);
+            requester.send::<LinkEmailPasswordResponse, _>(Idempotency::NotIdempotent, request, |err| {
+                match err.message {
+                    FirebaseErrorMessage::CredentialTooOldLoginAgain =>
+                        FirebaseAuthError::CredentialTooOldLoginAgain,
+                    FirebaseErrorMessage::InvalidIdToken => FirebaseAuthError::InvalidIdToken,
+                    FirebaseErrorMessage::EmailExists => FirebaseAuthError::EmailExists,
+                    FirebaseErrorMessage::InvalidEmail => FirebaseAuthError::InvalidEmail,
+                    FirebaseErrorMessage::WeakPassword => FirebaseAuthError::WeakPassword,
+                    _ => FirebaseAuthError::UnexpectedResponse(err.into()),
+                }
+            })
+        }).await?;
+        self.set_tokens(response.id_token, response.refresh_token, &response.expires_in)
     }
 
     // Sign in with email / password
@@ -117,23 +263,24 @@ impl FirebaseAuth {
             &format!("{}{}", self.identitytoolkit_endpoint, "accounts:signInWithPassword"),
             &[("key", &self.api_key)]
         )?;
-        let request = self.client.post(url.as_str())
+        let request = |client: &reqwest::Client| client.post(url.as_str())
             .json(&SignInRequest {
                 email: email.to_string(),
                 password: password.to_string(),
                 return_secure_token: true,
             });
-        let response = make_request::<SignInResponse>(request, |err| {
+        // Not retried, since every retry would be one more password attempt against an account
+        // Firebase may already be protecting with TOO_MANY_ATTEMPTS_TRY_LATER.
+        let response = self.requester.send::<SignInResponse, _>(Idempotency::NotIdempotent, request, |err| {
             match err.message {
                 FirebaseErrorMessage::EmailNotFound => FirebaseAuthError::EmailNotFound,
                 FirebaseErrorMessage::InvalidPassword => FirebaseAuthError::InvalidPassword,
                 FirebaseErrorMessage::UserDisabled => FirebaseAuthError::UserDisabled,
-                unexpected_err =>
-                    FirebaseAuthError::FirebaseUnexpectedError(format!("{}", unexpected_err)),
+                FirebaseErrorMessage::TooManyAttemptsTryLater => FirebaseAuthError::TooManyAttemptsTryLater,
+                _ => FirebaseAuthError::UnexpectedResponse(err.into()),
             }
         }).await?;
-        self.refresh_token = Some(response.refresh_token);
-        self.id_token = Some(response.id_token);
+        self.set_tokens(response.id_token, response.refresh_token, &response.expires_in)?;
         Ok(response.local_id)
     }
 
@@ -144,24 +291,27 @@ impl FirebaseAuth {
             &format!("{}{}", self.identitytoolkit_endpoint, "accounts:update"),
             &[("key", &self.api_key)]
         )?;
-        let id_token = self.id_token.as_ref().ok_or(FirebaseAuthError::AuthDataMissing)?;
-        let request = self.client.post(url.as_str())
-            .json(&ChangeEmailRequest{
-                id_token: id_token.to_string(),
+        let requester = self.requester.clone();
+        let response = self.with_fresh_id_token(|id_token| {
+            let url = url.as_str();
+            let body = ChangeEmailRequest {
+                id_token,
                 email: new_email.to_string(),
                 return_secure_token: true,
-            });
-        let response = make_request::<ChangeEmailResponse>(request, |err| {
-            match err.message {
-                FirebaseErrorMessage::EmailExists => FirebaseAuthError::EmailExists,
-                FirebaseErrorMessage::InvalidIdToken => FirebaseAuthError::InvalidIdToken,
-                unexpected_err =>
-                    FirebaseAuthError::FirebaseUnexpectedError(format!("{}", unexpected_err)),
-            }
+            };
+            let request = move |client: &reqwest::Client| client.post(url).json(&body);
+            requester.send::<ChangeEmailResponse, _>(Idempotency::NotIdempotent, request, |err| {
+                match err.message {
+                    FirebaseErrorMessage::CredentialTooOldLoginAgain =>
+                        FirebaseAuthError::CredentialTooOldLoginAgain,
+                    FirebaseErrorMessage::EmailExists => FirebaseAuthError::EmailExists,
+                    FirebaseErrorMessage::InvalidEmail => FirebaseAuthError::InvalidEmail,
+                    FirebaseErrorMessage::InvalidIdToken => FirebaseAuthError::InvalidIdToken,
+                    _ => FirebaseAuthError::UnexpectedResponse(err.into()),
+                }
+            })
         }).await?;
-        self.id_token = Some(response.id_token);
-        self.refresh_token = Some(response.refresh_token);
-        Ok(())
+        self.set_tokens(response.id_token, response.refresh_token, &response.expires_in)
     }
 
     // Change password
@@ -171,83 +321,335 @@ impl FirebaseAuth {
             &format!("{}{}", self.identitytoolkit_endpoint, "accounts:update"),
             &[("key", &self.api_key)]
         )?;
-        let id_token = self.id_token.as_ref().ok_or(FirebaseAuthError::AuthDataMissing)?;
-        let request = self.client.post(url.as_str())
-            .json(&ChangePasswordRequest{
-                id_token: id_token.to_string(),
+        let requester = self.requester.clone();
+        let response = self.with_fresh_id_token(|id_token| {
+            let url = url.as_str();
+            let body = ChangePasswordRequest {
+                id_token,
                 password: new_password.to_string(),
                 return_secure_token: true,
+            };
+            let request = move |client: &reqwest::Client| client.post(url).json(&body);
+            requester.send::<ChangePasswordResponse, _>(Idempotency::NotIdempotent, request, |err| {
+                match err.message {
+                    FirebaseErrorMessage::CredentialTooOldLoginAgain =>
+                        FirebaseAuthError::CredentialTooOldLoginAgain,
+                    FirebaseErrorMessage::InvalidIdToken => FirebaseAuthError::InvalidIdToken,
+                    FirebaseErrorMessage::WeakPassword => FirebaseAuthError::WeakPassword,
+                    _ => FirebaseAuthError::UnexpectedResponse(err.into()),
+                }
+            })
+        }).await?;
+        self.set_tokens(response.id_token, response.refresh_token, &response.expires_in)
+    }
+
+    // Update profile
+    // https://firebase.google.com/docs/reference/rest/auth#section-update-profile
+    pub async fn update_profile(&mut self, update: &ProfileUpdate) -> Result<()> {
+        let url = Url::parse_with_params(
+            &format!("{}{}", self.identitytoolkit_endpoint, "accounts:update"),
+            &[("key", &self.api_key)]
+        )?;
+        let requester = self.requester.clone();
+        let response = self.with_fresh_id_token(|id_token| {
+            let url = url.as_str();
+            let body = UpdateProfileRequest {
+                id_token,
+                display_name: update.display_name.clone(),
+                photo_url: update.photo_url.clone(),
+                delete_attribute: update.delete.iter().map(|attribute| attribute.name()).collect(),
+                return_secure_token: true,
+            };
+            let request = move |client: &reqwest::Client| client.post(url).json(&body);
+            requester.send::<UpdateProfileResponse, _>(Idempotency::Idempotent, request, |err| {
+                match err.message {
+                    FirebaseErrorMessage::InvalidIdToken => FirebaseAuthError::InvalidIdToken,
+                    _ => FirebaseAuthError::UnexpectedResponse(err.into()),
+                }
+            })
+        }).await?;
+        if let (Some(new_id_token), Some(new_refresh_token), Some(expires_in)) =
+            (response.id_token, response.refresh_token, response.expires_in)
+        {
+            self.set_tokens(new_id_token, new_refresh_token, &expires_in)?;
+        }
+        Ok(())
+    }
+
+    pub async fn change_display_name(&mut self, new_display_name: &str) -> Result<()> {
+        self.update_profile(&ProfileUpdate {
+            display_name: Some(new_display_name.to_string()),
+            ..ProfileUpdate::default()
+        }).await
+    }
+
+    // Get user data
+    // https://firebase.google.com/docs/reference/rest/auth#section-get-account-info
+    pub async fn get_account_info(&mut self) -> Result<AccountInfo> {
+        let url = Url::parse_with_params(
+            &format!("{}{}", self.identitytoolkit_endpoint, "accounts:lookup"),
+            &[("key", &self.api_key)]
+        )?;
+        let requester = self.requester.clone();
+        let response = self.with_fresh_id_token(|id_token| {
+            let url = url.as_str();
+            let body = GetUserDataRequest {
+                id_token,
+            };
+            let request = move |client: &reqwest::Client| client.post(url).json(&body);
+            requester.send::<GetUserDataResponse, _>(Idempotency::Idempotent, request, |err| {
+                match err.message {
+                    FirebaseErrorMessage::InvalidIdToken => FirebaseAuthError::InvalidIdToken,
+                    FirebaseErrorMessage::UserNotFound => FirebaseAuthError::UserNotFound,
+                    _ => FirebaseAuthError::UnexpectedResponse(err.into()),
+                }
+            })
+        }).await?;
+        response.users.into_iter().next()
+            .ok_or(FirebaseAuthError::FirebaseUnexpectedError(format!("No user data was returned.")))
+    }
+
+    pub async fn get_display_name(&mut self) -> Result<Option<String>> {
+        Ok(self.get_account_info().await?.display_name)
+    }
+
+    // Delete account. Signs out on success.
+    // https://firebase.google.com/docs/reference/rest/auth#section-delete-account
+    pub async fn delete_account(&mut self) -> Result<()> {
+        let url = Url::parse_with_params(
+            &format!("{}{}", self.identitytoolkit_endpoint, "accounts:delete"),
+            &[("key", &self.api_key)]
+        )?;
+        let requester = self.requester.clone();
+        self.with_fresh_id_token(|id_token| {
+            let url = url.as_str();
+            let body = DeleteAccountRequest {
+                id_token,
+            };
+            let request = move |client: &reqwest::Client| client.post(url).json(&body);
+            requester.send::<DeleteAccountResponse, _>(Idempotency::NotIdempotent, request, |err| {
+                match err.message {
+                    FirebaseErrorMessage::InvalidIdToken => FirebaseAuthError::InvalidIdToken,
+                    FirebaseErrorMessage::UserNotFound => FirebaseAuthError::UserNotFound,
+                    FirebaseErrorMessage::CredentialTooOldLoginAgain =>
+                        FirebaseAuthError::CredentialTooOldLoginAgain,
+                    _ => FirebaseAuthError::UnexpectedResponse(err.into()),
+                }
+            })
+        }).await?;
+        self.sign_out();
+        Ok(())
+    }
+
+    // Send password reset email
+    // https://firebase.google.com/docs/reference/rest/auth#section-send-password-reset-email
+    pub async fn send_password_reset_email(&self, email: &str) -> Result<()> {
+        let url = Url::parse_with_params(
+            &format!("{}{}", self.identitytoolkit_endpoint, "accounts:sendOobCode"),
+            &[("key", &self.api_key)]
+        )?;
+        let request = |client: &reqwest::Client| client.post(url.as_str())
+            .json(&SendOobCodeRequest {
+                request_type: "PASSWORD_RESET".to_string(),
+                email: Some(email.to_string()),
+                id_token: None,
             });
-        let response = make_request::<ChangePasswordResponse>(request, |err| {
+        self.requester.send::<SendOobCodeResponse, _>(Idempotency::NotIdempotent, request, |err| {
             match err.message {
-                FirebaseErrorMessage::InvalidIdToken => FirebaseAuthError::InvalidIdToken,
-                FirebaseErrorMessage::WeakPassword => FirebaseAuthError::WeakPassword,
-                unexpected_err =>
-                    FirebaseAuthError::FirebaseUnexpectedError(format!("{}", unexpected_err)),
+                FirebaseErrorMessage::EmailNotFound => FirebaseAuthError::EmailNotFound,
+                FirebaseErrorMessage::InvalidEmail => FirebaseAuthError::InvalidEmail,
+                _ => FirebaseAuthError::UnexpectedResponse(err.into()),
             }
         }).await?;
-        self.id_token = Some(response.id_token);
-        self.refresh_token = Some(response.refresh_token);
         Ok(())
     }
 
-    // Partial "Update profile"
-    // https://firebase.google.com/docs/reference/rest/auth#section-update-profile
-    // Note: we implement only display_name here! This is not all that Firebase supports.
-    pub async fn change_display_name(&mut self, new_display_name: &str) -> Result<()> {
+    // Send email verification, to the current user's email.
+    // https://firebase.google.com/docs/reference/rest/auth#section-send-email-verification
+    pub async fn send_email_verification(&mut self) -> Result<()> {
+        let url = Url::parse_with_params(
+            &format!("{}{}", self.identitytoolkit_endpoint, "accounts:sendOobCode"),
+            &[("key", &self.api_key)]
+        )?;
+        let requester = self.requester.clone();
+        self.with_fresh_id_token(|id_token| {
+            let url = url.as_str();
+            let body = SendOobCodeRequest {
+                request_type: "VERIFY_EMAIL".to_string(),
+                email: None,
+                id_token: Some(id_token),
+            };
+            let request = move |client: &reqwest::Client| client.post(url).json(&body);
+            requester.send::<SendOobCodeResponse, _>(Idempotency::NotIdempotent, request, |err| {
+                match err.message {
+                    FirebaseErrorMessage::InvalidIdToken => FirebaseAuthError::InvalidIdToken,
+                    FirebaseErrorMessage::UserNotFound => FirebaseAuthError::UserNotFound,
+                    _ => FirebaseAuthError::UnexpectedResponse(err.into()),
+                }
+            })
+        }).await?;
+        Ok(())
+    }
+
+    // Confirm email verification. Returns the email that got verified, or fails if Firebase says
+    // it is still not verified. The current id token's claims still say it is not verified until
+    // the token is refreshed.
+    // https://firebase.google.com/docs/reference/rest/auth#section-confirm-email-verification
+    pub async fn confirm_email_verification(&self, oob_code: &str) -> Result<String> {
         let url = Url::parse_with_params(
             &format!("{}{}", self.identitytoolkit_endpoint, "accounts:update"),
             &[("key", &self.api_key)]
         )?;
-        let id_token = self.id_token.as_ref().ok_or(FirebaseAuthError::AuthDataMissing)?;
-        let request = self.client.post(url.as_str())
-            .json(&UpdateProfileRequest{
-                id_token: id_token.to_string(),
-                display_name: new_display_name.to_string(),
-                return_secure_token: true,
+        let request = |client: &reqwest::Client| client.post(url.as_str())
+            .json(&ConfirmEmailVerificationRequest {
+                oob_code: oob_code.to_string(),
             });
-        let response = make_request::<UpdateProfileResponse>(request, |err| {
+        let response = self.requester.send::<ConfirmEmailVerificationResponse, _>(Idempotency::NotIdempotent, request, |err| {
             match err.message {
-                FirebaseErrorMessage::InvalidIdToken => FirebaseAuthError::InvalidIdToken,
-                unexpected_err =>
-                    FirebaseAuthError::FirebaseUnexpectedError(format!("{}", unexpected_err)),
+                FirebaseErrorMessage::ExpiredOobCode => FirebaseAuthError::ExpiredOobCode,
+                FirebaseErrorMessage::InvalidOobCode => FirebaseAuthError::InvalidOobCode,
+                FirebaseErrorMessage::UserDisabled => FirebaseAuthError::UserDisabled,
+                FirebaseErrorMessage::EmailNotFound => FirebaseAuthError::EmailNotFound,
+                _ => FirebaseAuthError::UnexpectedResponse(err.into()),
             }
         }).await?;
-        if let Some(new_id_token) = response.id_token {
-            self.id_token = Some(new_id_token);
-        }
-        if let Some(new_refresh_token) = response.refresh_token {
-            self.refresh_token = Some(new_refresh_token);
+        if !response.email_verified {
+            return Err(FirebaseAuthError::FirebaseUnexpectedError(
+                format!("The email {} is still not verified.", response.email)));
         }
-        Ok(())
+        Ok(response.email)
     }
 
-    // Partial "Get user data"
-    // https://firebase.google.com/docs/reference/rest/auth#section-get-account-info
-    // Note: we implement only display_name here! This is not all that Firebase supports.
-    pub async fn get_display_name(&mut self) -> Result<Option<String>> {
+    // Verify password reset code. Returns the email of the account the code is for.
+    // https://firebase.google.com/docs/reference/rest/auth#section-verify-password-reset-code
+    pub async fn verify_password_reset_code(&self, oob_code: &str) -> Result<String> {
+        self.reset_password(oob_code, None).await
+    }
+
+    // Confirm password reset. Returns the email of the account whose password was changed.
+    // https://firebase.google.com/docs/reference/rest/auth#section-confirm-reset-password
+    pub async fn confirm_password_reset(&self, oob_code: &str, new_password: &str)
+        -> Result<String>
+    {
+        self.reset_password(oob_code, Some(new_password)).await
+    }
+
+    // Both verifying and confirming use the same endpoint; without a new password, the code is
+    // only checked.
+    async fn reset_password(&self, oob_code: &str, new_password: Option<&str>) -> Result<String> {
         let url = Url::parse_with_params(
-            &format!("{}{}", self.identitytoolkit_endpoint, "accounts:lookup"),
+            &format!("{}{}", self.identitytoolkit_endpoint, "accounts:resetPassword"),
             &[("key", &self.api_key)]
         )?;
-        let id_token = self.id_token.as_ref().ok_or(FirebaseAuthError::AuthDataMissing)?;
-        let request = self.client.post(url.as_str())
-            .json(&GetUserDataRequest{
-                id_token: id_token.to_string(),
+        let request = |client: &reqwest::Client| client.post(url.as_str())
+            .json(&ResetPasswordRequest {
+                oob_code: oob_code.to_string(),
+                new_password: new_password.map(|password| password.to_string()),
             });
-        let response = make_request::<GetUserDataResponse>(request, |err| {
+        // Only checking the code can be repeated; once the password is reset, the code is used up.
+        let idempotency = match new_password {
+            None => Idempotency::Idempotent,
+            Some(_) => Idempotency::NotIdempotent,
+        };
+        let response = self.requester.send::<ResetPasswordResponse, _>(idempotency, request, |err| {
             match err.message {
-                FirebaseErrorMessage::InvalidIdToken => FirebaseAuthError::InvalidIdToken,
-                FirebaseErrorMessage::UserNotFound => FirebaseAuthError::UserNotFound,
-                unexpected_err =>
-                    FirebaseAuthError::FirebaseUnexpectedError(format!("{}", unexpected_err)),
+                FirebaseErrorMessage::OperationNotAllowed => FirebaseAuthError::OperationNotAllowed,
+                FirebaseErrorMessage::ExpiredOobCode => FirebaseAuthError::ExpiredOobCode,
+                FirebaseErrorMessage::InvalidOobCode => FirebaseAuthError::InvalidOobCode,
+                FirebaseErrorMessage::UserDisabled => FirebaseAuthError::UserDisabled,
+                FirebaseErrorMessage::WeakPassword => FirebaseAuthError::WeakPassword,
+                _ => FirebaseAuthError::UnexpectedResponse(err.into()),
             }
         }).await?;
-        if response.users.len() < 1 {
-            return Err(
-                FirebaseAuthError::FirebaseUnexpectedError(format!("No user data was returned.")));
-        }
-        Ok(response.users[0].display_name.clone())
+        Ok(response.email)
+    }
+
+    // expires_in is the number of seconds the id token is valid for, as a string.
+    fn set_tokens(&mut self, id_token: String, refresh_token: String, expires_in: &str)
+        -> Result<()>
+    {
+        let expires_in_secs = expires_in.parse::<u64>()
+            .map_err(|_| FirebaseAuthError::FirebaseUnexpectedError(
+                format!("Invalid expiresIn: {}", expires_in)))?;
+        self.id_token = Some(id_token);
+        self.id_token_expires_at = Some(now() + expires_in_secs * 1000);
+        self.refresh_token = Some(refresh_token);
+        Ok(())
+    }
+}
+
+// What accounts:lookup tells about the current user.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountInfo {
+    #[serde(rename = "localId")]
+    pub user_id: UserId,
+    // Anonymous users have none.
+    pub email: Option<String>,
+    #[serde(default)]
+    pub email_verified: bool,
+    pub display_name: Option<String>,
+    pub photo_url: Option<String>,
+}
+
+// Profile attributes that can be removed with ProfileUpdate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProfileAttribute {
+    DisplayName,
+    PhotoUrl,
+}
+
+impl ProfileAttribute {
+    fn name(&self) -> String {
+        match self {
+            ProfileAttribute::DisplayName => "DISPLAY_NAME",
+            ProfileAttribute::PhotoUrl => "PHOTO_URL",
+        }.to_string()
+    }
+}
+
+// Changes to the profile; attributes that are None stay as they are.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProfileUpdate {
+    pub display_name: Option<String>,
+    pub photo_url: Option<String>,
+    pub delete: Vec<ProfileAttribute>,
+}
+
+// Errors of requests authenticated with an id token (to Firebase Auth itself or other services),
+// which tell whether it was the token that got rejected.
+pub trait TokenRejection {
+    fn token_rejected(&self) -> bool;
+}
+
+impl TokenRejection for FirebaseAuthError {
+    fn token_rejected(&self) -> bool {
+        *self.last_attempt() == FirebaseAuthError::InvalidIdToken
+    }
+}
+
+// Sends requests to Firebase Auth, retrying them according to the retry policy.
+#[derive(Clone, Debug)]
+struct Requester {
+    // wasm version of reqwest's Client does not seem to do any connection pooling, since it should
+    // be handled by the browser. Still, this struct will try to use the same (potentially cloned
+    // since it is behind an Arc) client wherever possible.
+    client: reqwest::Client,
+    retry_policy: RetryPolicy,
+}
+
+impl Requester {
+    // The request is built anew for every attempt.
+    async fn send<Resp, B>(
+        &self, idempotency: Idempotency, build_request: B,
+        map_firebase_err: fn(FirebaseErrorDetails) -> FirebaseAuthError)
+        -> Result<Resp>
+        where B: Fn(&reqwest::Client) -> reqwest::RequestBuilder,
+              for<'de> Resp: Deserialize<'de>
+    {
+        self.retry_policy.run(idempotency, || {
+            make_request::<Resp>(build_request(&self.client), map_firebase_err)
+        }).await
     }
 }
 
@@ -256,13 +658,25 @@ async fn make_request<Resp>(
     -> Result<Resp> where for<'de> Resp: Deserialize<'de>
 {
     let response = request.send().await?;
-    if response.status().is_success() {
-        let resp_text = response.text().await?;
-        Ok(serde_json::from_str::<Resp>(&resp_text)?)
-    } else {
-        let resp_text = response.text().await?;
-        let firebase_resp = serde_json::from_str::<FirebaseErrorResponse>(&resp_text)?;
-        Err(map_firebase_err(firebase_resp.error))
+    let status = response.status();
+    let resp_text = response.text().await?;
+    if status.is_success() {
+        return Ok(serde_json::from_str::<Resp>(&resp_text)?);
+    }
+    let firebase_resp = match serde_json::from_str::<FirebaseErrorResponse>(&resp_text) {
+        Ok(firebase_resp) => firebase_resp,
+        // Errors from proxies in front of Firebase (e.g. when it is overloaded) are not JSON.
+        Err(_) => return Err(FirebaseAuthError::UnexpectedResponse(FirebaseApiError {
+            http_status: status.as_u16(),
+            status: None,
+            reason: None,
+            message: resp_text,
+        })),
+    };
+    // Every endpoint rejects a wrong API key the same way.
+    match firebase_resp.error.message {
+        FirebaseErrorMessage::ApiKeyInvalid => Err(FirebaseAuthError::ApiKeyInvalid),
+        _ => Err(map_firebase_err(firebase_resp.error)),
     }
 }
 
@@ -276,6 +690,7 @@ async fn make_request<Resp>(
 struct RefreshIdTokenResponse {
     refresh_token: String,
     id_token: String,
+    expires_in: String,
 }
 
 #[derive(Debug, PartialEq, Serialize)]
@@ -291,6 +706,32 @@ struct SignUpRequest {
 struct SignUpResponse {
     id_token: String,
     refresh_token: String,
+    expires_in: String,
+    local_id: String,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SignInAnonymouslyRequest {
+    return_secure_token: bool,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LinkEmailPasswordRequest {
+    id_token: String,
+    email: String,
+    password: String,
+    return_secure_token: bool,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LinkEmailPasswordResponse {
+    local_id: String,
+    id_token: String,
+    refresh_token: String,
+    expires_in: String,
 }
 
 #[derive(Debug, PartialEq, Serialize)]
@@ -306,6 +747,7 @@ struct SignInRequest {
 struct SignInResponse {
     id_token: String,
     refresh_token: String,
+    expires_in: String,
     local_id: String,
 }
 
@@ -323,6 +765,7 @@ struct ChangeEmailResponse {
     email: String,
     id_token: String,
     refresh_token: String,
+    expires_in: String,
 }
 
 #[derive(Debug, PartialEq, Serialize)]
@@ -339,13 +782,19 @@ struct ChangePasswordResponse {
     email: String,
     id_token: String,
     refresh_token: String,
+    expires_in: String,
 }
 
 #[derive(Debug, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
 struct UpdateProfileRequest {
     id_token: String,
-    display_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    display_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    photo_url: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    delete_attribute: Vec<String>,
     return_secure_token: bool,
 }
 
@@ -354,6 +803,7 @@ struct UpdateProfileRequest {
 struct UpdateProfileResponse {
     id_token: Option<String>,
     refresh_token: Option<String>,
+    expires_in: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Serialize)]
@@ -364,16 +814,62 @@ struct GetUserDataRequest {
 
 #[derive(Debug, PartialEq, Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct UserDetailsResponse {
-    local_id: String,
+struct GetUserDataResponse {
+    users: Vec<AccountInfo>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeleteAccountRequest {
+    id_token: String,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct DeleteAccountResponse {}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SendOobCodeRequest {
+    request_type: String,
+    // Password reset emails are sent to the given email, verification emails to the email of the
+    // user the id token belongs to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id_token: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SendOobCodeResponse {
     email: String,
-    display_name: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ConfirmEmailVerificationRequest {
+    oob_code: String,
 }
 
 #[derive(Debug, PartialEq, Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct GetUserDataResponse {
-    users: Vec<UserDetailsResponse>,
+struct ConfirmEmailVerificationResponse {
+    email: String,
+    email_verified: bool,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ResetPasswordRequest {
+    oob_code: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    new_password: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ResetPasswordResponse {
+    email: String,
 }
 
 // === Request/Response types ===
@@ -388,6 +884,730 @@ mod firebase_auth_tests {
         assert_ok!(FirebaseAuth::new("api_key"));
     }
 
+    #[test]
+    fn test_id_token_expiry() {
+        let mut auth = FirebaseAuth::new("api_key").unwrap();
+        assert!(auth.id_token_expires_soon());
+
+        assert_ok!(auth.set_tokens("id".to_string(), "refresh".to_string(), "3600"));
+        assert!(!auth.id_token_expires_soon());
+        let expires_at = auth.id_token_expires_at().unwrap();
+        assert!(expires_at > now() + 3500 * 1000 && expires_at <= now() + 3600 * 1000);
+
+        auth.set_id_token("id".to_string(), now() + 60 * 1000);
+        assert!(auth.id_token_expires_soon());
+
+        assert!(auth.set_tokens("id".to_string(), "refresh".to_string(), "soon").is_err());
+    }
+
+}
+
+#[cfg(test)]
+mod firebase_auth_http_tests {
+    // Firebase is replaced by mockito, serving canned responses of the REST API.
+    use super::*;
+    use k9::assert_ok;
+    use galvanic_assert::matchers::*;
+    use galvanic_assert::matchers::variant::*;
+    use mockito::{mock, Matcher, Mock};
+    use serde_json::json;
+    use std::error::Error;
+
+    const API_KEY: &str = "api_key";
+    const INVALID_API_KEY: &str = "API key not valid. Please pass a valid API key.";
+
+    fn new_auth() -> FirebaseAuth {
+        let mut auth = FirebaseAuth::new_custom_url_base(
+            API_KEY, &format!("{}/", mockito::server_url())).unwrap();
+        auth.set_retry_policy(quick_retry_policy());
+        auth
+    }
+
+    fn quick_retry_policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_delay: std::time::Duration::from_millis(1),
+            max_delay: std::time::Duration::from_millis(2),
+            multiplier: 2,
+        }
+    }
+
+    fn signed_in_auth() -> FirebaseAuth {
+        let mut auth = new_auth();
+        auth.set_refresh_token(Some("refresh_token".to_string()));
+        auth.set_id_token("id_token".to_string(), now() + 3600 * 1000);
+        auth
+    }
+
+    fn identitytoolkit_path(method: &str) -> String {
+        format!("/{}{}", IDENTITYTOOLKIT_URL_SUFFIX, method)
+    }
+
+    fn securetoken_path() -> String {
+        format!("/{}token", SECURETOKEN_URL_SUFFIX)
+    }
+
+    fn endpoint_mock(path: &str) -> Mock {
+        mock("POST", path)
+            .match_query(Matcher::UrlEncoded("key".to_string(), API_KEY.to_string()))
+            .with_header("content-type", "application/json")
+    }
+
+    fn success_response(path: &str, body: serde_json::Value) -> Mock {
+        endpoint_mock(path).with_status(200).with_body(body.to_string())
+    }
+
+    fn error_response(path: &str, message: &str) -> Mock {
+        let body = json!({
+            "error": {
+                "code": 400,
+                "message": message,
+                "errors": [{ "message": message, "domain": "global", "reason": "invalid" }],
+            }
+        });
+        endpoint_mock(path).with_status(400).with_body(body.to_string())
+    }
+
+    // Tokens in the format of the identitytoolkit endpoints.
+    fn tokens(local_id: &str) -> serde_json::Value {
+        json!({
+            "localId": local_id,
+            "email": "user@example.com",
+            "idToken": "new_id_token",
+            "refreshToken": "new_refresh_token",
+            "expiresIn": "3600",
+        })
+    }
+
+    // Tokens in the format of the securetoken endpoint.
+    fn refreshed_tokens() -> serde_json::Value {
+        json!({
+            "expires_in": "3600",
+            "token_type": "Bearer",
+            "refresh_token": "new_refresh_token",
+            "id_token": "new_id_token",
+            "user_id": "user_id",
+            "project_id": "1234",
+        })
+    }
+
+    fn assert_new_tokens(auth: &FirebaseAuth) {
+        assert_eq!(auth.id_token().map(String::as_str), Some("new_id_token"));
+        assert_eq!(auth.refresh_token().map(String::as_str), Some("new_refresh_token"));
+        assert!(!auth.id_token_expires_soon());
+    }
+
+    fn assert_old_tokens(auth: &FirebaseAuth) {
+        assert_eq!(auth.id_token().map(String::as_str), Some("id_token"));
+        assert_eq!(auth.refresh_token().map(String::as_str), Some("refresh_token"));
+    }
+
+    // Runs call against an endpoint failing with each of the messages in turn, checking what it
+    // gets reported as.
+    fn assert_error_mapping<T, F>(path: &str, call: F, cases: &[(&str, FirebaseAuthError)])
+        where T: std::fmt::Debug,
+              F: Fn() -> Result<T>
+    {
+        for (message, expected) in cases {
+            let _mock = error_response(path, message).create();
+            assert_that!(&call(), maybe_err(eq(expected.clone())));
+        }
+    }
+
+    // An error response error_response would produce, without a specific variant.
+    fn unexpected(message: &str) -> FirebaseAuthError {
+        FirebaseAuthError::UnexpectedResponse(FirebaseApiError {
+            http_status: 400,
+            status: None,
+            reason: Some("invalid".to_string()),
+            message: message.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_refresh_id_token() {
+        let mock = success_response(&securetoken_path(), refreshed_tokens())
+            .match_body(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("grant_type".to_string(), "refresh_token".to_string()),
+                Matcher::UrlEncoded("refresh_token".to_string(), "refresh_token".to_string()),
+            ]))
+            .create();
+        let mut auth = signed_in_auth();
+        assert_ok!(tokio_test::block_on(auth.refresh_id_token()));
+        assert_new_tokens(&auth);
+        mock.assert();
+    }
+
+    #[test]
+    fn test_refresh_id_token_errors() {
+        assert_that!(
+            &tokio_test::block_on(new_auth().refresh_id_token()),
+            maybe_err(eq(FirebaseAuthError::AuthDataMissing))
+        );
+        let call = || tokio_test::block_on(signed_in_auth().refresh_id_token());
+        assert_error_mapping(&securetoken_path(), call, &[
+            ("TOKEN_EXPIRED", FirebaseAuthError::TokenExpired),
+            ("USER_DISABLED", FirebaseAuthError::UserDisabled),
+            ("USER_NOT_FOUND", FirebaseAuthError::UserNotFound),
+            ("INVALID_REFRESH_TOKEN", FirebaseAuthError::Internal("InvalidRefreshToken".to_string())),
+            ("INVALID_GRANT_TYPE", FirebaseAuthError::Internal("InvalidGrantType".to_string())),
+            ("MISSING_REFRESH_TOKEN", FirebaseAuthError::Internal("MissingRefreshToken".to_string())),
+            (INVALID_API_KEY, FirebaseAuthError::ApiKeyInvalid),
+            ("INVALID_ARGUMENT", unexpected("INVALID_ARGUMENT")),
+        ]);
+    }
+
+    #[test]
+    fn test_sign_up() {
+        let mock = success_response(&identitytoolkit_path("accounts:signUp"), tokens("user_id"))
+            .match_body(Matcher::Json(json!({
+                "email": "user@example.com",
+                "password": "password",
+                "returnSecureToken": true,
+            })))
+            .create();
+        let mut auth = new_auth();
+        assert_that!(
+            &tokio_test::block_on(auth.sign_up("user@example.com", "password")),
+            maybe_ok(eq("user_id".to_string()))
+        );
+        assert_new_tokens(&auth);
+        mock.assert();
+    }
+
+    #[test]
+    fn test_sign_up_errors() {
+        let call = || tokio_test::block_on(new_auth().sign_up("user@example.com", "password"));
+        assert_error_mapping(&identitytoolkit_path("accounts:signUp"), call, &[
+            ("EMAIL_EXISTS", FirebaseAuthError::EmailExists),
+            ("INVALID_EMAIL", FirebaseAuthError::InvalidEmail),
+            ("WEAK_PASSWORD : Password should be at least 6 characters", FirebaseAuthError::WeakPassword),
+            ("OPERATION_NOT_ALLOWED", FirebaseAuthError::OperationNotAllowed),
+            ("TOO_MANY_ATTEMPTS_TRY_LATER : Try again later.", FirebaseAuthError::TooManyAttemptsTryLater),
+            (INVALID_API_KEY, FirebaseAuthError::ApiKeyInvalid),
+            ("Something new.", unexpected("Something new.")),
+        ]);
+    }
+
+    #[test]
+    fn test_sign_in_anonymously() {
+        let mut response = tokens("user_id");
+        response.as_object_mut().unwrap().remove("email");
+        let mock = success_response(&identitytoolkit_path("accounts:signUp"), response)
+            .match_body(Matcher::Json(json!({ "returnSecureToken": true })))
+            .create();
+        let mut auth = new_auth();
+        assert_that!(
+            &tokio_test::block_on(auth.sign_in_anonymously()),
+            maybe_ok(eq("user_id".to_string()))
+        );
+        assert_new_tokens(&auth);
+        mock.assert();
+
+        let call = || tokio_test::block_on(new_auth().sign_in_anonymously());
+        assert_error_mapping(&identitytoolkit_path("accounts:signUp"), call, &[
+            ("OPERATION_NOT_ALLOWED", FirebaseAuthError::OperationNotAllowed),
+            ("TOO_MANY_ATTEMPTS_TRY_LATER", FirebaseAuthError::TooManyAttemptsTryLater),
+            (INVALID_API_KEY, FirebaseAuthError::ApiKeyInvalid),
+        ]);
+    }
+
+    #[test]
+    fn test_sign_in() {
+        let mock = success_response(&identitytoolkit_path("accounts:signInWithPassword"), tokens("user_id"))
+            .match_body(Matcher::Json(json!({
+                "email": "user@example.com",
+                "password": "password",
+                "returnSecureToken": true,
+            })))
+            .create();
+        let mut auth = new_auth();
+        assert_that!(
+            &tokio_test::block_on(auth.sign_in("user@example.com", "password")),
+            maybe_ok(eq("user_id".to_string()))
+        );
+        assert_new_tokens(&auth);
+        mock.assert();
+    }
+
+    #[test]
+    fn test_sign_in_errors() {
+        let call = || tokio_test::block_on(new_auth().sign_in("user@example.com", "password"));
+        assert_error_mapping(&identitytoolkit_path("accounts:signInWithPassword"), call, &[
+            ("EMAIL_NOT_FOUND", FirebaseAuthError::EmailNotFound),
+            ("INVALID_PASSWORD", FirebaseAuthError::InvalidPassword),
+            ("USER_DISABLED", FirebaseAuthError::UserDisabled),
+            (INVALID_API_KEY, FirebaseAuthError::ApiKeyInvalid),
+            ("INVALID_ARGUMENT", unexpected("INVALID_ARGUMENT")),
+        ]);
+    }
+
+    #[test]
+    fn test_malformed_responses() {
+        let path = identitytoolkit_path("accounts:signInWithPassword");
+        let call = || tokio_test::block_on(new_auth().sign_in("user@example.com", "password"));
+        {
+            let mut response = tokens("user_id");
+            response["expiresIn"] = json!("soon");
+            let _mock = success_response(&path, response).create();
+            assert_that!(&call(), maybe_err(eq(FirebaseAuthError::FirebaseUnexpectedError(
+                "Invalid expiresIn: soon".to_string()))));
+        }
+        {
+            let _mock = success_response(&path, json!({ "kind": "identitytoolkit#VerifyPasswordResponse" }))
+                .create();
+            assert!(matches!(call(), Err(FirebaseAuthError::FirebaseUnexpectedError(_))));
+        }
+        {
+            let mock = endpoint_mock(&path)
+                .with_status(502)
+                .with_header("content-type", "text/html")
+                .with_body("<html><body>Bad Gateway</body></html>")
+                .expect(1)
+                .create();
+            let result = call();
+            assert_that!(&result, maybe_err(eq(FirebaseAuthError::UnexpectedResponse(FirebaseApiError {
+                http_status: 502,
+                status: None,
+                reason: None,
+                message: "<html><body>Bad Gateway</body></html>".to_string(),
+            }))));
+            assert_eq!(result.unwrap_err().class(), ErrorClass::Retryable);
+            mock.assert();
+        }
+    }
+
+    #[test]
+    fn test_network_error() {
+        // Nothing listens on port 1.
+        let mut auth = FirebaseAuth::new_custom_url_base(API_KEY, "http://127.0.0.1:1/").unwrap();
+        auth.set_retry_policy(quick_retry_policy());
+        auth.set_refresh_token(Some("refresh_token".to_string()));
+        auth.set_id_token("id_token".to_string(), now() + 3600 * 1000);
+        let result = tokio_test::block_on(auth.get_account_info());
+        match result {
+            Err(err @ FirebaseAuthError::GaveUp { attempts: 3, .. }) => {
+                assert_eq!(err.class(), ErrorClass::Retryable);
+                let last_error = err.source().unwrap();
+                assert!(matches!(
+                    last_error.downcast_ref::<FirebaseAuthError>(),
+                    Some(FirebaseAuthError::NetworkError(_))
+                ));
+                // Down to the error reqwest failed with.
+                assert!(last_error.source().and_then(|transport_err| transport_err.source()).is_some());
+            },
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        auth.set_retry_policy(RetryPolicy { max_attempts: 1, ..quick_retry_policy() });
+        let result = tokio_test::block_on(auth.get_account_info());
+        assert!(matches!(result, Err(FirebaseAuthError::NetworkError(_))));
+    }
+
+    #[test]
+    fn test_retries_idempotent_requests() {
+        let path = identitytoolkit_path("accounts:resetPassword");
+        let mock = endpoint_mock(&path)
+            .with_status(503)
+            .with_header("content-type", "text/html")
+            .with_body("Service Unavailable")
+            .expect(3)
+            .create();
+        let result = tokio_test::block_on(new_auth().verify_password_reset_code("code"));
+        assert_that!(&result, maybe_err(eq(FirebaseAuthError::GaveUp {
+            attempts: 3,
+            last_error: Box::new(FirebaseAuthError::UnexpectedResponse(FirebaseApiError {
+                http_status: 503,
+                status: None,
+                reason: None,
+                message: "Service Unavailable".to_string(),
+            })),
+        })));
+        mock.assert();
+    }
+
+    #[test]
+    fn test_does_not_retry_other_requests() {
+        let path = identitytoolkit_path("accounts:signUp");
+        let mock = error_response(&path, "TOO_MANY_ATTEMPTS_TRY_LATER").expect(1).create();
+        let result = tokio_test::block_on(new_auth().sign_up("user@example.com", "password"));
+        assert_that!(&result, maybe_err(eq(FirebaseAuthError::TooManyAttemptsTryLater)));
+        mock.assert();
+
+        // Retrying would only make more password attempts against a rate limited account.
+        let path = identitytoolkit_path("accounts:signInWithPassword");
+        let mock = error_response(&path, "TOO_MANY_ATTEMPTS_TRY_LATER").expect(1).create();
+        let result = tokio_test::block_on(new_auth().sign_in("user@example.com", "password"));
+        assert_that!(&result, maybe_err(eq(FirebaseAuthError::TooManyAttemptsTryLater)));
+        mock.assert();
+    }
+
+    #[test]
+    fn test_link_email_password() {
+        let path = identitytoolkit_path("accounts:update");
+        let mock = success_response(&path, tokens("user_id"))
+            .match_body(Matcher::Json(json!({
+                "idToken": "id_token",
+                "email": "user@example.com",
+                "password": "password",
+                "returnSecureToken": true,
+            })))
+            .create();
+        let mut auth = signed_in_auth();
+        assert_ok!(tokio_test::block_on(auth.link_email_password("user@example.com", "password")));
+        assert_new_tokens(&auth);
+        mock.assert();
+
+        let call = || {
+            tokio_test::block_on(signed_in_auth().link_email_password("user@example.com", "password"))
+        };
+        assert_error_mapping(&path, call, &[
+            ("CREDENTIAL_TOO_OLD_LOGIN_AGAIN", FirebaseAuthError::CredentialTooOldLoginAgain),
+            ("EMAIL_EXISTS", FirebaseAuthError::EmailExists),
+            ("INVALID_EMAIL", FirebaseAuthError::InvalidEmail),
+            ("WEAK_PASSWORD : Password should be at least 6 characters", FirebaseAuthError::WeakPassword),
+            (INVALID_API_KEY, FirebaseAuthError::ApiKeyInvalid),
+        ]);
+    }
+
+    #[test]
+    fn test_change_email() {
+        let path = identitytoolkit_path("accounts:update");
+        let mock = success_response(&path, tokens("user_id"))
+            .match_body(Matcher::Json(json!({
+                "idToken": "id_token",
+                "email": "new@example.com",
+                "returnSecureToken": true,
+            })))
+            .create();
+        let mut auth = signed_in_auth();
+        assert_ok!(tokio_test::block_on(auth.change_email("new@example.com")));
+        assert_new_tokens(&auth);
+        mock.assert();
+
+        let call = || tokio_test::block_on(signed_in_auth().change_email("new@example.com"));
+        assert_error_mapping(&path, call, &[
+            ("CREDENTIAL_TOO_OLD_LOGIN_AGAIN", FirebaseAuthError::CredentialTooOldLoginAgain),
+            ("EMAIL_EXISTS", FirebaseAuthError::EmailExists),
+            ("INVALID_EMAIL", FirebaseAuthError::InvalidEmail),
+            (INVALID_API_KEY, FirebaseAuthError::ApiKeyInvalid),
+            ("INVALID_ARGUMENT", unexpected("INVALID_ARGUMENT")),
+        ]);
+    }
+
+    #[test]
+    fn test_change_password() {
+        let path = identitytoolkit_path("accounts:update");
+        let mock = success_response(&path, tokens("user_id"))
+            .match_body(Matcher::Json(json!({
+                "idToken": "id_token",
+                "password": "new_password",
+                "returnSecureToken": true,
+            })))
+            .create();
+        let mut auth = signed_in_auth();
+        assert_ok!(tokio_test::block_on(auth.change_password("new_password")));
+        assert_new_tokens(&auth);
+        mock.assert();
+
+        let call = || tokio_test::block_on(signed_in_auth().change_password("new_password"));
+        assert_error_mapping(&path, call, &[
+            ("CREDENTIAL_TOO_OLD_LOGIN_AGAIN", FirebaseAuthError::CredentialTooOldLoginAgain),
+            ("WEAK_PASSWORD : Password should be at least 6 characters", FirebaseAuthError::WeakPassword),
+            (INVALID_API_KEY, FirebaseAuthError::ApiKeyInvalid),
+        ]);
+    }
+
+    #[test]
+    fn test_update_profile() {
+        let path = identitytoolkit_path("accounts:update");
+        let update = ProfileUpdate {
+            display_name: Some("Name".to_string()),
+            delete: vec![ProfileAttribute::PhotoUrl],
+            ..ProfileUpdate::default()
+        };
+        let mut auth = signed_in_auth();
+        {
+            // Firebase may leave the tokens out, in which case the current ones are kept.
+            let mock = success_response(&path, json!({ "localId": "user_id", "displayName": "Name" }))
+                .match_body(Matcher::Json(json!({
+                    "idToken": "id_token",
+                    "displayName": "Name",
+                    "deleteAttribute": ["PHOTO_URL"],
+                    "returnSecureToken": true,
+                })))
+                .create();
+            assert_ok!(tokio_test::block_on(auth.update_profile(&update)));
+            assert_old_tokens(&auth);
+            mock.assert();
+        }
+        {
+            let mock = success_response(&path, tokens("user_id"))
+                .match_body(Matcher::Json(json!({
+                    "idToken": "id_token",
+                    "deleteAttribute": ["DISPLAY_NAME"],
+                    "returnSecureToken": true,
+                })))
+                .create();
+            assert_ok!(tokio_test::block_on(auth.update_profile(&ProfileUpdate {
+                delete: vec![ProfileAttribute::DisplayName],
+                ..ProfileUpdate::default()
+            })));
+            assert_new_tokens(&auth);
+            mock.assert();
+        }
+
+        let call = || tokio_test::block_on(signed_in_auth().update_profile(&update));
+        assert_error_mapping(&path, call, &[
+            (INVALID_API_KEY, FirebaseAuthError::ApiKeyInvalid),
+            ("INVALID_ARGUMENT", unexpected("INVALID_ARGUMENT")),
+        ]);
+    }
+
+    #[test]
+    fn test_get_account_info() {
+        let path = identitytoolkit_path("accounts:lookup");
+        let mock = success_response(&path, json!({
+            "kind": "identitytoolkit#GetAccountInfoResponse",
+            "users": [{
+                "localId": "user_id",
+                "email": "user@example.com",
+                "emailVerified": true,
+                "displayName": "Name",
+                "passwordHash": "UkVEQUNURUQ=",
+                "providerUserInfo": [],
+            }],
+        }))
+            .match_body(Matcher::Json(json!({ "idToken": "id_token" })))
+            .create();
+        assert_that!(
+            &tokio_test::block_on(signed_in_auth().get_account_info()),
+            maybe_ok(eq(AccountInfo {
+                user_id: "user_id".to_string(),
+                email: Some("user@example.com".to_string()),
+                email_verified: true,
+                display_name: Some("Name".to_string()),
+                photo_url: None,
+            }))
+        );
+        mock.assert();
+        drop(mock);
+
+        let mock = success_response(&path, json!({ "users": [] })).create();
+        assert_that!(
+            &tokio_test::block_on(signed_in_auth().get_account_info()),
+            maybe_err(eq(FirebaseAuthError::FirebaseUnexpectedError(
+                "No user data was returned.".to_string())))
+        );
+        drop(mock);
+
+        let call = || tokio_test::block_on(signed_in_auth().get_account_info());
+        assert_error_mapping(&path, call, &[
+            ("USER_NOT_FOUND", FirebaseAuthError::UserNotFound),
+            (INVALID_API_KEY, FirebaseAuthError::ApiKeyInvalid),
+        ]);
+    }
+
+    #[test]
+    fn test_delete_account() {
+        let path = identitytoolkit_path("accounts:delete");
+        let mock = success_response(&path, json!({ "kind": "identitytoolkit#DeleteAccountResponse" }))
+            .match_body(Matcher::Json(json!({ "idToken": "id_token" })))
+            .create();
+        let mut auth = signed_in_auth();
+        assert_ok!(tokio_test::block_on(auth.delete_account()));
+        assert_eq!(auth.id_token(), None);
+        assert_eq!(auth.refresh_token(), None);
+        mock.assert();
+        drop(mock);
+
+        // Failing to delete the account keeps the user signed in.
+        let mock = error_response(&path, "CREDENTIAL_TOO_OLD_LOGIN_AGAIN").create();
+        let mut auth = signed_in_auth();
+        assert_that!(
+            &tokio_test::block_on(auth.delete_account()),
+            maybe_err(eq(FirebaseAuthError::CredentialTooOldLoginAgain))
+        );
+        assert_old_tokens(&auth);
+        drop(mock);
+
+        let call = || tokio_test::block_on(signed_in_auth().delete_account());
+        assert_error_mapping(&path, call, &[
+            ("USER_NOT_FOUND", FirebaseAuthError::UserNotFound),
+            (INVALID_API_KEY, FirebaseAuthError::ApiKeyInvalid),
+        ]);
+    }
+
+    #[test]
+    fn test_refreshes_expiring_id_token_first() {
+        let refresh_mock = success_response(&securetoken_path(), refreshed_tokens()).create();
+        let lookup_mock = success_response(&identitytoolkit_path("accounts:lookup"), json!({
+            "users": [{ "localId": "user_id" }],
+        }))
+            .match_body(Matcher::Json(json!({ "idToken": "new_id_token" })))
+            .create();
+        let mut auth = signed_in_auth();
+        auth.set_id_token("id_token".to_string(), now() + 60 * 1000);
+        assert_ok!(tokio_test::block_on(auth.get_account_info()));
+        assert_new_tokens(&auth);
+        refresh_mock.assert();
+        lookup_mock.assert();
+    }
+
+    #[test]
+    fn test_retries_once_with_refreshed_id_token() {
+        let path = identitytoolkit_path("accounts:lookup");
+        let refresh_mock = success_response(&securetoken_path(), refreshed_tokens()).create();
+        let rejected_mock = error_response(&path, "INVALID_ID_TOKEN")
+            .match_body(Matcher::Json(json!({ "idToken": "id_token" })))
+            .create();
+        let accepted_mock = success_response(&path, json!({ "users": [{ "localId": "user_id" }] }))
+            .match_body(Matcher::Json(json!({ "idToken": "new_id_token" })))
+            .create();
+        let mut auth = signed_in_auth();
+        assert_ok!(tokio_test::block_on(auth.get_account_info()));
+        assert_new_tokens(&auth);
+        refresh_mock.assert();
+        rejected_mock.assert();
+        accepted_mock.assert();
+    }
+
+    #[test]
+    fn test_gives_up_when_refreshed_id_token_is_rejected() {
+        let refresh_mock = success_response(&securetoken_path(), refreshed_tokens())
+            .expect(1)
+            .create();
+        let rejected_mock = error_response(&identitytoolkit_path("accounts:lookup"), "INVALID_ID_TOKEN")
+            .expect(2)
+            .create();
+        assert_that!(
+            &tokio_test::block_on(signed_in_auth().get_account_info()),
+            maybe_err(eq(FirebaseAuthError::InvalidIdToken))
+        );
+        refresh_mock.assert();
+        rejected_mock.assert();
+    }
+
+    #[test]
+    fn test_send_password_reset_email() {
+        let path = identitytoolkit_path("accounts:sendOobCode");
+        let mock = success_response(&path, json!({ "email": "user@example.com" }))
+            .match_body(Matcher::Json(json!({
+                "requestType": "PASSWORD_RESET",
+                "email": "user@example.com",
+            })))
+            .create();
+        let auth = new_auth();
+        assert_ok!(tokio_test::block_on(auth.send_password_reset_email("user@example.com")));
+        mock.assert();
+
+        let call = || tokio_test::block_on(new_auth().send_password_reset_email("user@example.com"));
+        assert_error_mapping(&path, call, &[
+            ("EMAIL_NOT_FOUND", FirebaseAuthError::EmailNotFound),
+            ("INVALID_EMAIL", FirebaseAuthError::InvalidEmail),
+            (INVALID_API_KEY, FirebaseAuthError::ApiKeyInvalid),
+        ]);
+    }
+
+    #[test]
+    fn test_send_email_verification() {
+        let path = identitytoolkit_path("accounts:sendOobCode");
+        let mock = success_response(&path, json!({ "email": "user@example.com" }))
+            .match_body(Matcher::Json(json!({
+                "requestType": "VERIFY_EMAIL",
+                "idToken": "id_token",
+            })))
+            .create();
+        let mut auth = signed_in_auth();
+        assert_ok!(tokio_test::block_on(auth.send_email_verification()));
+        assert_old_tokens(&auth);
+        mock.assert();
+
+        let call = || tokio_test::block_on(signed_in_auth().send_email_verification());
+        assert_error_mapping(&path, call, &[
+            ("USER_NOT_FOUND", FirebaseAuthError::UserNotFound),
+            (INVALID_API_KEY, FirebaseAuthError::ApiKeyInvalid),
+        ]);
+    }
+
+    #[test]
+    fn test_confirm_email_verification() {
+        let path = identitytoolkit_path("accounts:update");
+        let mock = success_response(&path, json!({
+            "localId": "user_id",
+            "email": "user@example.com",
+            "emailVerified": true,
+        }))
+            .match_body(Matcher::Json(json!({ "oobCode": "code" })))
+            .create();
+        assert_that!(
+            &tokio_test::block_on(new_auth().confirm_email_verification("code")),
+            maybe_ok(eq("user@example.com".to_string()))
+        );
+        mock.assert();
+
+        let mock = success_response(&path, json!({
+            "localId": "user_id",
+            "email": "user@example.com",
+            "emailVerified": false,
+        })).create();
+        assert_that!(
+            &tokio_test::block_on(new_auth().confirm_email_verification("code")),
+            maybe_err(eq(FirebaseAuthError::FirebaseUnexpectedError(
+                "The email user@example.com is still not verified.".to_string())))
+        );
+        mock.assert();
+
+        let call = || tokio_test::block_on(new_auth().confirm_email_verification("code"));
+        assert_error_mapping(&path, call, &[
+            ("EXPIRED_OOB_CODE", FirebaseAuthError::ExpiredOobCode),
+            ("INVALID_OOB_CODE", FirebaseAuthError::InvalidOobCode),
+            ("USER_DISABLED", FirebaseAuthError::UserDisabled),
+            ("EMAIL_NOT_FOUND", FirebaseAuthError::EmailNotFound),
+            (INVALID_API_KEY, FirebaseAuthError::ApiKeyInvalid),
+        ]);
+    }
+
+    #[test]
+    fn test_password_reset() {
+        let path = identitytoolkit_path("accounts:resetPassword");
+        {
+            let mock = success_response(&path, json!({
+                "email": "user@example.com",
+                "requestType": "PASSWORD_RESET",
+            }))
+                .match_body(Matcher::Json(json!({ "oobCode": "code" })))
+                .create();
+            assert_that!(
+                &tokio_test::block_on(new_auth().verify_password_reset_code("code")),
+                maybe_ok(eq("user@example.com".to_string()))
+            );
+            mock.assert();
+        }
+        {
+            let mock = success_response(&path, json!({
+                "email": "user@example.com",
+                "requestType": "PASSWORD_RESET",
+            }))
+                .match_body(Matcher::Json(json!({ "oobCode": "code", "newPassword": "new_password" })))
+                .create();
+            assert_that!(
+                &tokio_test::block_on(new_auth().confirm_password_reset("code", "new_password")),
+                maybe_ok(eq("user@example.com".to_string()))
+            );
+            mock.assert();
+        }
+
+        let call = || tokio_test::block_on(new_auth().confirm_password_reset("code", "new_password"));
+        assert_error_mapping(&path, call, &[
+            ("OPERATION_NOT_ALLOWED", FirebaseAuthError::OperationNotAllowed),
+            ("EXPIRED_OOB_CODE", FirebaseAuthError::ExpiredOobCode),
+            ("INVALID_OOB_CODE", FirebaseAuthError::InvalidOobCode),
+            ("USER_DISABLED", FirebaseAuthError::UserDisabled),
+            ("WEAK_PASSWORD : Password should be at least 6 characters", FirebaseAuthError::WeakPassword),
+            (INVALID_API_KEY, FirebaseAuthError::ApiKeyInvalid),
+        ]);
+    }
 }
 
 #[cfg(test)]
@@ -423,13 +1643,42 @@ mod firebase_auth_local_emulator_tests {
         ));
     }
 
+    // The emulator does not send emails; the codes it would have sent can be listed instead.
+    fn last_oob_code_for(email: &str) -> String {
+        let project_id = "packing-checklist-3879";
+        let host = get_host_from_config();
+        let response = tokio_test::block_on(async {
+            reqwest::Client::new()
+                .get(format!("{}emulator/v1/projects/{}/oobCodes", host, project_id))
+                .send().await?
+                .json::<serde_json::Value>().await
+        }).unwrap();
+        response["oobCodes"].as_array().unwrap().iter()
+            .rev()
+            .find(|code| code["email"] == email)
+            .and_then(|code| code["oobCode"].as_str())
+            .unwrap()
+            .to_string()
+    }
+
     #[test]
     fn test_new_user_flow() {
         global_setup();
         let mut auth = FirebaseAuth::new_custom_url_base("api_key", &get_host_from_config()).unwrap();
-        assert_ok!(tokio_test::block_on(auth.sign_up("user@example.com", "password")));
-        assert_ok!(tokio_test::block_on(auth.sign_in("user@example.com", "password")));
+        let user_id = tokio_test::block_on(auth.sign_up("user@example.com", "password")).unwrap();
+        assert!(!auth.id_token_expires_soon());
+        let claims = auth.claims().unwrap();
+        assert_eq!(claims.user_id, user_id);
+        assert_that!(&claims.email, maybe_some(eq("user@example.com".to_string())));
+        assert_that!(
+            &tokio_test::block_on(auth.sign_in("user@example.com", "password")),
+            maybe_ok(eq(user_id))
+        );
         assert_ok!(tokio_test::block_on(auth.refresh_id_token()));
+        assert_that!(
+            &tokio_test::block_on(auth.sign_up("user@example.com", "password")),
+            maybe_err(eq(FirebaseAuthError::EmailExists))
+        );
     }
 
     #[test]
@@ -448,6 +1697,37 @@ mod firebase_auth_local_emulator_tests {
         );
     }
 
+    #[test]
+    fn test_profile_update() {
+        global_setup();
+        let mut auth = FirebaseAuth::new_custom_url_base("api_key", &get_host_from_config()).unwrap();
+
+        let user_id = tokio_test::block_on(auth.sign_up("user8@example.com", "password8")).unwrap();
+        assert_ok!(tokio_test::block_on(auth.update_profile(&ProfileUpdate {
+            display_name: Some("Name".to_string()),
+            photo_url: Some("https://example.com/photo.png".to_string()),
+            ..ProfileUpdate::default()
+        })));
+        assert_that!(
+            &tokio_test::block_on(auth.get_account_info()),
+            maybe_ok(eq(AccountInfo {
+                user_id,
+                email: Some("user8@example.com".to_string()),
+                email_verified: false,
+                display_name: Some("Name".to_string()),
+                photo_url: Some("https://example.com/photo.png".to_string()),
+            }))
+        );
+
+        assert_ok!(tokio_test::block_on(auth.update_profile(&ProfileUpdate {
+            delete: vec![ProfileAttribute::PhotoUrl],
+            ..ProfileUpdate::default()
+        })));
+        let account_info = tokio_test::block_on(auth.get_account_info()).unwrap();
+        assert_eq!(account_info.photo_url, None);
+        assert_that!(&account_info.display_name, maybe_some(eq("Name".to_string())));
+    }
+
     #[test]
     fn test_password_change() {
         global_setup();
@@ -483,4 +1763,90 @@ mod firebase_auth_local_emulator_tests {
         );
         assert_ok!(tokio_test::block_on(auth.sign_in("new_email@new_email.com", "password3")));
     }
+
+    #[test]
+    fn test_password_reset() {
+        global_setup();
+        let mut auth = FirebaseAuth::new_custom_url_base("api_key", &get_host_from_config()).unwrap();
+
+        assert_ok!(tokio_test::block_on(auth.sign_up("user4@example.com", "password4")));
+        assert_that!(
+            &tokio_test::block_on(auth.send_password_reset_email("nobody@example.com")),
+            maybe_err(eq(FirebaseAuthError::EmailNotFound))
+        );
+        assert_ok!(tokio_test::block_on(auth.send_password_reset_email("user4@example.com")));
+        let oob_code = last_oob_code_for("user4@example.com");
+
+        assert_that!(
+            &tokio_test::block_on(auth.verify_password_reset_code(&oob_code)),
+            maybe_ok(eq("user4@example.com".to_string()))
+        );
+        assert_that!(
+            &tokio_test::block_on(auth.confirm_password_reset(&oob_code, "new_password4")),
+            maybe_ok(eq("user4@example.com".to_string()))
+        );
+        assert_that!(
+            &tokio_test::block_on(auth.confirm_password_reset(&oob_code, "other_password4")),
+            maybe_err(eq(FirebaseAuthError::InvalidOobCode))
+        );
+        assert_that!(
+            &tokio_test::block_on(auth.sign_in("user4@example.com", "password4")),
+            maybe_err(eq(FirebaseAuthError::InvalidPassword))
+        );
+        assert_ok!(tokio_test::block_on(auth.sign_in("user4@example.com", "new_password4")));
+    }
+
+    #[test]
+    fn test_anonymous_user_upgrade() {
+        global_setup();
+        let mut auth = FirebaseAuth::new_custom_url_base("api_key", &get_host_from_config()).unwrap();
+
+        let user_id = tokio_test::block_on(auth.sign_in_anonymously()).unwrap();
+        assert!(auth.claims().unwrap().is_anonymous());
+        assert_ok!(tokio_test::block_on(auth.link_email_password("user7@example.com", "password7")));
+        let claims = auth.claims().unwrap();
+        assert_eq!(claims.user_id, user_id);
+        assert!(!claims.is_anonymous());
+        assert_that!(
+            &tokio_test::block_on(auth.sign_in("user7@example.com", "password7")),
+            maybe_ok(eq(user_id))
+        );
+    }
+
+    #[test]
+    fn test_account_deletion() {
+        global_setup();
+        let mut auth = FirebaseAuth::new_custom_url_base("api_key", &get_host_from_config()).unwrap();
+
+        assert_ok!(tokio_test::block_on(auth.sign_up("user6@example.com", "password6")));
+        assert!(!auth.sign_in_too_old());
+        assert_ok!(tokio_test::block_on(auth.delete_account()));
+        assert_eq!(auth.refresh_token(), None);
+        assert_that!(
+            &tokio_test::block_on(auth.sign_in("user6@example.com", "password6")),
+            maybe_err(eq(FirebaseAuthError::EmailNotFound))
+        );
+    }
+
+    #[test]
+    fn test_email_verification() {
+        global_setup();
+        let mut auth = FirebaseAuth::new_custom_url_base("api_key", &get_host_from_config()).unwrap();
+
+        assert_ok!(tokio_test::block_on(auth.sign_up("user5@example.com", "password5")));
+        assert!(!auth.claims().unwrap().email_verified);
+        assert_ok!(tokio_test::block_on(auth.send_email_verification()));
+        let oob_code = last_oob_code_for("user5@example.com");
+
+        assert_that!(
+            &tokio_test::block_on(auth.confirm_email_verification(&oob_code)),
+            maybe_ok(eq("user5@example.com".to_string()))
+        );
+        assert_ok!(tokio_test::block_on(auth.refresh_id_token()));
+        assert!(auth.claims().unwrap().email_verified);
+        assert_that!(
+            &tokio_test::block_on(auth.confirm_email_verification("not-a-code")),
+            maybe_err(eq(FirebaseAuthError::InvalidOobCode))
+        );
+    }
 }
@@ -0,0 +1,193 @@
+// The part of FirebaseAuth the app relies on to authenticate users and manage their accounts, so
+// that it can be swapped for InMemoryAuth in tests and offline development.
+
+use std::future::Future;
+
+use async_trait::async_trait;
+
+use super::auth::{
+    AccountInfo, FirebaseAuth, FirebaseAuthError, ProfileUpdate, Result, TokenRejection, UserId};
+use super::id_token::{decode_claims, IdTokenClaims};
+use super::in_memory_auth::InMemoryAuth;
+use crate::config::{Config};
+use crate::types::{Timestamp};
+
+// Futures are not Send, since neither are the browser's.
+#[async_trait(?Send)]
+pub trait AuthBackend: std::fmt::Debug {
+    // Copy with the same tokens, talking to the same backend.
+    fn clone_box(&self) -> Box<dyn AuthBackend>;
+
+    fn id_token(&self) -> Option<&String>;
+
+    fn id_token_expires_at(&self) -> Option<Timestamp>;
+
+    // Whether the id token is missing or about to expire, so that it should be refreshed before
+    // it is used.
+    fn id_token_expires_soon(&self) -> bool;
+
+    fn refresh_token(&self) -> Option<&String>;
+
+    // Replaces the refresh token and forgets the id token, which belongs to the old one.
+    fn set_refresh_token(&mut self, refresh_token: Option<String>);
+
+    fn set_id_token(&mut self, id_token: String, expires_at: Timestamp);
+
+    // Whether sensitive operations, such as deleting the account, would be refused until the user
+    // signs in again.
+    fn sign_in_too_old(&self) -> bool;
+
+    fn sign_out(&mut self);
+
+    fn claims(&self) -> Result<IdTokenClaims> {
+        let id_token = self.id_token().ok_or(FirebaseAuthError::AuthDataMissing)?;
+        decode_claims(id_token)
+    }
+
+    async fn refresh_id_token(&mut self) -> Result<()>;
+
+    async fn sign_up(&mut self, email: &str, password: &str) -> Result<UserId>;
+
+    async fn sign_in(&mut self, email: &str, password: &str) -> Result<UserId>;
+
+    async fn sign_in_anonymously(&mut self) -> Result<UserId>;
+
+    async fn link_email_password(&mut self, email: &str, password: &str) -> Result<()>;
+
+    async fn change_email(&mut self, new_email: &str) -> Result<()>;
+
+    async fn change_password(&mut self, new_password: &str) -> Result<()>;
+
+    async fn update_profile(&mut self, update: &ProfileUpdate) -> Result<()>;
+
+    async fn get_account_info(&mut self) -> Result<AccountInfo>;
+
+    async fn send_email_verification(&mut self) -> Result<()>;
+
+    async fn delete_account(&mut self) -> Result<()>;
+}
+
+impl Clone for Box<dyn AuthBackend> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+// The backend the config asks for: Firebase Auth, or the fake when working offline.
+pub fn from_config(config: &Config) -> Result<Box<dyn AuthBackend>> {
+    if config.offline_auth {
+        return Ok(Box::new(InMemoryAuth::shared()));
+    }
+    Ok(Box::new(FirebaseAuth::new_custom_url_base(&config.api_key, &config.auth_host)?))
+}
+
+// Runs a request authenticated with the id token, refreshing the token first if it is about to
+// expire. If the token gets rejected anyway (e.g. it was revoked), it is refreshed and the request
+// is retried once.
+pub async fn with_fresh_id_token<A, T, E, F, Fut>(auth: &mut A, request: F)
+    -> std::result::Result<T, E>
+    where A: AuthBackend + ?Sized,
+          F: Fn(String) -> Fut,
+          Fut: Future<Output = std::result::Result<T, E>>,
+          E: From<FirebaseAuthError> + TokenRejection
+{
+    if auth.id_token_expires_soon() {
+        auth.refresh_id_token().await?;
+    }
+    let id_token = auth.id_token().cloned().ok_or(FirebaseAuthError::AuthDataMissing)?;
+    match request(id_token).await {
+        Err(err) if err.token_rejected() => {
+            auth.refresh_id_token().await?;
+            let id_token = auth.id_token().cloned().ok_or(FirebaseAuthError::AuthDataMissing)?;
+            request(id_token).await
+        },
+        result => result,
+    }
+}
+
+#[async_trait(?Send)]
+impl AuthBackend for FirebaseAuth {
+    fn clone_box(&self) -> Box<dyn AuthBackend> {
+        Box::new(self.clone())
+    }
+
+    fn id_token(&self) -> Option<&String> {
+        FirebaseAuth::id_token(self)
+    }
+
+    fn id_token_expires_at(&self) -> Option<Timestamp> {
+        FirebaseAuth::id_token_expires_at(self)
+    }
+
+    fn id_token_expires_soon(&self) -> bool {
+        FirebaseAuth::id_token_expires_soon(self)
+    }
+
+    fn refresh_token(&self) -> Option<&String> {
+        FirebaseAuth::refresh_token(self)
+    }
+
+    fn set_refresh_token(&mut self, refresh_token: Option<String>) {
+        FirebaseAuth::set_refresh_token(self, refresh_token)
+    }
+
+    fn set_id_token(&mut self, id_token: String, expires_at: Timestamp) {
+        FirebaseAuth::set_id_token(self, id_token, expires_at)
+    }
+
+    fn sign_in_too_old(&self) -> bool {
+        FirebaseAuth::sign_in_too_old(self)
+    }
+
+    fn sign_out(&mut self) {
+        FirebaseAuth::sign_out(self)
+    }
+
+    fn claims(&self) -> Result<IdTokenClaims> {
+        FirebaseAuth::claims(self)
+    }
+
+    async fn refresh_id_token(&mut self) -> Result<()> {
+        FirebaseAuth::refresh_id_token(self).await
+    }
+
+    async fn sign_up(&mut self, email: &str, password: &str) -> Result<UserId> {
+        FirebaseAuth::sign_up(self, email, password).await
+    }
+
+    async fn sign_in(&mut self, email: &str, password: &str) -> Result<UserId> {
+        FirebaseAuth::sign_in(self, email, password).await
+    }
+
+    async fn sign_in_anonymously(&mut self) -> Result<UserId> {
+        FirebaseAuth::sign_in_anonymously(self).await
+    }
+
+    async fn link_email_password(&mut self, email: &str, password: &str) -> Result<()> {
+        FirebaseAuth::link_email_password(self, email, password).await
+    }
+
+    async fn change_email(&mut self, new_email: &str) -> Result<()> {
+        FirebaseAuth::change_email(self, new_email).await
+    }
+
+    async fn change_password(&mut self, new_password: &str) -> Result<()> {
+        FirebaseAuth::change_password(self, new_password).await
+    }
+
+    async fn update_profile(&mut self, update: &ProfileUpdate) -> Result<()> {
+        FirebaseAuth::update_profile(self, update).await
+    }
+
+    async fn get_account_info(&mut self) -> Result<AccountInfo> {
+        FirebaseAuth::get_account_info(self).await
+    }
+
+    async fn send_email_verification(&mut self) -> Result<()> {
+        FirebaseAuth::send_email_verification(self).await
+    }
+
+    async fn delete_account(&mut self) -> Result<()> {
+        FirebaseAuth::delete_account(self).await
+    }
+}
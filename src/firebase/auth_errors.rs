@@ -1,3 +1,8 @@
+use std::sync::Arc;
+
+use super::retry::RetryableError;
+use super::types::FirebaseErrorDetails;
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum FirebaseAuthError {
     TokenExpired,
@@ -12,10 +17,158 @@ pub enum FirebaseAuthError {
     InvalidPassword,
     InvalidIdToken,
     WeakPassword,
+    InvalidEmail,
+    ExpiredOobCode,
+    InvalidOobCode,
+    CredentialTooOldLoginAgain,
     ParseError(url::ParseError),
-    NetworkError(String),
+    NetworkError(TransportError),
+    // Firebase responded with an error there is no specific variant for.
+    UnexpectedResponse(FirebaseApiError),
+    // Firebase responded with something that does not make sense, e.g. malformed JSON.
     FirebaseUnexpectedError(String),
     Internal(String),
+    // The request failed after being sent more than once, last with last_error. Either a transient
+    // error persisted through all the attempts the RetryPolicy allowed, or a retry failed for good.
+    GaveUp { attempts: u32, last_error: Box<FirebaseAuthError> },
+}
+
+// How the app should react to an error.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ErrorClass {
+    // The user can do something about it, e.g. pick another email or log in again.
+    UserFixable,
+    // Might go away by itself, so trying again later makes sense.
+    Retryable,
+    // A bug or misconfiguration, which only the developers can fix.
+    Fatal,
+}
+
+// An error response from Firebase, as sent.
+#[derive(Debug, PartialEq, Clone)]
+pub struct FirebaseApiError {
+    pub http_status: u16,
+    // Google's canonical error code, e.g. "INVALID_ARGUMENT".
+    pub status: Option<String>,
+    // A machine readable cause, e.g. "API_KEY_INVALID".
+    pub reason: Option<String>,
+    pub message: String,
+}
+
+impl FirebaseApiError {
+    // Overloaded and failing servers, as well as rate limiting, are temporary.
+    pub fn is_transient(&self) -> bool {
+        self.http_status == 429 || self.http_status >= 500
+    }
+}
+
+impl std::fmt::Display for FirebaseApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} (HTTP {}", self.message, self.http_status)?;
+        for detail in self.status.iter().chain(self.reason.iter()) {
+            write!(f, ", {}", detail)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl From<FirebaseErrorDetails> for FirebaseApiError {
+    fn from(details: FirebaseErrorDetails) -> Self {
+        Self {
+            http_status: details.code,
+            status: details.status,
+            reason: details.reason,
+            message: details.raw_message,
+        }
+    }
+}
+
+// A request that got no response at all, e.g. because the device is offline. Keeps the error it
+// failed with as the source; two of them are equal when their messages are.
+#[derive(Debug, Clone)]
+pub struct TransportError {
+    message: String,
+    source: Option<Arc<reqwest::Error>>,
+}
+
+impl TransportError {
+    pub fn new(message: &str) -> Self {
+        Self { message: message.to_string(), source: None }
+    }
+}
+
+impl PartialEq for TransportError {
+    fn eq(&self, other: &Self) -> bool {
+        self.message == other.message
+    }
+}
+
+impl std::fmt::Display for TransportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for TransportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source.as_ref().map(|err| err.as_ref() as &(dyn std::error::Error + 'static))
+    }
+}
+
+impl From<reqwest::Error> for TransportError {
+    fn from(err: reqwest::Error) -> Self {
+        Self { message: format!("{}", err), source: Some(Arc::new(err)) }
+    }
+}
+
+impl FirebaseAuthError {
+    // Whether the user's refresh token no longer works (e.g. it was revoked, or the account was
+    // disabled or deleted), so that they have to log in again.
+    pub fn ends_session(&self) -> bool {
+        matches!(self.last_attempt(),
+            FirebaseAuthError::TokenExpired | FirebaseAuthError::UserDisabled
+                | FirebaseAuthError::UserNotFound)
+    }
+
+    // The error the last attempt of the request failed with, for telling what went wrong.
+    pub fn last_attempt(&self) -> &Self {
+        match self {
+            FirebaseAuthError::GaveUp { last_error, .. } => last_error.last_attempt(),
+            err => err,
+        }
+    }
+
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            FirebaseAuthError::TokenExpired | FirebaseAuthError::UserNotFound
+                | FirebaseAuthError::AuthDataMissing | FirebaseAuthError::EmailExists
+                | FirebaseAuthError::EmailNotFound | FirebaseAuthError::InvalidPassword
+                | FirebaseAuthError::InvalidIdToken | FirebaseAuthError::WeakPassword
+                | FirebaseAuthError::InvalidEmail | FirebaseAuthError::ExpiredOobCode
+                | FirebaseAuthError::InvalidOobCode | FirebaseAuthError::CredentialTooOldLoginAgain =>
+                ErrorClass::UserFixable,
+            FirebaseAuthError::NetworkError(_) | FirebaseAuthError::TooManyAttemptsTryLater =>
+                ErrorClass::Retryable,
+            FirebaseAuthError::UnexpectedResponse(err) if err.is_transient() => ErrorClass::Retryable,
+            // Including UserDisabled, since only an administrator can enable the account again.
+            FirebaseAuthError::UserDisabled | FirebaseAuthError::ApiKeyInvalid
+                | FirebaseAuthError::OperationNotAllowed | FirebaseAuthError::ParseError(_)
+                | FirebaseAuthError::UnexpectedResponse(_)
+                | FirebaseAuthError::FirebaseUnexpectedError(_) | FirebaseAuthError::Internal(_) =>
+                ErrorClass::Fatal,
+            FirebaseAuthError::GaveUp { last_error, .. } => last_error.class(),
+        }
+    }
+}
+
+impl RetryableError for FirebaseAuthError {
+    fn is_transient(&self) -> bool {
+        self.class() == ErrorClass::Retryable
+    }
+
+    fn after_attempts(self, attempts: u32) -> Self {
+        FirebaseAuthError::GaveUp { attempts, last_error: Box::new(self) }
+    }
 }
 
 impl std::fmt::Display for FirebaseAuthError {
@@ -45,19 +198,42 @@ impl std::fmt::Display for FirebaseAuthError {
                 "The user's credential is no longer valid. The user must sign in again.".to_string(),
             FirebaseAuthError::WeakPassword =>
                 "The password must be 6 characters long or more.".to_string(),
+            FirebaseAuthError::InvalidEmail =>
+                "The email address is badly formatted.".to_string(),
+            FirebaseAuthError::ExpiredOobCode =>
+                "The link has expired. Request a new one.".to_string(),
+            FirebaseAuthError::InvalidOobCode =>
+                "The link is invalid. It might have been used already.".to_string(),
+            FirebaseAuthError::CredentialTooOldLoginAgain =>
+                "You logged in too long ago. Log in again to do this.".to_string(),
             FirebaseAuthError::ParseError(error) =>
                 format!("There was an error parsing the URL: {}", error),
-            FirebaseAuthError::NetworkError(msg) =>
-                format!("Network error occurred: {}", msg),
+            FirebaseAuthError::NetworkError(err) =>
+                format!("Network error occurred: {}", err),
+            FirebaseAuthError::UnexpectedResponse(err) =>
+                format!("Firebase reported an error: {}", err),
             FirebaseAuthError::FirebaseUnexpectedError(msg) =>
                 format!("Unknown response from Firebase was received: {}", msg),
             FirebaseAuthError::Internal(msg) =>
                 format!("Internal error happened: {}", msg),
+            FirebaseAuthError::GaveUp { attempts, last_error } =>
+                format!("{} (after {} attempts)", last_error, attempts),
         };
         write!(f, "{}", err_msg)
     }
 }
 
+impl std::error::Error for FirebaseAuthError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FirebaseAuthError::ParseError(err) => Some(err),
+            FirebaseAuthError::NetworkError(err) => Some(err),
+            FirebaseAuthError::GaveUp { last_error, .. } => Some(last_error.as_ref()),
+            _ => None,
+        }
+    }
+}
+
 impl From<url::ParseError> for FirebaseAuthError {
     fn from(error: url::ParseError) -> Self {
         Self::ParseError(error)
@@ -72,6 +248,6 @@ impl From<serde_json::Error> for FirebaseAuthError {
 
 impl From<reqwest::Error> for FirebaseAuthError {
     fn from(err: reqwest::Error) -> Self {
-        Self::NetworkError(format!("ReqwestError({})", err))
+        Self::NetworkError(err.into())
     }
 }
@@ -0,0 +1,393 @@
+use url::Url;
+use serde::{Deserialize, Serialize};
+
+use super::auth::FirebaseApiError;
+use super::retry::{Idempotency, RetryPolicy};
+use super::types::*;
+
+pub use super::database_errors::FirebaseDatabaseError;
+
+// The app always gets the database host from its config; these are for new().
+#[allow(dead_code)]
+static DEFAULT_URL_BASE: &str = "https://";
+#[allow(dead_code)]
+static DEFAULT_URL_SUFFIX: &str = ".firebaseio.com/";
+
+
+// Client for the Realtime Database REST API:
+// https://firebase.google.com/docs/reference/rest/database
+// Every path is relative to the root of the database and may, but does not have to, start with
+// a slash. Data is (de)serialized with serde, so any type that maps onto the JSON stored under the
+// path can be used.
+#[derive(Clone, Debug)]
+pub struct FirebaseDatabase {
+    // See the comment in FirebaseAuth - the same applies here.
+    client: reqwest::Client,
+    database_endpoint: String,
+
+    namespace: String,
+    id_token: Option<String>,
+    retry_policy: RetryPolicy,
+}
+
+pub type Result<T> = std::result::Result<T, FirebaseDatabaseError>;
+
+impl FirebaseDatabase {
+    #[allow(dead_code)]
+    pub fn new(namespace: &str) -> Result<Self> {
+        Ok(Self {
+            client: reqwest::Client::new(),
+            database_endpoint: format!("{}{}{}", DEFAULT_URL_BASE, namespace, DEFAULT_URL_SUFFIX),
+
+            namespace: namespace.to_owned(),
+            id_token: None,
+            retry_policy: RetryPolicy::default(),
+        })
+    }
+
+    // The emulator (and databases outside of the default region) are not reachable under
+    // <namespace>.firebaseio.com, so for those the namespace is passed in the query instead.
+    pub fn new_custom_url_base(namespace: &str, url_base: &str) -> Result<Self> {
+        Ok(Self {
+            client: reqwest::Client::new(),
+            database_endpoint: url_base.to_owned(),
+
+            namespace: namespace.to_owned(),
+            id_token: None,
+            retry_policy: RetryPolicy::default(),
+        })
+    }
+
+    // Sets the id token (as obtained from FirebaseAuth) used to authenticate all the following
+    // requests. Passing None makes the client unauthenticated again.
+    pub fn set_id_token(&mut self, id_token: Option<String>) {
+        self.id_token = id_token;
+    }
+
+    // How requests failing with transient errors are retried. Pushing new children is never
+    // retried, since it is not idempotent.
+    #[allow(dead_code)]
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = retry_policy;
+    }
+
+    // Reading data
+    // https://firebase.google.com/docs/reference/rest/database#section-get
+    // Returns None if there is no data under the given path.
+    pub async fn get<T>(&self, path: &str) -> Result<Option<T>>
+        where for<'de> T: Deserialize<'de>
+    {
+        let url = self.url_for(path, &[])?;
+        let resp_text = self.make_request(Idempotency::Idempotent, || self.client.get(url.as_str()))
+            .await?;
+        Ok(serde_json::from_str::<Option<T>>(&resp_text)?)
+    }
+
+    // Reading data along with its ETag, which identifies the version of the data for put_if_match
+    // https://firebase.google.com/docs/reference/rest/database#section-cond-etag
+    pub async fn get_with_etag<T>(&self, path: &str) -> Result<(Option<T>, String)>
+        where for<'de> T: Deserialize<'de>
+    {
+        let url = self.url_for(path, &[])?;
+        let (resp_text, etag) = self.make_request_with_etag(
+            Idempotency::Idempotent,
+            || self.client.get(url.as_str()).header("X-Firebase-ETag", "true")).await?;
+        let etag = etag.ok_or_else(|| FirebaseDatabaseError::FirebaseUnexpectedError(
+            "The response has no ETag.".to_string()))?;
+        Ok((serde_json::from_str::<Option<T>>(&resp_text)?, etag))
+    }
+
+    // Writing data, replacing anything that was under the path
+    // https://firebase.google.com/docs/reference/rest/database#section-put
+    pub async fn put<T: Serialize>(&self, path: &str, value: &T) -> Result<()> {
+        let url = self.url_for(path, &[("print", "silent")])?;
+        self.make_request(Idempotency::Idempotent, || self.client.put(url.as_str()).json(value))
+            .await?;
+        Ok(())
+    }
+
+    // Writing data only if it was not modified since it was read with the given ETag, failing with
+    // PreconditionFailed otherwise
+    // https://firebase.google.com/docs/reference/rest/database#section-conditional-requests
+    // Not retried: if the first attempt made it through, a retry would fail on the ETag it changed.
+    pub async fn put_if_match<T: Serialize>(&self, path: &str, value: &T, etag: &str) -> Result<()> {
+        let url = self.url_for(path, &[("print", "silent")])?;
+        self.make_request(
+            Idempotency::NotIdempotent,
+            || self.client.put(url.as_str()).header("if-match", etag).json(value)).await?;
+        Ok(())
+    }
+
+    // Updating only the children present in the value
+    // https://firebase.google.com/docs/reference/rest/database#section-patch
+    #[allow(dead_code)]
+    pub async fn patch<T: Serialize>(&self, path: &str, value: &T) -> Result<()> {
+        let url = self.url_for(path, &[("print", "silent")])?;
+        self.make_request(Idempotency::Idempotent, || self.client.patch(url.as_str()).json(value))
+            .await?;
+        Ok(())
+    }
+
+    // Pushing a new child with a key generated by Firebase
+    // https://firebase.google.com/docs/reference/rest/database#section-post
+    // Returns the generated key. Not used by the app, whose ids are generated locally (see new_id).
+    #[allow(dead_code)]
+    pub async fn post<T: Serialize>(&self, path: &str, value: &T) -> Result<String> {
+        let url = self.url_for(path, &[])?;
+        let resp_text = self.make_request(
+            Idempotency::NotIdempotent, || self.client.post(url.as_str()).json(value)).await?;
+        Ok(serde_json::from_str::<PostResponse>(&resp_text)?.name)
+    }
+
+    // Removing data
+    // https://firebase.google.com/docs/reference/rest/database#section-delete
+    pub async fn delete(&self, path: &str) -> Result<()> {
+        let url = self.url_for(path, &[("print", "silent")])?;
+        self.make_request(Idempotency::Idempotent, || self.client.delete(url.as_str())).await?;
+        Ok(())
+    }
+
+    fn url_for(&self, path: &str, params: &[(&str, &str)]) -> Result<Url> {
+        let id_token = self.id_token.as_ref().ok_or(FirebaseDatabaseError::AuthDataMissing)?;
+        let mut url = Url::parse_with_params(
+            &format!("{}{}.json", self.database_endpoint, path.trim_matches('/')),
+            &[("ns", self.namespace.as_str()), ("auth", id_token.as_str())]
+        )?;
+        url.query_pairs_mut().extend_pairs(params);
+        Ok(url)
+    }
+
+    // Sends the request built by build_request, retrying according to the retry policy.
+    async fn make_request<B>(&self, idempotency: Idempotency, build_request: B) -> Result<String>
+        where B: Fn() -> reqwest::RequestBuilder
+    {
+        Ok(self.make_request_with_etag(idempotency, build_request).await?.0)
+    }
+
+    // Like make_request, but also returns the ETag header of the response, if there was one.
+    async fn make_request_with_etag<B>(&self, idempotency: Idempotency, build_request: B)
+        -> Result<(String, Option<String>)>
+        where B: Fn() -> reqwest::RequestBuilder
+    {
+        self.retry_policy.run(idempotency, || send_request(build_request())).await
+    }
+}
+
+// Returns the text of the response - for requests sent with print=silent it is empty - and its
+// ETag header.
+async fn send_request(request: reqwest::RequestBuilder) -> Result<(String, Option<String>)> {
+    let response = request.send().await?;
+    let status = response.status();
+    let etag = response.headers().get("ETag")
+        .and_then(|etag| etag.to_str().ok())
+        .map(|etag| etag.to_string());
+    let resp_text = response.text().await?;
+    if status.is_success() {
+        return Ok((resp_text, etag));
+    }
+    // Errors from proxies in front of the database (e.g. when it is unavailable) are not JSON.
+    let message = serde_json::from_str::<FirebaseDatabaseErrorResponse>(&resp_text)
+        .map(|response| response.error)
+        .unwrap_or(resp_text);
+    Err(match status.as_u16() {
+        400 => FirebaseDatabaseError::BadRequest(message),
+        401 if message.to_lowercase().contains("expired") => FirebaseDatabaseError::TokenExpired,
+        401 => FirebaseDatabaseError::PermissionDenied,
+        404 => FirebaseDatabaseError::NotFound,
+        412 => FirebaseDatabaseError::PreconditionFailed,
+        503 => FirebaseDatabaseError::ServiceUnavailable,
+        http_status => FirebaseDatabaseError::UnexpectedResponse(FirebaseApiError {
+            http_status,
+            status: None,
+            reason: None,
+            message,
+        }),
+    })
+}
+
+// === Request/Response types ===
+
+#[allow(dead_code)]
+#[derive(Debug, PartialEq, Deserialize)]
+struct PostResponse {
+    name: String,
+}
+
+// === Request/Response types ===
+
+#[cfg(test)]
+mod firebase_database_tests {
+    use super::*;
+    use k9::assert_ok;
+    use galvanic_assert::matchers::*;
+    use galvanic_assert::matchers::variant::*;
+
+    #[test]
+    fn test_default_addresses() {
+        assert_ok!(FirebaseDatabase::new("namespace"));
+    }
+
+    #[test]
+    fn test_url_for_path() {
+        let mut db = FirebaseDatabase::new("namespace").unwrap();
+        db.set_id_token(Some("token".to_string()));
+        assert_eq!(
+            db.url_for("/users/user_id/", &[("print", "silent")]).unwrap().as_str(),
+            "https://namespace.firebaseio.com/users/user_id.json?ns=namespace&auth=token&print=silent"
+        );
+    }
+
+    #[test]
+    fn test_unauthenticated() {
+        let db = FirebaseDatabase::new("namespace").unwrap();
+        assert_that!(
+            &db.url_for("users", &[]),
+            maybe_err(eq(FirebaseDatabaseError::AuthDataMissing))
+        );
+    }
+
+    fn mocked_database() -> FirebaseDatabase {
+        let mut db = FirebaseDatabase::new_custom_url_base(
+            "namespace", &format!("{}/", mockito::server_url())).unwrap();
+        db.set_id_token(Some("token".to_string()));
+        db.set_retry_policy(RetryPolicy {
+            max_attempts: 3,
+            initial_delay: std::time::Duration::from_millis(1),
+            max_delay: std::time::Duration::from_millis(2),
+            multiplier: 2,
+        });
+        db
+    }
+
+    fn unavailable_mock(method: &str, expected_requests: usize) -> mockito::Mock {
+        mockito::mock(method, "/items.json")
+            .match_query(mockito::Matcher::Any)
+            .with_status(503)
+            .with_header("content-type", "text/html")
+            .with_body("<html><body>Service Unavailable</body></html>")
+            .expect(expected_requests)
+            .create()
+    }
+
+    #[test]
+    fn test_retries_idempotent_requests() {
+        let mock = unavailable_mock("GET", 3);
+        assert_that!(
+            &tokio_test::block_on(mocked_database().get::<u32>("items")),
+            maybe_err(eq(FirebaseDatabaseError::GaveUp {
+                attempts: 3,
+                last_error: Box::new(FirebaseDatabaseError::ServiceUnavailable),
+            }))
+        );
+        mock.assert();
+    }
+
+    #[test]
+    fn test_get_with_etag() {
+        let mock = mockito::mock("GET", "/items.json")
+            .match_query(mockito::Matcher::Any)
+            .match_header("X-Firebase-ETag", "true")
+            .with_header("ETag", "etag1")
+            .with_body("3")
+            .create();
+        assert_eq!(
+            tokio_test::block_on(mocked_database().get_with_etag::<u32>("items")).unwrap(),
+            (Some(3), "etag1".to_string())
+        );
+        mock.assert();
+    }
+
+    #[test]
+    fn test_put_if_match_fails_on_modified_data() {
+        let mock = mockito::mock("PUT", "/items.json")
+            .match_query(mockito::Matcher::Any)
+            .match_header("if-match", "etag1")
+            .with_status(412)
+            .with_header("ETag", "etag2")
+            .with_body(r#"{"error": "ETag mismatch"}"#)
+            .expect(1)
+            .create();
+        assert_that!(
+            &tokio_test::block_on(mocked_database().put_if_match("items", &4, "etag1")),
+            maybe_err(eq(FirebaseDatabaseError::PreconditionFailed))
+        );
+        mock.assert();
+    }
+
+    #[test]
+    fn test_does_not_retry_pushing() {
+        let mock = unavailable_mock("POST", 1);
+        assert_that!(
+            &tokio_test::block_on(mocked_database().post("items", &1)),
+            maybe_err(eq(FirebaseDatabaseError::ServiceUnavailable))
+        );
+        mock.assert();
+    }
+}
+
+#[cfg(test)]
+mod firebase_database_local_emulator_tests {
+    // These tests work only with local auth and database emulators running.
+    use super::*;
+    use crate::config::parse_from_embedded_file;
+    use crate::firebase::auth::FirebaseAuth;
+    use k9::assert_ok;
+    use galvanic_assert::matchers::*;
+    use galvanic_assert::matchers::variant::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct TestItem {
+        name: String,
+        count: u32,
+    }
+
+    fn authenticated_database(email: &str) -> FirebaseDatabase {
+        let config = parse_from_embedded_file().unwrap();
+        let mut auth = FirebaseAuth::new_custom_url_base("api_key", &config.auth_host).unwrap();
+        assert_ok!(tokio_test::block_on(auth.sign_up(email, "password")));
+        let mut db = FirebaseDatabase::new_custom_url_base(
+            &config.database_namespace, &config.database_host).unwrap();
+        db.set_id_token(auth.id_token().cloned());
+        db
+    }
+
+    #[test]
+    fn test_put_get_delete() {
+        let db = authenticated_database("db_user1@example.com");
+        let item = TestItem { name: "socks".to_string(), count: 3 };
+
+        assert_ok!(tokio_test::block_on(db.put("test/put_get_delete", &item)));
+        assert_that!(
+            &tokio_test::block_on(db.get::<TestItem>("test/put_get_delete")).unwrap(),
+            maybe_some(eq(TestItem { name: "socks".to_string(), count: 3 }))
+        );
+
+        assert_ok!(tokio_test::block_on(db.delete("test/put_get_delete")));
+        assert_eq!(tokio_test::block_on(db.get::<TestItem>("test/put_get_delete")).unwrap(), None);
+    }
+
+    #[test]
+    fn test_patch() {
+        let db = authenticated_database("db_user2@example.com");
+        let item = TestItem { name: "socks".to_string(), count: 3 };
+
+        assert_ok!(tokio_test::block_on(db.put("test/patch", &item)));
+        assert_ok!(tokio_test::block_on(db.patch("test/patch", &serde_json::json!({"count": 5}))));
+        assert_that!(
+            &tokio_test::block_on(db.get::<TestItem>("test/patch")).unwrap(),
+            maybe_some(eq(TestItem { name: "socks".to_string(), count: 5 }))
+        );
+    }
+
+    #[test]
+    fn test_post() {
+        let db = authenticated_database("db_user3@example.com");
+        let item = TestItem { name: "towel".to_string(), count: 1 };
+
+        let key = tokio_test::block_on(db.post("test/post", &item)).unwrap();
+        assert_that!(
+            &tokio_test::block_on(db.get::<TestItem>(&format!("test/post/{}", key))).unwrap(),
+            maybe_some(eq(TestItem { name: "towel".to_string(), count: 1 }))
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use super::auth::{ErrorClass, FirebaseApiError, FirebaseAuthError, TokenRejection, TransportError};
+use super::retry::RetryableError;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum FirebaseDatabaseError {
+    AuthDataMissing,
+    PermissionDenied,
+    TokenExpired,
+    NotFound,
+    PreconditionFailed,
+    ServiceUnavailable,
+    BadRequest(String),
+    ParseError(url::ParseError),
+    NetworkError(TransportError),
+    // The database responded with an error there is no specific variant for.
+    UnexpectedResponse(FirebaseApiError),
+    // The database responded with something that does not make sense, e.g. malformed JSON.
+    FirebaseUnexpectedError(String),
+    // Getting a fresh id token for the request failed.
+    Auth(FirebaseAuthError),
+    // The request failed after being sent more than once, last with last_error. Either a transient
+    // error persisted through all the attempts the RetryPolicy allowed, or a retry failed for good.
+    GaveUp { attempts: u32, last_error: Box<FirebaseDatabaseError> },
+}
+
+impl FirebaseDatabaseError {
+    // The error the last attempt of the request failed with, for telling what went wrong.
+    pub fn last_attempt(&self) -> &Self {
+        match self {
+            FirebaseDatabaseError::GaveUp { last_error, .. } => last_error.last_attempt(),
+            err => err,
+        }
+    }
+
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            // The user can log in again, or reload the data someone else modified.
+            FirebaseDatabaseError::AuthDataMissing | FirebaseDatabaseError::TokenExpired
+                | FirebaseDatabaseError::PreconditionFailed =>
+                ErrorClass::UserFixable,
+            FirebaseDatabaseError::NetworkError(_) | FirebaseDatabaseError::ServiceUnavailable =>
+                ErrorClass::Retryable,
+            FirebaseDatabaseError::UnexpectedResponse(err) if err.is_transient() =>
+                ErrorClass::Retryable,
+            FirebaseDatabaseError::PermissionDenied | FirebaseDatabaseError::NotFound
+                | FirebaseDatabaseError::BadRequest(_) | FirebaseDatabaseError::ParseError(_)
+                | FirebaseDatabaseError::UnexpectedResponse(_)
+                | FirebaseDatabaseError::FirebaseUnexpectedError(_) =>
+                ErrorClass::Fatal,
+            FirebaseDatabaseError::Auth(err) => err.class(),
+            FirebaseDatabaseError::GaveUp { last_error, .. } => last_error.class(),
+        }
+    }
+}
+
+impl std::fmt::Display for FirebaseDatabaseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let err_msg = match self {
+            FirebaseDatabaseError::AuthDataMissing =>
+                "Attempting to access the database without authenticating. Log in first.".to_string(),
+            FirebaseDatabaseError::PermissionDenied =>
+                "The user is not allowed to access this data.".to_string(),
+            FirebaseDatabaseError::TokenExpired =>
+                "User's credentials are no longer valid. They must log in again.".to_string(),
+            FirebaseDatabaseError::NotFound =>
+                "The requested database was not found.".to_string(),
+            FirebaseDatabaseError::PreconditionFailed =>
+                "The data was modified by someone else in the meantime.".to_string(),
+            FirebaseDatabaseError::ServiceUnavailable =>
+                "The database is temporarily unavailable. Try again later.".to_string(),
+            FirebaseDatabaseError::BadRequest(msg) =>
+                format!("The database rejected the request: {}", msg),
+            FirebaseDatabaseError::ParseError(error) =>
+                format!("There was an error parsing the URL: {}", error),
+            FirebaseDatabaseError::NetworkError(err) =>
+                format!("Network error occurred: {}", err),
+            FirebaseDatabaseError::UnexpectedResponse(err) =>
+                format!("The database reported an error: {}", err),
+            FirebaseDatabaseError::FirebaseUnexpectedError(msg) =>
+                format!("Unknown response from Firebase was received: {}", msg),
+            FirebaseDatabaseError::Auth(err) => format!("{}", err),
+            FirebaseDatabaseError::GaveUp { attempts, last_error } =>
+                format!("{} (after {} attempts)", last_error, attempts),
+        };
+        write!(f, "{}", err_msg)
+    }
+}
+
+impl std::error::Error for FirebaseDatabaseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FirebaseDatabaseError::ParseError(err) => Some(err),
+            FirebaseDatabaseError::NetworkError(err) => Some(err),
+            FirebaseDatabaseError::Auth(err) => Some(err),
+            FirebaseDatabaseError::GaveUp { last_error, .. } => Some(last_error.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<url::ParseError> for FirebaseDatabaseError {
+    fn from(error: url::ParseError) -> Self {
+        Self::ParseError(error)
+    }
+}
+
+impl From<serde_json::Error> for FirebaseDatabaseError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::FirebaseUnexpectedError(format!("SerdeParseError({})", err))
+    }
+}
+
+impl From<reqwest::Error> for FirebaseDatabaseError {
+    fn from(err: reqwest::Error) -> Self {
+        Self::NetworkError(err.into())
+    }
+}
+
+impl From<FirebaseAuthError> for FirebaseDatabaseError {
+    fn from(err: FirebaseAuthError) -> Self {
+        Self::Auth(err)
+    }
+}
+
+impl TokenRejection for FirebaseDatabaseError {
+    fn token_rejected(&self) -> bool {
+        *self.last_attempt() == FirebaseDatabaseError::TokenExpired
+    }
+}
+
+impl RetryableError for FirebaseDatabaseError {
+    fn is_transient(&self) -> bool {
+        self.class() == ErrorClass::Retryable
+    }
+
+    fn after_attempts(self, attempts: u32) -> Self {
+        FirebaseDatabaseError::GaveUp { attempts, last_error: Box::new(self) }
+    }
+}
@@ -0,0 +1,122 @@
+// Id tokens are JWTs, whose payload holds claims about the user. The backend verifies the
+// signature; the client only needs to read the claims, so the signature is ignored here.
+// https://firebase.google.com/docs/auth/admin/verify-id-tokens#verify_id_tokens_using_a_third-party_jwt_library
+
+use serde::{Deserialize};
+
+use super::auth::{FirebaseAuthError, UserId};
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct IdTokenClaims {
+    // The subject of the token is the user's id.
+    #[serde(rename = "sub")]
+    pub user_id: UserId,
+    // Missing for anonymous users.
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub email_verified: bool,
+    // Seconds since the Unix epoch, when the user last signed in (not when the token was
+    // refreshed).
+    pub auth_time: u64,
+    // Seconds since the Unix epoch, when the token expires.
+    pub exp: u64,
+    #[serde(default)]
+    pub firebase: FirebaseClaims,
+}
+
+// Firebase specific claims, under the "firebase" key.
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct FirebaseClaims {
+    // E.g. "password" or "anonymous".
+    pub sign_in_provider: Option<String>,
+}
+
+impl IdTokenClaims {
+    pub fn is_anonymous(&self) -> bool {
+        self.firebase.sign_in_provider.as_deref() == Some("anonymous")
+    }
+}
+
+pub fn decode_claims(id_token: &str) -> Result<IdTokenClaims, FirebaseAuthError> {
+    let malformed = |reason: String| {
+        FirebaseAuthError::FirebaseUnexpectedError(format!("Malformed id token: {}", reason))
+    };
+    let payload = match id_token.split('.').collect::<Vec<_>>()[..] {
+        [_header, payload, _signature] => payload,
+        _ => return Err(malformed("expected three parts".to_string())),
+    };
+    let decoded = base64::decode_config(payload, base64::URL_SAFE_NO_PAD)
+        .map_err(|err| malformed(format!("{}", err)))?;
+    serde_json::from_slice::<IdTokenClaims>(&decoded).map_err(|err| malformed(format!("{}", err)))
+}
+
+#[cfg(test)]
+mod id_token_tests {
+    use super::*;
+
+    fn token_with_payload(payload: &str) -> String {
+        format!(
+            "{}.{}.signature",
+            base64::encode_config(r#"{"alg":"RS256","typ":"JWT"}"#, base64::URL_SAFE_NO_PAD),
+            base64::encode_config(payload, base64::URL_SAFE_NO_PAD))
+    }
+
+    #[test]
+    fn decodes_claims() {
+        let id_token = token_with_payload(r#"{
+            "iss": "https://securetoken.google.com/packing-checklist-3879",
+            "aud": "packing-checklist-3879",
+            "auth_time": 1665000000,
+            "user_id": "aBcD1234",
+            "sub": "aBcD1234",
+            "iat": 1665003600,
+            "exp": 1665007200,
+            "email": "user@example.com",
+            "email_verified": true,
+            "firebase": { "identities": {}, "sign_in_provider": "password" }
+        }"#);
+        assert_eq!(
+            decode_claims(&id_token).unwrap(),
+            IdTokenClaims {
+                user_id: "aBcD1234".to_string(),
+                email: Some("user@example.com".to_string()),
+                email_verified: true,
+                auth_time: 1665000000,
+                exp: 1665007200,
+                firebase: FirebaseClaims { sign_in_provider: Some("password".to_string()) },
+            }
+        );
+    }
+
+    #[test]
+    fn recognizes_anonymous_users() {
+        let id_token = token_with_payload(
+            r#"{"sub": "guest", "auth_time": 1, "exp": 2, "firebase": {"sign_in_provider": "anonymous"}}"#);
+        assert!(decode_claims(&id_token).unwrap().is_anonymous());
+        let id_token = token_with_payload(r#"{"sub": "user", "auth_time": 1, "exp": 2}"#);
+        assert!(!decode_claims(&id_token).unwrap().is_anonymous());
+    }
+
+    #[test]
+    fn decodes_claims_without_email() {
+        let id_token = token_with_payload(r#"{"sub": "anonymous", "auth_time": 1, "exp": 2}"#);
+        let claims = decode_claims(&id_token).unwrap();
+        assert_eq!(claims.email, None);
+        assert!(!claims.email_verified);
+    }
+
+    #[test]
+    fn rejects_malformed_tokens() {
+        let malformed = [
+            "not a token".to_string(),
+            "a.!!!.c".to_string(),
+            token_with_payload(r#"{"sub": "user"}"#),
+        ];
+        for id_token in &malformed {
+            assert!(
+                matches!(decode_claims(id_token), Err(FirebaseAuthError::FirebaseUnexpectedError(_))),
+                "{} should be rejected", id_token);
+        }
+    }
+}
@@ -0,0 +1,563 @@
+// Fake of Firebase Auth that keeps accounts in memory, for tests and offline development. It
+// reports the same errors Firebase does (compare the emulator tests in auth) and issues id tokens
+// whose claims decode like real ones, except that they are not signed.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::auth::{AccountInfo, FirebaseAuthError, ProfileAttribute, ProfileUpdate, Result, UserId};
+use super::auth_backend::AuthBackend;
+use crate::types::{now, Timestamp};
+
+const ID_TOKEN_LIFETIME_SECS: u64 = 3600;
+// Firebase refuses sensitive operations when the user signed in longer ago than this.
+const RECENT_SIGN_IN_SECS: u64 = 5 * 60;
+const MIN_PASSWORD_LENGTH: usize = 6;
+
+thread_local! {
+    // Accounts of the app running with Config::offline_auth, see InMemoryAuth::shared.
+    static SHARED_ACCOUNTS: Rc<RefCell<Accounts>> = Rc::default();
+}
+
+#[derive(Clone, Debug)]
+struct Account {
+    // Both are None for anonymous users.
+    email: Option<String>,
+    password: Option<String>,
+    email_verified: bool,
+    display_name: Option<String>,
+    photo_url: Option<String>,
+    disabled: bool,
+}
+
+#[derive(Clone, Debug)]
+struct Session {
+    user_id: UserId,
+    // Seconds since the Unix epoch.
+    auth_time: u64,
+}
+
+#[derive(Debug, Default)]
+struct Accounts {
+    users: HashMap<UserId, Account>,
+    // By refresh token.
+    sessions: HashMap<String, Session>,
+    // Makes user ids and refresh tokens unique.
+    counter: u64,
+}
+
+impl Accounts {
+    fn next_id(&mut self, prefix: &str) -> String {
+        self.counter += 1;
+        format!("{}{}", prefix, self.counter)
+    }
+
+    fn email_taken(&self, email: &str) -> bool {
+        self.users.values().any(|account| account.email.as_deref() == Some(email))
+    }
+
+    // Signs the user out everywhere, like Firebase does on password changes.
+    fn revoke_sessions(&mut self, user_id: &UserId) {
+        self.sessions.retain(|_, session| session.user_id != *user_id);
+    }
+}
+
+// Clones share the accounts, just like copies of FirebaseAuth talk to the same backend, but each
+// clone has a session of its own.
+#[derive(Clone, Debug, Default)]
+pub struct InMemoryAuth {
+    accounts: Rc<RefCell<Accounts>>,
+    id_token: Option<String>,
+    id_token_expires_at: Option<Timestamp>,
+    refresh_token: Option<String>,
+}
+
+impl InMemoryAuth {
+    // Backend with accounts of its own, for tests.
+    #[cfg(test)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Backend whose accounts are shared with all the other shared ones, so that the app's
+    // UserContextProvider and Stores see the same users for as long as the page is open.
+    pub fn shared() -> Self {
+        Self {
+            accounts: SHARED_ACCOUNTS.with(Rc::clone),
+            ..Self::default()
+        }
+    }
+
+    // Like an administrator disabling the account in the Firebase console.
+    #[cfg(test)]
+    pub fn disable_user(&self, user_id: &UserId) {
+        if let Some(account) = self.accounts.borrow_mut().users.get_mut(user_id) {
+            account.disabled = true;
+        }
+    }
+
+    fn start_session(&mut self, user_id: &UserId) -> Result<()> {
+        let refresh_token = {
+            let mut accounts = self.accounts.borrow_mut();
+            let refresh_token = accounts.next_id("refresh-token-");
+            let session = Session { user_id: user_id.clone(), auth_time: now() / 1000 };
+            accounts.sessions.insert(refresh_token.clone(), session);
+            refresh_token
+        };
+        self.set_refresh_token(Some(refresh_token));
+        self.issue_id_token()
+    }
+
+    // Exchanges the refresh token for a new id token, reflecting the current state of the account.
+    fn issue_id_token(&mut self) -> Result<()> {
+        let accounts = Rc::clone(&self.accounts);
+        let accounts = accounts.borrow();
+        let refresh_token = self.refresh_token.as_ref().ok_or(FirebaseAuthError::AuthDataMissing)?;
+        let session = accounts.sessions.get(refresh_token).ok_or(FirebaseAuthError::TokenExpired)?;
+        let account = accounts.users.get(&session.user_id).ok_or(FirebaseAuthError::UserNotFound)?;
+        if account.disabled {
+            return Err(FirebaseAuthError::UserDisabled);
+        }
+        let expires_at = now() / 1000 + ID_TOKEN_LIFETIME_SECS;
+        let claims = json!({
+            "sub": session.user_id,
+            "email": account.email,
+            "email_verified": account.email_verified,
+            "auth_time": session.auth_time,
+            "exp": expires_at,
+            "firebase": {
+                "sign_in_provider": if account.password.is_some() { "password" } else { "anonymous" },
+            },
+        });
+        self.id_token = Some(format!(
+            "{}.{}.unsigned",
+            base64::encode_config(r#"{"alg":"none","typ":"JWT"}"#, base64::URL_SAFE_NO_PAD),
+            base64::encode_config(claims.to_string(), base64::URL_SAFE_NO_PAD)));
+        self.id_token_expires_at = Some(expires_at * 1000);
+        Ok(())
+    }
+
+    // The user the backend would authenticate a request with the id token as.
+    fn current_user(&mut self) -> Result<UserId> {
+        if self.id_token.is_none() {
+            self.issue_id_token()?;
+        }
+        let user_id = self.claims()?.user_id;
+        match self.accounts.borrow().users.get(&user_id) {
+            Some(account) if account.disabled => Err(FirebaseAuthError::UserDisabled),
+            Some(_) => Ok(user_id),
+            None => Err(FirebaseAuthError::UserNotFound),
+        }
+    }
+
+    fn require_recent_sign_in(&self) -> Result<()> {
+        if self.claims()?.auth_time + RECENT_SIGN_IN_SECS < now() / 1000 {
+            return Err(FirebaseAuthError::CredentialTooOldLoginAgain);
+        }
+        Ok(())
+    }
+
+    fn update_account<F>(&self, user_id: &UserId, update: F) -> Result<()>
+        where F: FnOnce(&mut Account)
+    {
+        let mut accounts = self.accounts.borrow_mut();
+        let account = accounts.users.get_mut(user_id).ok_or(FirebaseAuthError::UserNotFound)?;
+        update(account);
+        Ok(())
+    }
+
+    // Checks a new email and password the way Firebase does.
+    fn check_credentials(&self, email: Option<&str>, password: Option<&str>) -> Result<()> {
+        if let Some(email) = email {
+            let well_formed = matches!(email.split_once('@'),
+                Some((local, domain)) if !local.is_empty() && !domain.is_empty());
+            if !well_formed {
+                return Err(FirebaseAuthError::InvalidEmail);
+            }
+            if self.accounts.borrow().email_taken(email) {
+                return Err(FirebaseAuthError::EmailExists);
+            }
+        }
+        match password {
+            Some(password) if password.chars().count() < MIN_PASSWORD_LENGTH =>
+                Err(FirebaseAuthError::WeakPassword),
+            _ => Ok(()),
+        }
+    }
+
+    fn create_account(&mut self, email: Option<&str>, password: Option<&str>) -> Result<UserId> {
+        let user_id = {
+            let mut accounts = self.accounts.borrow_mut();
+            let user_id = accounts.next_id("user");
+            accounts.users.insert(user_id.clone(), Account {
+                email: email.map(|email| email.to_string()),
+                password: password.map(|password| password.to_string()),
+                email_verified: false,
+                display_name: None,
+                photo_url: None,
+                disabled: false,
+            });
+            user_id
+        };
+        self.start_session(&user_id)?;
+        Ok(user_id)
+    }
+}
+
+#[async_trait(?Send)]
+impl AuthBackend for InMemoryAuth {
+    fn clone_box(&self) -> Box<dyn AuthBackend> {
+        Box::new(self.clone())
+    }
+
+    fn id_token(&self) -> Option<&String> {
+        self.id_token.as_ref()
+    }
+
+    fn id_token_expires_at(&self) -> Option<Timestamp> {
+        self.id_token_expires_at
+    }
+
+    fn id_token_expires_soon(&self) -> bool {
+        match self.id_token_expires_at {
+            Some(expires_at) if self.id_token.is_some() => now() >= expires_at,
+            _ => true,
+        }
+    }
+
+    fn refresh_token(&self) -> Option<&String> {
+        self.refresh_token.as_ref()
+    }
+
+    fn set_refresh_token(&mut self, refresh_token: Option<String>) {
+        self.refresh_token = refresh_token;
+        self.id_token = None;
+        self.id_token_expires_at = None;
+    }
+
+    fn set_id_token(&mut self, id_token: String, expires_at: Timestamp) {
+        self.id_token = Some(id_token);
+        self.id_token_expires_at = Some(expires_at);
+    }
+
+    fn sign_in_too_old(&self) -> bool {
+        self.require_recent_sign_in().is_err()
+    }
+
+    fn sign_out(&mut self) {
+        self.set_refresh_token(None);
+    }
+
+    async fn refresh_id_token(&mut self) -> Result<()> {
+        self.issue_id_token()
+    }
+
+    async fn sign_up(&mut self, email: &str, password: &str) -> Result<UserId> {
+        self.check_credentials(Some(email), Some(password))?;
+        self.create_account(Some(email), Some(password))
+    }
+
+    async fn sign_in(&mut self, email: &str, password: &str) -> Result<UserId> {
+        let user_id = {
+            let accounts = self.accounts.borrow();
+            let (user_id, account) = accounts.users.iter()
+                .find(|(_, account)| account.email.as_deref() == Some(email))
+                .ok_or(FirebaseAuthError::EmailNotFound)?;
+            if account.password.as_deref() != Some(password) {
+                return Err(FirebaseAuthError::InvalidPassword);
+            }
+            if account.disabled {
+                return Err(FirebaseAuthError::UserDisabled);
+            }
+            user_id.clone()
+        };
+        self.start_session(&user_id)?;
+        Ok(user_id)
+    }
+
+    async fn sign_in_anonymously(&mut self) -> Result<UserId> {
+        self.create_account(None, None)
+    }
+
+    async fn link_email_password(&mut self, email: &str, password: &str) -> Result<()> {
+        let user_id = self.current_user()?;
+        self.check_credentials(Some(email), Some(password))?;
+        self.update_account(&user_id, |account| {
+            account.email = Some(email.to_string());
+            account.password = Some(password.to_string());
+        })?;
+        self.issue_id_token()
+    }
+
+    async fn change_email(&mut self, new_email: &str) -> Result<()> {
+        let user_id = self.current_user()?;
+        self.require_recent_sign_in()?;
+        self.check_credentials(Some(new_email), None)?;
+        self.update_account(&user_id, |account| {
+            account.email = Some(new_email.to_string());
+            account.email_verified = false;
+        })?;
+        self.issue_id_token()
+    }
+
+    async fn change_password(&mut self, new_password: &str) -> Result<()> {
+        let user_id = self.current_user()?;
+        self.require_recent_sign_in()?;
+        self.check_credentials(None, Some(new_password))?;
+        self.update_account(&user_id, |account| account.password = Some(new_password.to_string()))?;
+        self.accounts.borrow_mut().revoke_sessions(&user_id);
+        self.start_session(&user_id)
+    }
+
+    async fn update_profile(&mut self, update: &ProfileUpdate) -> Result<()> {
+        let user_id = self.current_user()?;
+        self.update_account(&user_id, |account| {
+            if update.display_name.is_some() {
+                account.display_name = update.display_name.clone();
+            }
+            if update.photo_url.is_some() {
+                account.photo_url = update.photo_url.clone();
+            }
+            for attribute in &update.delete {
+                match attribute {
+                    ProfileAttribute::DisplayName => account.display_name = None,
+                    ProfileAttribute::PhotoUrl => account.photo_url = None,
+                }
+            }
+        })
+    }
+
+    async fn get_account_info(&mut self) -> Result<AccountInfo> {
+        let user_id = self.current_user()?;
+        let accounts = self.accounts.borrow();
+        let account = accounts.users.get(&user_id).ok_or(FirebaseAuthError::UserNotFound)?;
+        Ok(AccountInfo {
+            user_id,
+            email: account.email.clone(),
+            email_verified: account.email_verified,
+            display_name: account.display_name.clone(),
+            photo_url: account.photo_url.clone(),
+        })
+    }
+
+    // There is no mailbox to send the link to, so the email counts as verified right away. Like
+    // with Firebase, the id token says so only once it is refreshed.
+    async fn send_email_verification(&mut self) -> Result<()> {
+        let user_id = self.current_user()?;
+        self.update_account(&user_id, |account| {
+            if account.email.is_some() {
+                account.email_verified = true;
+            }
+        })
+    }
+
+    async fn delete_account(&mut self) -> Result<()> {
+        let user_id = self.current_user()?;
+        self.require_recent_sign_in()?;
+        {
+            let mut accounts = self.accounts.borrow_mut();
+            accounts.users.remove(&user_id);
+            accounts.revoke_sessions(&user_id);
+        }
+        self.sign_out();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod in_memory_auth_tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::firebase::auth_backend;
+    use k9::assert_ok;
+    use galvanic_assert::matchers::*;
+    use galvanic_assert::matchers::variant::*;
+
+    #[test]
+    fn test_new_user_flow() {
+        let mut auth = InMemoryAuth::new();
+        let user_id = tokio_test::block_on(auth.sign_up("user@example.com", "password")).unwrap();
+        let claims = auth.claims().unwrap();
+        assert_eq!(claims.user_id, user_id);
+        assert_that!(&claims.email, maybe_some(eq("user@example.com".to_string())));
+        assert!(!claims.is_anonymous());
+        assert_that!(
+            &tokio_test::block_on(auth.sign_in("user@example.com", "password")),
+            maybe_ok(eq(user_id))
+        );
+        assert_ok!(tokio_test::block_on(auth.refresh_id_token()));
+        assert_that!(
+            &tokio_test::block_on(auth.sign_up("user@example.com", "password")),
+            maybe_err(eq(FirebaseAuthError::EmailExists))
+        );
+    }
+
+    #[test]
+    fn test_sign_up_checks_credentials() {
+        let mut auth = InMemoryAuth::new();
+        assert_that!(
+            &tokio_test::block_on(auth.sign_up("not an email", "password")),
+            maybe_err(eq(FirebaseAuthError::InvalidEmail))
+        );
+        assert_that!(
+            &tokio_test::block_on(auth.sign_up("user@example.com", "short")),
+            maybe_err(eq(FirebaseAuthError::WeakPassword))
+        );
+    }
+
+    #[test]
+    fn test_sign_in_errors() {
+        let mut auth = InMemoryAuth::new();
+        let user_id = tokio_test::block_on(auth.sign_up("user@example.com", "password")).unwrap();
+        assert_that!(
+            &tokio_test::block_on(auth.sign_in("nobody@example.com", "password")),
+            maybe_err(eq(FirebaseAuthError::EmailNotFound))
+        );
+        assert_that!(
+            &tokio_test::block_on(auth.sign_in("user@example.com", "wrong_password")),
+            maybe_err(eq(FirebaseAuthError::InvalidPassword))
+        );
+
+        auth.disable_user(&user_id);
+        assert_that!(
+            &tokio_test::block_on(auth.sign_in("user@example.com", "password")),
+            maybe_err(eq(FirebaseAuthError::UserDisabled))
+        );
+        assert_that!(
+            &tokio_test::block_on(auth.refresh_id_token()),
+            maybe_err(eq(FirebaseAuthError::UserDisabled))
+        );
+    }
+
+    #[test]
+    fn test_password_change_revokes_other_sessions() {
+        let mut auth = InMemoryAuth::new();
+        assert_ok!(tokio_test::block_on(auth.sign_up("user@example.com", "password")));
+        let mut other_device = auth.clone();
+        assert_ok!(tokio_test::block_on(other_device.sign_in("user@example.com", "password")));
+
+        assert_ok!(tokio_test::block_on(auth.change_password("new_password")));
+        assert_ok!(tokio_test::block_on(auth.refresh_id_token()));
+        assert_that!(
+            &tokio_test::block_on(other_device.refresh_id_token()),
+            maybe_err(eq(FirebaseAuthError::TokenExpired))
+        );
+        assert_that!(
+            &tokio_test::block_on(auth.sign_in("user@example.com", "password")),
+            maybe_err(eq(FirebaseAuthError::InvalidPassword))
+        );
+        assert_ok!(tokio_test::block_on(auth.sign_in("user@example.com", "new_password")));
+    }
+
+    #[test]
+    fn test_email_change() {
+        let mut auth = InMemoryAuth::new();
+        assert_ok!(tokio_test::block_on(auth.sign_up("user@example.com", "password")));
+        assert_ok!(tokio_test::block_on(auth.sign_up("other@example.com", "password")));
+
+        assert_that!(
+            &tokio_test::block_on(auth.change_email("user@example.com")),
+            maybe_err(eq(FirebaseAuthError::EmailExists))
+        );
+        assert_ok!(tokio_test::block_on(auth.change_email("new@example.com")));
+        assert_that!(&auth.claims().unwrap().email, maybe_some(eq("new@example.com".to_string())));
+        assert_that!(
+            &tokio_test::block_on(auth.sign_in("other@example.com", "password")),
+            maybe_err(eq(FirebaseAuthError::EmailNotFound))
+        );
+    }
+
+    #[test]
+    fn test_anonymous_user_upgrade() {
+        let mut auth = InMemoryAuth::new();
+        let user_id = tokio_test::block_on(auth.sign_in_anonymously()).unwrap();
+        assert!(auth.claims().unwrap().is_anonymous());
+
+        assert_ok!(tokio_test::block_on(auth.link_email_password("user@example.com", "password")));
+        let claims = auth.claims().unwrap();
+        assert_eq!(claims.user_id, user_id);
+        assert!(!claims.is_anonymous());
+        assert_that!(
+            &tokio_test::block_on(auth.sign_in("user@example.com", "password")),
+            maybe_ok(eq(user_id))
+        );
+    }
+
+    #[test]
+    fn test_profile_update() {
+        let mut auth = InMemoryAuth::new();
+        let user_id = tokio_test::block_on(auth.sign_up("user@example.com", "password")).unwrap();
+        assert_ok!(tokio_test::block_on(auth.update_profile(&ProfileUpdate {
+            display_name: Some("Name".to_string()),
+            photo_url: Some("https://example.com/photo.png".to_string()),
+            ..ProfileUpdate::default()
+        })));
+        assert_ok!(tokio_test::block_on(auth.update_profile(&ProfileUpdate {
+            delete: vec![ProfileAttribute::PhotoUrl],
+            ..ProfileUpdate::default()
+        })));
+        assert_that!(
+            &tokio_test::block_on(auth.get_account_info()),
+            maybe_ok(eq(AccountInfo {
+                user_id,
+                email: Some("user@example.com".to_string()),
+                email_verified: false,
+                display_name: Some("Name".to_string()),
+                photo_url: None,
+            }))
+        );
+    }
+
+    #[test]
+    fn test_account_deletion() {
+        let mut auth = InMemoryAuth::new();
+        assert_ok!(tokio_test::block_on(auth.sign_up("user@example.com", "password")));
+        let mut other_device = auth.clone();
+        assert_ok!(tokio_test::block_on(other_device.sign_in("user@example.com", "password")));
+
+        assert_ok!(tokio_test::block_on(auth.delete_account()));
+        assert_eq!(auth.refresh_token(), None);
+        assert_that!(
+            &tokio_test::block_on(auth.sign_in("user@example.com", "password")),
+            maybe_err(eq(FirebaseAuthError::EmailNotFound))
+        );
+        assert_that!(
+            &tokio_test::block_on(other_device.get_account_info()),
+            maybe_err(eq(FirebaseAuthError::UserNotFound))
+        );
+    }
+
+    #[test]
+    fn test_email_verification() {
+        let mut auth = InMemoryAuth::new();
+        assert_ok!(tokio_test::block_on(auth.sign_up("user@example.com", "password")));
+        assert_ok!(tokio_test::block_on(auth.send_email_verification()));
+        assert!(!auth.claims().unwrap().email_verified);
+        assert_ok!(tokio_test::block_on(auth.refresh_id_token()));
+        assert!(auth.claims().unwrap().email_verified);
+    }
+
+    #[test]
+    fn test_shared_backends() {
+        let config = Config {
+            api_key: "api_key".to_string(),
+            auth_host: "http://localhost:9099/".to_string(),
+            database_host: "http://localhost:9000/".to_string(),
+            database_namespace: "namespace".to_string(),
+            offline_auth: true,
+        };
+        let mut auth = auth_backend::from_config(&config).unwrap();
+        assert_ok!(tokio_test::block_on(auth.sign_up("shared@example.com", "password")));
+        let mut store_auth = auth_backend::from_config(&config).unwrap();
+        assert_ok!(tokio_test::block_on(store_auth.sign_in("shared@example.com", "password")));
+        // Not shared with the ones tests create.
+        assert_that!(
+            &tokio_test::block_on(InMemoryAuth::new().sign_in("shared@example.com", "password")),
+            maybe_err(eq(FirebaseAuthError::EmailNotFound))
+        );
+    }
+}
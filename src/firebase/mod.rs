@@ -1,4 +1,10 @@
 pub mod auth;
+pub mod auth_backend;
+pub mod database;
+pub mod id_token;
+pub mod in_memory_auth;
+pub mod retry;
 
 pub(self) mod auth_errors;
+pub(self) mod database_errors;
 pub(self) mod types;
@@ -0,0 +1,194 @@
+// Retrying requests that failed for reasons likely to go away by themselves, such as a flaky
+// connection or Firebase asking to slow down. Shared by FirebaseAuth and FirebaseDatabase.
+
+use std::future::Future;
+use std::time::Duration;
+
+use crate::types::{random_below, sleep};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RetryPolicy {
+    // Including the first attempt, so 1 means no retries.
+    pub max_attempts: u32,
+    // The delays grow exponentially from this one, up to max_delay.
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    pub multiplier: u32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(10),
+            multiplier: 2,
+        }
+    }
+}
+
+// Whether sending a request twice has the same effect as sending it once. Only such requests are
+// retried: a request that failed with a network error might have been carried out anyway.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Idempotency {
+    Idempotent,
+    NotIdempotent,
+}
+
+// Errors of requests that can be retried.
+pub trait RetryableError: Sized {
+    // Whether the same request might succeed if sent again later.
+    fn is_transient(&self) -> bool;
+
+    // The error to report when the request failed after being sent more than once, so that it
+    // tells how many attempts were made.
+    fn after_attempts(self, attempts: u32) -> Self;
+}
+
+impl RetryPolicy {
+    // Sends the request (as many times as needed, so it has to be built anew each time) until it
+    // succeeds, fails for good or the attempts run out.
+    pub async fn run<T, E, F, Fut>(&self, idempotency: Idempotency, request: F)
+        -> std::result::Result<T, E>
+        where F: Fn() -> Fut,
+              Fut: Future<Output = std::result::Result<T, E>>,
+              E: RetryableError
+    {
+        let max_attempts = match idempotency {
+            Idempotency::Idempotent => self.max_attempts.max(1),
+            Idempotency::NotIdempotent => 1,
+        };
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            match request().await {
+                Err(err) if err.is_transient() && attempts < max_attempts =>
+                    sleep(self.delay_after(attempts)).await,
+                Err(err) if attempts > 1 => return Err(err.after_attempts(attempts)),
+                result => return result,
+            }
+        }
+    }
+
+    // Exponential backoff with jitter: half of the delay is fixed, the other half random, so that
+    // clients that failed at the same time do not all retry at the same time as well.
+    fn delay_after(&self, attempts: u32) -> Duration {
+        let exponent = attempts.saturating_sub(1);
+        let delay = self.initial_delay
+            .saturating_mul(self.multiplier.saturating_pow(exponent))
+            .min(self.max_delay);
+        let half_ms = delay.as_millis() as u64 / 2;
+        Duration::from_millis(half_ms + random_below(half_ms + 1))
+    }
+}
+
+#[cfg(test)]
+mod retry_tests {
+    use super::*;
+    use std::cell::{Cell, RefCell};
+    use galvanic_assert::matchers::*;
+    use galvanic_assert::matchers::variant::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum TestError {
+        Transient,
+        Permanent,
+        GaveUp(u32, Box<TestError>),
+    }
+
+    impl RetryableError for TestError {
+        fn is_transient(&self) -> bool {
+            *self == TestError::Transient
+        }
+
+        fn after_attempts(self, attempts: u32) -> Self {
+            TestError::GaveUp(attempts, Box::new(self))
+        }
+    }
+
+    fn quick_policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            initial_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(2),
+            multiplier: 2,
+        }
+    }
+
+    // Fails with the given errors, in order, then succeeds.
+    fn run_failing(policy: RetryPolicy, idempotency: Idempotency, errors: Vec<TestError>)
+        -> (Result<(), TestError>, u32)
+    {
+        let calls = Cell::new(0);
+        let errors = RefCell::new(errors.into_iter());
+        let result = tokio_test::block_on(policy.run(idempotency, || {
+            calls.set(calls.get() + 1);
+            let next = errors.borrow_mut().next();
+            async move {
+                match next {
+                    Some(err) => Err(err),
+                    None => Ok(()),
+                }
+            }
+        }));
+        (result, calls.get())
+    }
+
+    #[test]
+    fn test_retries_transient_failures() {
+        let (result, calls) = run_failing(
+            quick_policy(3), Idempotency::Idempotent, vec![TestError::Transient, TestError::Transient]);
+        assert_that!(&result, maybe_ok(eq(())));
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn test_gives_up_after_max_attempts() {
+        let (result, calls) = run_failing(
+            quick_policy(3), Idempotency::Idempotent, vec![TestError::Transient; 5]);
+        assert_that!(&result, maybe_err(eq(TestError::GaveUp(3, Box::new(TestError::Transient)))));
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn test_does_not_retry_permanent_failures() {
+        let (result, calls) = run_failing(
+            quick_policy(3), Idempotency::Idempotent, vec![TestError::Permanent]);
+        assert_that!(&result, maybe_err(eq(TestError::Permanent)));
+        assert_eq!(calls, 1);
+
+        // Still tells about the earlier attempts.
+        let (result, calls) = run_failing(
+            quick_policy(3), Idempotency::Idempotent, vec![TestError::Transient, TestError::Permanent]);
+        assert_that!(&result, maybe_err(eq(TestError::GaveUp(2, Box::new(TestError::Permanent)))));
+        assert_eq!(calls, 2);
+    }
+
+    #[test]
+    fn test_does_not_retry_non_idempotent_requests() {
+        let (result, calls) = run_failing(
+            quick_policy(3), Idempotency::NotIdempotent, vec![TestError::Transient]);
+        assert_that!(&result, maybe_err(eq(TestError::Transient)));
+        assert_eq!(calls, 1);
+
+        let (result, calls) = run_failing(
+            quick_policy(1), Idempotency::Idempotent, vec![TestError::Transient]);
+        assert_that!(&result, maybe_err(eq(TestError::Transient)));
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn test_delays_grow_exponentially_up_to_max() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(1000),
+            multiplier: 2,
+        };
+        for (attempts, full_delay_ms) in [(1, 100), (2, 200), (3, 400), (4, 800), (5, 1000), (30, 1000)] {
+            let delay_ms = policy.delay_after(attempts).as_millis() as u64;
+            assert!(delay_ms >= full_delay_ms / 2 && delay_ms <= full_delay_ms,
+                "{} ms after {} attempts", delay_ms, attempts);
+        }
+    }
+}
@@ -38,6 +38,14 @@ impl<'de> Deserialize<'de> for FirebaseErrorMessage {
         if let Ok(result) = try_default {
             return Ok(result);
         }
+        // Some codes come with an explanation, e.g. "WEAK_PASSWORD : Password should be at least
+        // 6 characters".
+        if let Some((code, _explanation)) = str_value.split_once(" : ") {
+            let try_code: Result<Self, D::Error> = Self::deserialize(code.to_string().into_deserializer());
+            if let Ok(result) = try_code {
+                return Ok(result);
+            }
+        }
         Ok(match str_value.as_str() {
             "API key not valid. Please pass a valid API key." => Self::ApiKeyInvalid,
             val => Self::Unparsable(val.to_string()),
@@ -52,9 +60,50 @@ impl std::fmt::Display for FirebaseErrorMessage {
 }
 
 #[derive(Debug, Deserialize, PartialEq)]
+#[serde(from = "RawFirebaseErrorDetails")]
 pub struct FirebaseErrorDetails {
-    pub code: i32,
+    // The HTTP status code.
+    pub code: u16,
     pub message: FirebaseErrorMessage,
+    // The message as sent, e.g. with the explanation FirebaseErrorMessage drops.
+    pub raw_message: String,
+    // Google's canonical error code, e.g. "INVALID_ARGUMENT".
+    pub status: Option<String>,
+    // A machine readable cause, e.g. "API_KEY_INVALID".
+    pub reason: Option<String>,
+}
+
+// The error as sent by Firebase; FirebaseErrorDetails keeps what is useful of it.
+#[derive(Deserialize)]
+struct RawFirebaseErrorDetails {
+    code: u16,
+    message: String,
+    #[serde(default)]
+    status: Option<String>,
+    // Newer, google.rpc style details.
+    #[serde(default)]
+    details: Vec<RawReason>,
+    // Older style details, usually with a less specific reason.
+    #[serde(default)]
+    errors: Vec<RawReason>,
+}
+
+#[derive(Deserialize)]
+struct RawReason {
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+impl From<RawFirebaseErrorDetails> for FirebaseErrorDetails {
+    fn from(raw: RawFirebaseErrorDetails) -> Self {
+        // The trait's deserialize, which also understands the non-standard messages.
+        let message: Result<FirebaseErrorMessage, serde::de::value::Error> =
+            <FirebaseErrorMessage as Deserialize>::deserialize(raw.message.clone().into_deserializer());
+        let message = message.unwrap_or_else(|_| FirebaseErrorMessage::Unparsable(raw.message.clone()));
+        let reason = raw.details.into_iter().chain(raw.errors)
+            .find_map(|details| details.reason);
+        Self { code: raw.code, message, raw_message: raw.message, status: raw.status, reason }
+    }
 }
 
 
@@ -63,6 +112,13 @@ pub struct FirebaseErrorResponse {
     pub error: FirebaseErrorDetails,
 }
 
+// Realtime Database reports errors in a much simpler format than Auth: a single human-readable
+// message, with the HTTP status code carrying the actual meaning.
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct FirebaseDatabaseErrorResponse {
+    pub error: String,
+}
+
 #[cfg(test)]
 mod firebase_types_tests {
     use super::*;
@@ -85,6 +141,15 @@ mod firebase_types_tests {
             serde_json::from_str::<FirebaseErrorMessage>(r#""INVALID_REFRESH_TOKEN""#).unwrap(),
             FirebaseErrorMessage::InvalidRefreshToken
         );
+        assert_eq!(
+            serde_json::from_str::<FirebaseErrorMessage>(
+                r#""WEAK_PASSWORD : Password should be at least 6 characters""#).unwrap(),
+            FirebaseErrorMessage::WeakPassword
+        );
+        assert_eq!(
+            serde_json::from_str::<FirebaseErrorMessage>(r#""UNKNOWN_CODE : Explanation""#).unwrap(),
+            FirebaseErrorMessage::Unparsable("UNKNOWN_CODE : Explanation".to_string())
+        );
         assert_eq!(
             serde_json::from_str::<FirebaseErrorMessage>(r#""Unexpected string.""#).unwrap(),
             FirebaseErrorMessage::Unparsable("Unexpected string.".to_string())
@@ -105,6 +170,38 @@ mod firebase_types_tests {
                 error: FirebaseErrorDetails {
                     code: 400,
                     message: FirebaseErrorMessage::UserDisabled,
+                    raw_message: "USER_DISABLED".to_string(),
+                    status: None,
+                    reason: None,
+                }
+            }
+        );
+    }
+
+    #[test]
+    fn parses_response_with_errors() {
+        assert_eq!(
+            serde_json::from_str::<FirebaseErrorResponse>(r#"
+                    {
+                        "error": {
+                            "code": 400,
+                            "message": "WEAK_PASSWORD : Password should be at least 6 characters",
+                            "errors": [
+                              {
+                                "message": "WEAK_PASSWORD : Password should be at least 6 characters",
+                                "domain": "global",
+                                "reason": "invalid"
+                              }
+                            ]
+                        }
+                    }"#).unwrap(),
+            FirebaseErrorResponse {
+                error: FirebaseErrorDetails {
+                    code: 400,
+                    message: FirebaseErrorMessage::WeakPassword,
+                    raw_message: "WEAK_PASSWORD : Password should be at least 6 characters".to_string(),
+                    status: None,
+                    reason: Some("invalid".to_string()),
                 }
             }
         );
@@ -135,9 +232,25 @@ mod firebase_types_tests {
                 error: FirebaseErrorDetails {
                     code: 400,
                     message: FirebaseErrorMessage::ApiKeyInvalid,
+                    raw_message: "API key not valid. Please pass a valid API key.".to_string(),
+                    status: Some("INVALID_ARGUMENT".to_string()),
+                    reason: Some("API_KEY_INVALID".to_string()),
                 }
             }
         );
     }
+
+    #[test]
+    fn parses_database_response() {
+        assert_eq!(
+            serde_json::from_str::<FirebaseDatabaseErrorResponse>(r#"
+                    {
+                        "error" : "Permission denied"
+                    }"#).unwrap(),
+            FirebaseDatabaseErrorResponse {
+                error: "Permission denied".to_string(),
+            }
+        );
+    }
 }
 
@@ -5,6 +5,8 @@ mod components;
 mod config;
 mod firebase;
 mod routes;
+mod session;
+mod store;
 mod types;
 
 use crate::components::*;
@@ -22,7 +24,9 @@ fn app() -> Html {
           <UserContextProvider>
             <BrowserRouter>
               <HeaderBar/>
-              <Switch<Route> render={Switch::render(switch)} />
+              <SessionScope>
+                <Switch<Route> render={Switch::render(switch)} />
+              </SessionScope>
             </BrowserRouter>
           </UserContextProvider>
         </ConfigProvider>
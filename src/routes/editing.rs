@@ -0,0 +1,85 @@
+// Helpers shared by the trip and template editors.
+
+use yew::prelude::*;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Direction {
+    Up,
+    Down,
+}
+
+// Swaps the element with its neighbour in the given direction. Returns false if there is no such
+// neighbour (or element), in which case nothing changes.
+pub fn move_element<T>(elements: &mut [T], index: usize, direction: Direction) -> bool {
+    let other = match direction {
+        _ if index >= elements.len() => return false,
+        Direction::Up if index > 0 => index - 1,
+        Direction::Down if index + 1 < elements.len() => index + 1,
+        _ => return false,
+    };
+    elements.swap(index, other);
+    true
+}
+
+// Removes the element if it exists. Returns whether anything was removed.
+pub fn remove_element<T>(elements: &mut Vec<T>, index: usize) -> bool {
+    if index < elements.len() {
+        elements.remove(index);
+        true
+    } else {
+        false
+    }
+}
+
+// Buttons for moving an element up or down and deleting it.
+pub fn element_controls(
+    on_up: Callback<MouseEvent>, on_down: Callback<MouseEvent>, on_delete: Callback<MouseEvent>)
+    -> Html
+{
+    html! {
+      <span class="buttons are-small has-addons ml-2 is-flex-shrink-0">
+        <button class="button is-white" aria-label="Move up" onclick={on_up}>
+          <span class="icon is-small"><i class="fas fa-arrow-up"></i></span>
+        </button>
+        <button class="button is-white" aria-label="Move down" onclick={on_down}>
+          <span class="icon is-small"><i class="fas fa-arrow-down"></i></span>
+        </button>
+        <button class="button is-white has-text-danger" aria-label="Delete" onclick={on_delete}>
+          <span class="icon is-small"><i class="fas fa-trash"></i></span>
+        </button>
+      </span>
+    }
+}
+
+#[cfg(test)]
+mod editing_tests {
+    use super::*;
+
+    #[test]
+    fn moves_elements() {
+        let mut elements = vec![1, 2, 3];
+        assert!(move_element(&mut elements, 1, Direction::Up));
+        assert_eq!(elements, vec![2, 1, 3]);
+        assert!(move_element(&mut elements, 1, Direction::Down));
+        assert_eq!(elements, vec![2, 3, 1]);
+    }
+
+    #[test]
+    fn does_not_move_past_the_ends() {
+        let mut elements = vec![1, 2, 3];
+        assert!(!move_element(&mut elements, 0, Direction::Up));
+        assert!(!move_element(&mut elements, 2, Direction::Down));
+        assert!(!move_element(&mut elements, 5, Direction::Up));
+        assert!(!move_element(&mut Vec::<u32>::new(), 0, Direction::Down));
+        assert_eq!(elements, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn removes_elements() {
+        let mut elements = vec![1, 2, 3];
+        assert!(remove_element(&mut elements, 1));
+        assert_eq!(elements, vec![1, 3]);
+        assert!(!remove_element(&mut elements, 2));
+        assert_eq!(elements, vec![1, 3]);
+    }
+}
@@ -0,0 +1,229 @@
+use serde::{Deserialize};
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+use crate::components::*;
+use crate::config::{Config};
+use crate::firebase::auth::{FirebaseAuth, FirebaseAuthError};
+use crate::routes::{Route};
+
+// Query of the link in the password reset email, e.g. ?mode=resetPassword&oobCode=...
+#[derive(Deserialize)]
+struct ResetLinkQuery {
+    #[serde(rename = "oobCode")]
+    oob_code: String,
+}
+
+enum Stage {
+    AskForEmail,
+    EmailSent,
+    CheckingCode,
+    // Code from the link and the email of the account it resets the password of.
+    AskForPassword { oob_code: String, email: String },
+    PasswordChanged,
+}
+
+pub enum Msg {
+    SetEmail(String),
+    SetPassword(String),
+    Submit,
+    EmailSent(Result<(), FirebaseAuthError>),
+    // Code that was checked and the result of checking it.
+    CodeChecked(String, Result<String, FirebaseAuthError>),
+    PasswordChanged(Result<String, FirebaseAuthError>),
+}
+
+// Sends the password reset email and, when opened from the link in it, lets the user choose a new
+// password.
+pub struct ForgotPassword {
+    stage: Stage,
+    email: String,
+    password: String,
+    in_progress: bool,
+    error: Option<String>,
+}
+
+impl Component for ForgotPassword {
+    type Message = Msg;
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let mut forgot_password = Self {
+            stage: Stage::AskForEmail,
+            email: String::new(),
+            password: String::new(),
+            in_progress: false,
+            error: None,
+        };
+        let reset_link = ctx.link().location()
+            .and_then(|location| location.query::<ResetLinkQuery>().ok());
+        if let Some(ResetLinkQuery { oob_code }) = reset_link {
+            forgot_password.stage = Stage::CheckingCode;
+            forgot_password.with_auth(ctx, |auth| async move {
+                let result = auth.verify_password_reset_code(&oob_code).await;
+                Msg::CodeChecked(oob_code, result)
+            });
+        }
+        forgot_password
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::SetEmail(email) => {
+                self.email = email;
+                true
+            },
+            Msg::SetPassword(password) => {
+                self.password = password;
+                true
+            },
+            Msg::Submit => {
+                if self.in_progress {
+                    return false;
+                }
+                match &self.stage {
+                    Stage::AskForEmail => {
+                        let email = self.email.trim().to_string();
+                        self.with_auth(ctx, |auth| async move {
+                            Msg::EmailSent(auth.send_password_reset_email(&email).await)
+                        });
+                    },
+                    Stage::AskForPassword { oob_code, .. } => {
+                        let (oob_code, password) = (oob_code.clone(), self.password.clone());
+                        self.with_auth(ctx, |auth| async move {
+                            Msg::PasswordChanged(auth.confirm_password_reset(&oob_code, &password).await)
+                        });
+                    },
+                    _ => return false,
+                }
+                self.in_progress = true;
+                self.error = None;
+                true
+            },
+            Msg::EmailSent(result) => {
+                self.in_progress = false;
+                match result {
+                    Ok(()) => self.stage = Stage::EmailSent,
+                    Err(err) => self.error = Some(format!("{}", err)),
+                }
+                true
+            },
+            Msg::CodeChecked(oob_code, result) => {
+                match result {
+                    Ok(email) => self.stage = Stage::AskForPassword { oob_code, email },
+                    Err(err) => {
+                        self.stage = Stage::AskForEmail;
+                        self.error = Some(format!("{}", err));
+                    },
+                }
+                true
+            },
+            Msg::PasswordChanged(result) => {
+                self.in_progress = false;
+                match result {
+                    Ok(_) => self.stage = Stage::PasswordChanged,
+                    Err(err) => self.error = Some(format!("{}", err)),
+                }
+                true
+            },
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let crumbs = vec![
+            BreadcrumbElement::Link { text: "Home".to_string(), destination: Route::Home },
+            BreadcrumbElement::Link { text: "Log in".to_string(), destination: Route::Login },
+            BreadcrumbElement::Active { text: "Forgot password".to_string() },
+        ];
+        let content = match &self.stage {
+            Stage::AskForEmail => self.view_form(ctx, "Email", "email", &self.email, "Send reset link"),
+            Stage::EmailSent => html! {
+                <div class="notification is-success is-light">
+                  { format!("We sent a link to reset your password to {}.", self.email.trim()) }
+                </div>
+            },
+            Stage::CheckingCode => html! { <progress class="progress is-small is-primary"/> },
+            Stage::AskForPassword { email, .. } => html! {
+                <>
+                  <p class="mb-3">{ format!("Choose a new password for {}.", email) }</p>
+                  { self.view_form(ctx, "New password", "password", &self.password, "Change password") }
+                </>
+            },
+            Stage::PasswordChanged => html! {
+                <div class="notification is-success is-light">
+                  { "Your password was changed. " }
+                  <Link<Route> to={Route::Login}>{ "Log in" }</Link<Route>>
+                  { " with the new one." }
+                </div>
+            },
+        };
+        html! {
+            <>
+                <Breadcrumbs {crumbs}/>
+                <section class="section">
+                  <div class="container">
+                    <div class="columns is-centered">
+                      <div class="column is-half-tablet is-one-third-desktop">
+                        { content }
+                      </div>
+                    </div>
+                  </div>
+                </section>
+            </>
+        }
+    }
+}
+
+impl ForgotPassword {
+
+    fn with_auth<F, Fut>(&mut self, ctx: &Context<Self>, make_future: F)
+        where F: FnOnce(FirebaseAuth) -> Fut, Fut: std::future::Future<Output = Msg> + 'static
+    {
+        let auth = ctx.link().context::<Config>(Callback::noop())
+            .ok_or(FirebaseAuthError::Internal("Config is not available.".to_string()))
+            .and_then(|(config, _)| FirebaseAuth::new_custom_url_base(&config.api_key, &config.auth_host));
+        match auth {
+            Ok(auth) => ctx.link().send_future(make_future(auth)),
+            Err(err) => self.error = Some(format!("{}", err)),
+        }
+    }
+
+    // Single input form; input_type is either "email" or "password".
+    fn view_form(&self, ctx: &Context<Self>, label: &str, input_type: &'static str, value: &str,
+                 submit_text: &str) -> Html
+    {
+        let link = ctx.link();
+        let on_input = link.callback(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            match input_type {
+                "email" => Msg::SetEmail(input.value()),
+                _ => Msg::SetPassword(input.value()),
+            }
+        });
+        let on_submit = link.callback(|e: FocusEvent| {
+            e.prevent_default();
+            Msg::Submit
+        });
+        let mut submit_classes = classes!("button", "is-primary", "is-fullwidth");
+        if self.in_progress {
+            submit_classes.push("is-loading");
+        }
+        html! {
+            <form class="box" onsubmit={on_submit}>
+              <div class="field">
+                <label class="label">{ label }</label>
+                <div class="control">
+                  <input class="input" type={input_type} value={value.to_string()} oninput={on_input}/>
+                </div>
+              </div>
+              if let Some(error) = &self.error {
+                <p class="help is-danger mb-3">{ error }</p>
+              }
+              <button class={submit_classes} type="submit" disabled={value.trim().is_empty()}>
+                { submit_text }
+              </button>
+            </form>
+        }
+    }
+}
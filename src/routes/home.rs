@@ -3,47 +3,99 @@ use yew_router::prelude::*;
 
 use crate::components::*;
 use crate::routes::{Route};
+use crate::store::{Store, StoreError};
+use crate::types::{Template, Timestamp, Trip};
 
-#[function_component(Home)]
-pub fn home_view() -> Html {
-    let trips = vec![
-        recent_widget::Element {
-            name: "Trip 1".to_string(),
-            el_type: recent_widget::ElementType::Trip,
-        },
-        recent_widget::Element {
-            name: "Trip 2".to_string(),
-            el_type: recent_widget::ElementType::Trip,
-        },
-        recent_widget::Element {
-            name: "Trip 3".to_string(),
-            el_type: recent_widget::ElementType::Trip,
-        },
-    ];
-    let templates = vec![
-        recent_widget::Element {
-            name: "Template 1".to_string(),
-            el_type: recent_widget::ElementType::Template,
-        },
-        recent_widget::Element {
-            name: "Template 2".to_string(),
-            el_type: recent_widget::ElementType::Template,
-        },
-    ];
-    html! {
-        <section class={ "section" }>
-            <div class="container">
-                <div class="columns is-centered">
-                    <div class="column is-two-thirds">
-                        <RecentWidget title={"Recent trips"} elements={trips} />
-                        <Link<Route> to={Route::Trips}>{">> Go to Trips"}</Link<Route>>
-                    </div>
-                    <div class="column is-one-third">
-                        <RecentWidget title={"Recent templates"} elements={templates} />
-                        <Link<Route> to={Route::Templates}>{">> Go to Templates"}</Link<Route>>
+// How many of the most recently modified trips and templates are shown.
+const RECENT_COUNT: usize = 5;
+
+pub enum Msg {
+    TripsLoaded(Result<Vec<recent_widget::Element>, StoreError>),
+    TemplatesLoaded(Result<Vec<recent_widget::Element>, StoreError>),
+}
+
+// The user's most recently modified trips and templates. Shows empty lists to users who are not
+// logged in.
+pub struct Home {
+    trips: Vec<recent_widget::Element>,
+    templates: Vec<recent_widget::Element>,
+    error: Option<String>,
+}
+
+impl Component for Home {
+    type Message = Msg;
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let mut home = Self {
+            trips: Vec::new(),
+            templates: Vec::new(),
+            error: None,
+        };
+        match Store::from_context(ctx.link()) {
+            Ok(mut trips_store) => {
+                let mut templates_store = trips_store.clone();
+                ctx.link().send_future(async move {
+                    Msg::TripsLoaded(trips_store.get_trips().await.map(|trips| recent(
+                        trips.into_iter().map(|(id, trip): (_, Trip)| (id, trip.name, trip.updated_at)),
+                        recent_widget::ElementType::Trip)))
+                });
+                ctx.link().send_future(async move {
+                    Msg::TemplatesLoaded(templates_store.get_templates().await.map(|templates| recent(
+                        templates.into_iter()
+                            .map(|(id, template): (_, Template)| (id, template.name, template.updated_at)),
+                        recent_widget::ElementType::Template)))
+                });
+            },
+            Err(StoreError::Unauthenticated) => {},
+            Err(err) => home.error = Some(format!("{}", err)),
+        }
+        home
+    }
+
+    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::TripsLoaded(Ok(trips)) => self.trips = trips,
+            Msg::TemplatesLoaded(Ok(templates)) => self.templates = templates,
+            Msg::TripsLoaded(Err(err)) | Msg::TemplatesLoaded(Err(err)) => {
+                log::error!("Failed to load recent trips and templates: {}", err);
+                self.error = Some(format!("{}", err));
+            },
+        }
+        true
+    }
+
+    fn view(&self, _ctx: &Context<Self>) -> Html {
+        html! {
+            <section class={ "section" }>
+                <div class="container">
+                    if let Some(error) = &self.error {
+                      <div class="notification is-danger is-light">{ error }</div>
+                    }
+                    <div class="columns is-centered">
+                        <div class="column is-two-thirds">
+                            <RecentWidget title={"Recent trips"} elements={self.trips.clone()} />
+                            <Link<Route> to={Route::Trips}>{">> Go to Trips"}</Link<Route>>
+                        </div>
+                        <div class="column is-one-third">
+                            <RecentWidget title={"Recent templates"} elements={self.templates.clone()} />
+                            <Link<Route> to={Route::Templates}>{">> Go to Templates"}</Link<Route>>
+                        </div>
                     </div>
                 </div>
-            </div>
-        </section>
+            </section>
+        }
     }
 }
+
+// The most recently modified of the (id, name, modification time) triples, newest first.
+fn recent<I>(elements: I, el_type: recent_widget::ElementType) -> Vec<recent_widget::Element>
+    where I: Iterator<Item = (u64, String, Timestamp)>
+{
+    let mut elements = elements.collect::<Vec<_>>();
+    elements.sort_by_key(|(_, _, updated_at)| std::cmp::Reverse(*updated_at));
+    elements.into_iter()
+        .take(RECENT_COUNT)
+        .map(|(id, name, _)| recent_widget::Element { name, id, el_type: el_type.clone() })
+        .collect()
+}
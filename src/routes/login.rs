@@ -0,0 +1,225 @@
+use serde::{Deserialize, Serialize};
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+use crate::components::*;
+use crate::firebase::auth::ErrorClass;
+use crate::routes::{Route};
+
+// Query of the login page.
+#[derive(Serialize, Deserialize, Default)]
+pub struct LoginQuery {
+    // Path of the route to return to after logging in, instead of the home page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redirect: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Mode {
+    SignIn,
+    SignUp,
+}
+
+pub enum Msg {
+    SetMode(Mode),
+    SetEmail(String),
+    SetPassword(String),
+    Submit,
+    ContinueAsGuest,
+    Done(AuthResult),
+}
+
+// Log in and sign up form. The actual work is done by UserContextProvider; once the user is
+// authenticated, they are taken to the home page.
+pub struct Login {
+    mode: Mode,
+    email: String,
+    password: String,
+    in_progress: bool,
+    error: Option<String>,
+}
+
+impl Component for Login {
+    type Message = Msg;
+    type Properties = ();
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            mode: Mode::SignIn,
+            email: String::new(),
+            password: String::new(),
+            in_progress: false,
+            error: None,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::SetMode(mode) => {
+                self.mode = mode;
+                self.error = None;
+                true
+            },
+            Msg::SetEmail(email) => {
+                self.email = email;
+                true
+            },
+            Msg::SetPassword(password) => {
+                self.password = password;
+                true
+            },
+            Msg::Submit => {
+                let (email, password) = (self.email.trim().to_string(), self.password.clone());
+                let mode = self.mode;
+                self.dispatch(ctx, |on_done| match mode {
+                    Mode::SignIn => AuthAction::SignIn { email, password, on_done },
+                    Mode::SignUp => AuthAction::SignUp { email, password, on_done },
+                })
+            },
+            Msg::ContinueAsGuest => {
+                self.dispatch(ctx, |on_done| AuthAction::SignInAsGuest { on_done })
+            },
+            Msg::Done(Ok(())) => {
+                self.in_progress = false;
+                if let Some(history) = ctx.link().history() {
+                    history.push(redirect_route(ctx).unwrap_or(Route::Home));
+                }
+                true
+            },
+            Msg::Done(Err(err)) => {
+                log::warn!("Authentication failed: {}", err);
+                self.in_progress = false;
+                self.error = Some(match err.class() {
+                    ErrorClass::UserFixable => format!("{}", err),
+                    ErrorClass::Retryable => format!("{} Try again in a moment.", err),
+                    // Nothing the user could do about it, so the details are only for the logs.
+                    ErrorClass::Fatal => {
+                        log::error!("Authentication is broken: {:?}", err);
+                        "Something went wrong on our side. Logging in is not possible right now."
+                            .to_string()
+                    },
+                });
+                true
+            },
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let link = ctx.link();
+        let crumbs = vec![
+            BreadcrumbElement::Link { text: "Home".to_string(), destination: Route::Home },
+            BreadcrumbElement::Active { text: "Log in".to_string() },
+        ];
+        let on_email_input = link.callback(|e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            Msg::SetEmail(input.value())
+        });
+        let on_password_input = link.callback(|e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            Msg::SetPassword(input.value())
+        });
+        let on_submit = link.callback(|e: FocusEvent| {
+            e.prevent_default();
+            Msg::Submit
+        });
+        let tab = |mode: Mode, text: &str| html! {
+            <li class={classes!((self.mode == mode).then_some("is-active"))}>
+              <a onclick={link.callback(move |_: MouseEvent| Msg::SetMode(mode))}>{ text }</a>
+            </li>
+        };
+        let (submit_text, password_autocomplete) = match self.mode {
+            Mode::SignIn => ("Log in", "current-password"),
+            Mode::SignUp => ("Sign up", "new-password"),
+        };
+        let mut submit_classes = classes!("button", "is-primary", "is-fullwidth");
+        if self.in_progress {
+            submit_classes.push("is-loading");
+        }
+        let can_submit = !self.email.trim().is_empty() && !self.password.is_empty();
+        html! {
+            <>
+                <Breadcrumbs {crumbs}/>
+                <section class="section">
+                  <div class="container">
+                    <div class="columns is-centered">
+                      <div class="column is-half-tablet is-one-third-desktop">
+                        <div class="tabs is-centered">
+                          <ul>
+                            { tab(Mode::SignIn, "Log in") }
+                            { tab(Mode::SignUp, "Sign up") }
+                          </ul>
+                        </div>
+                        <form class="box" onsubmit={on_submit}>
+                          <div class="field">
+                            <label class="label">{ "Email" }</label>
+                            <div class="control has-icons-left">
+                              <input class="input" type="email" autocomplete="email"
+                                  value={self.email.clone()} oninput={on_email_input}/>
+                              <span class="icon is-left"><i class="fas fa-envelope"></i></span>
+                            </div>
+                          </div>
+                          <div class="field">
+                            <label class="label">{ "Password" }</label>
+                            <div class="control has-icons-left">
+                              <input class="input" type="password" autocomplete={password_autocomplete}
+                                  value={self.password.clone()} oninput={on_password_input}/>
+                              <span class="icon is-left"><i class="fas fa-lock"></i></span>
+                            </div>
+                          </div>
+                          if let Some(error) = &self.error {
+                            <p class="help is-danger mb-3">{ error }</p>
+                          }
+                          <button class={submit_classes} type="submit" disabled={!can_submit}>
+                            { submit_text }
+                          </button>
+                        </form>
+                        <div class="has-text-centered mb-3">
+                          <button class="button is-text" disabled={self.in_progress}
+                              onclick={link.callback(|_: MouseEvent| Msg::ContinueAsGuest)}>
+                            { "Try it without an account" }
+                          </button>
+                        </div>
+                        if self.mode == Mode::SignIn {
+                          <p class="has-text-centered">
+                            <Link<Route> to={Route::ForgotPassword}>{ "Forgot password?" }</Link<Route>>
+                          </p>
+                        }
+                      </div>
+                    </div>
+                  </div>
+                </section>
+            </>
+        }
+    }
+}
+
+impl Login {
+
+    // Dispatches the action made by make_action, which gets the callback to report the result to.
+    fn dispatch<F>(&mut self, ctx: &Context<Self>, make_action: F) -> bool
+        where F: FnOnce(Callback<AuthResult>) -> AuthAction
+    {
+        if self.in_progress {
+            return false;
+        }
+        let dispatcher = match ctx.link().context::<AuthDispatcher>(Callback::noop()) {
+            Some((dispatcher, _)) => dispatcher,
+            None => {
+                self.error = Some("Authentication is not available.".to_string());
+                return true;
+            },
+        };
+        dispatcher.emit(make_action(ctx.link().callback(Msg::Done)));
+        self.in_progress = true;
+        self.error = None;
+        true
+    }
+}
+
+// Route the user asked for before they were sent to log in. Only paths of the app's own routes are
+// followed.
+fn redirect_route(ctx: &Context<Login>) -> Option<Route> {
+    let path = ctx.link().location()?.query::<LoginQuery>().ok()?.redirect?;
+    Route::recognize(&path).filter(|route| !matches!(route, Route::Login | Route::NotFound))
+}
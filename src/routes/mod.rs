@@ -1,13 +1,34 @@
 use yew_router::prelude::*;
 use yew::prelude::*;
 
-use crate::types::{TripId};
+use crate::components::{RequireAuth};
+use crate::types::{TemplateId, TripId};
 
+mod editing;
+mod forgot_password;
 mod home;
+mod login;
+mod profile;
+mod template;
+mod template_edit;
+mod templates;
+mod trip;
+mod trip_edit;
 mod trips;
+mod verify_email;
 
+use forgot_password::ForgotPassword;
 use home::Home;
+use login::Login;
+pub use login::LoginQuery;
+use profile::Profile;
+use template::TemplateView;
+use template_edit::TemplateEdit;
+use templates::Templates;
+use trip::TripView;
+use trip_edit::TripEdit;
 use trips::Trips;
+use verify_email::VerifyEmail;
 
 #[derive(Clone, Routable, PartialEq)]
 pub enum Route {
@@ -22,29 +43,56 @@ pub enum Route {
     #[at("/templates")]
     Templates,
     #[at("/template/edit/:template_id")]
-    TemplateEdit { template_id: u64 },
+    TemplateEdit { template_id: TemplateId },
     #[at("/template/:template_id")]
-    Template { template_id: u64 },
+    Template { template_id: TemplateId },
     #[at("/profile")]
     Profile,
     #[at("/login")]
     Login,
+    #[at("/forgot-password")]
+    ForgotPassword,
+    #[at("/verify-email")]
+    VerifyEmail,
     #[not_found]
     #[at("/404")]
     NotFound,
 }
 
+impl Route {
+    // Whether the route shows the data of a user, so that there is nothing to show without one.
+    pub fn requires_auth(&self) -> bool {
+        match self {
+            Route::Trips | Route::TripEdit { .. } | Route::Trip { .. } | Route::Templates
+                | Route::TemplateEdit { .. } | Route::Template { .. } | Route::Profile => true,
+            Route::Home | Route::Login | Route::ForgotPassword | Route::VerifyEmail
+                | Route::NotFound => false,
+        }
+    }
+}
+
 pub fn switch(route: &Route) -> Html {
+    let page = render(route);
+    if route.requires_auth() {
+        html! { <RequireAuth>{ page }</RequireAuth> }
+    } else {
+        page
+    }
+}
+
+fn render(route: &Route) -> Html {
     match route {
         Route::Home => html! { <Home/>  },
         Route::Trips => html! { <Trips/> },
-        Route::Trip { id } => html! { <h1> {format!("Trip view {} - unimplemented", id)} </h1> },
-        Route::TripEdit { id } => html! { <h1> {format!("TripEdit view {} - unimplemented", id)} </h1> },
-        Route::Templates => html! { <h1> {"Templates - unimplemented"} </h1> },
-        Route::Template { template_id } => html! { <h1> {format!("Template view {} - unimplemented", template_id)} </h1> },
-        Route::TemplateEdit { template_id } => html! { <h1> {format!("TemplateEdit view {} - unimplemented", template_id)} </h1> },
-        Route::Profile => html! { <h1> {"Profile - unimplemented"} </h1> },
-        Route::Login => html! { <h1> {"Login - unimplemented"} </h1> },
+        Route::Trip { id } => html! { <TripView id={*id}/> },
+        Route::TripEdit { id } => html! { <TripEdit id={*id}/> },
+        Route::Templates => html! { <Templates/> },
+        Route::Template { template_id } => html! { <TemplateView template_id={*template_id}/> },
+        Route::TemplateEdit { template_id } => html! { <TemplateEdit template_id={*template_id}/> },
+        Route::Profile => html! { <Profile/> },
+        Route::Login => html! { <Login/> },
+        Route::ForgotPassword => html! { <ForgotPassword/> },
+        Route::VerifyEmail => html! { <VerifyEmail/> },
         Route::NotFound => html! { <h1> { "Page not found"} </h1> },
     }
 }
@@ -0,0 +1,661 @@
+use url::Url;
+use web_sys::HtmlInputElement;
+use yew::context::ContextHandle;
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+use crate::components::*;
+use crate::firebase::auth::{AccountInfo, FirebaseAuthError, ProfileAttribute, ProfileUpdate};
+use crate::routes::{Route};
+use crate::store::{Store, StoreError};
+use crate::types::{UserAuthStatus};
+
+const MAX_DISPLAY_NAME_LENGTH: usize = 100;
+const MIN_PASSWORD_LENGTH: usize = 6;
+
+// Forms of the page that change the account.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Form {
+    Profile,
+    Email,
+    Password,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Input {
+    DisplayName,
+    PhotoUrl,
+    Email,
+    Password,
+    PasswordConfirmation,
+}
+
+pub enum Msg {
+    UserAuthChanged(UserAuthStatus),
+    AccountInfoLoaded(Result<AccountInfo, StoreError>),
+    SetInput(Input, String),
+    Save(Form),
+    Saved(Form, AuthResult),
+    VerificationSent(AuthResult),
+    SetConfirmingDelete(bool),
+    DeleteAccount,
+    Deleted(Result<(), StoreError>),
+    SetPassword(String),
+    Reauthenticate,
+    Reauthenticated(AuthResult),
+    SetNewEmail(String),
+    SetNewPassword(String),
+    LinkEmailPassword,
+    Linked(AuthResult),
+}
+
+// The current user's account: profile, email and password changes and account deletion.
+pub struct Profile {
+    user_auth: UserAuthStatus,
+    _user_auth_listener: Option<ContextHandle<UserAuthStatus>>,
+    account_info: Option<AccountInfo>,
+    display_name: String,
+    photo_url: String,
+    email: String,
+    new_account_password: String,
+    password_confirmation: String,
+    saving: Option<Form>,
+    // Outcome of the last save, shown under its form.
+    form_status: Option<(Form, Result<String, String>)>,
+    confirming_delete: bool,
+    // Firebase only allows deleting the account and changing the email or password when the user
+    // logged in recently; asks for the password first.
+    needs_reauth: bool,
+    password: String,
+    // Credentials a guest upgrades their account with.
+    new_email: String,
+    new_password: String,
+    link_error: Option<String>,
+    in_progress: bool,
+    error: Option<String>,
+}
+
+impl Component for Profile {
+    type Message = Msg;
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let (user_auth, listener) = match ctx.link()
+            .context::<UserAuthStatus>(ctx.link().callback(Msg::UserAuthChanged))
+        {
+            Some((user_auth, listener)) => (user_auth, Some(listener)),
+            None => (UserAuthStatus::new_empty(), None),
+        };
+        let profile = Self {
+            user_auth,
+            _user_auth_listener: listener,
+            account_info: None,
+            display_name: String::new(),
+            photo_url: String::new(),
+            email: String::new(),
+            new_account_password: String::new(),
+            password_confirmation: String::new(),
+            saving: None,
+            form_status: None,
+            confirming_delete: false,
+            needs_reauth: false,
+            password: String::new(),
+            new_email: String::new(),
+            new_password: String::new(),
+            link_error: None,
+            in_progress: false,
+            error: None,
+        };
+        if profile.user_auth.user_info.is_some() {
+            profile.load(ctx);
+        }
+        profile
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::UserAuthChanged(user_auth) => {
+                let user_changed = user_auth.user_info.as_ref().map(|user| &user.user_id)
+                    != self.user_auth.user_info.as_ref().map(|user| &user.user_id);
+                self.user_auth = user_auth;
+                if user_changed {
+                    self.account_info = None;
+                    if self.user_auth.user_info.is_some() {
+                        self.load(ctx);
+                    }
+                }
+                true
+            },
+            Msg::AccountInfoLoaded(Ok(account_info)) => {
+                self.display_name = account_info.display_name.clone().unwrap_or_default();
+                self.photo_url = account_info.photo_url.clone().unwrap_or_default();
+                self.email = account_info.email.clone().unwrap_or_default();
+                self.account_info = Some(account_info);
+                true
+            },
+            Msg::AccountInfoLoaded(Err(err)) => {
+                log::error!("Failed to load account info: {}", err);
+                self.error = Some(format!("{}", err));
+                true
+            },
+            Msg::SetInput(input, value) => {
+                match input {
+                    Input::DisplayName => self.display_name = value,
+                    Input::PhotoUrl => self.photo_url = value,
+                    Input::Email => self.email = value,
+                    Input::Password => self.new_account_password = value,
+                    Input::PasswordConfirmation => self.password_confirmation = value,
+                }
+                true
+            },
+            Msg::Save(form) => {
+                if self.saving.is_some() {
+                    return false;
+                }
+                let change = match self.validate(form) {
+                    Ok(change) => change,
+                    Err(message) => {
+                        self.form_status = Some((form, Err(message)));
+                        return true;
+                    },
+                };
+                match ctx.link().context::<AuthDispatcher>(Callback::noop()) {
+                    Some((dispatcher, _)) => dispatcher.emit(AuthAction::UpdateAccount {
+                        change,
+                        on_done: ctx.link().callback(move |result| Msg::Saved(form, result)),
+                    }),
+                    None => return false,
+                }
+                self.saving = Some(form);
+                self.form_status = None;
+                true
+            },
+            Msg::Saved(form, Ok(())) => {
+                self.saving = None;
+                let message = match form {
+                    Form::Profile => {
+                        self.load(ctx);
+                        "Profile saved."
+                    },
+                    // Changing the email sends nothing to the new address, so the verification
+                    // email is sent separately, and the form stays busy until it is.
+                    Form::Email => match ctx.link().context::<AuthDispatcher>(Callback::noop()) {
+                        Some((dispatcher, _)) => {
+                            self.saving = Some(form);
+                            dispatcher.emit(AuthAction::SendVerificationEmail {
+                                on_done: ctx.link().callback(Msg::VerificationSent),
+                            });
+                            return true;
+                        },
+                        None => "Email changed.",
+                    },
+                    Form::Password => {
+                        self.new_account_password.clear();
+                        self.password_confirmation.clear();
+                        "Password changed."
+                    },
+                };
+                self.form_status = Some((form, Ok(message.to_string())));
+                true
+            },
+            Msg::VerificationSent(result) => {
+                self.saving = None;
+                self.form_status = Some((Form::Email, match result {
+                    Ok(()) => Ok("Email changed. Follow the link we sent to the new address to verify it."
+                        .to_string()),
+                    Err(err) => {
+                        log::warn!("Failed to send the verification email: {}", err);
+                        Err(format!(
+                            "Your email was changed, but the verification email could not be sent: {} \
+                             Use Resend above to try again.", err))
+                    },
+                }));
+                true
+            },
+            Msg::Saved(form, Err(err)) => {
+                log::warn!("Failed to update account: {}", err);
+                self.saving = None;
+                if *err.last_attempt() == FirebaseAuthError::CredentialTooOldLoginAgain {
+                    self.needs_reauth = true;
+                }
+                self.form_status = Some((form, Err(format!("{}", err))));
+                true
+            },
+            Msg::SetConfirmingDelete(confirming) => {
+                self.confirming_delete = confirming;
+                self.error = None;
+                true
+            },
+            Msg::DeleteAccount => {
+                if self.in_progress {
+                    return false;
+                }
+                match Store::from_context(ctx.link()) {
+                    Ok(mut store) => ctx.link().send_future(async move {
+                        Msg::Deleted(store.delete_account().await)
+                    }),
+                    Err(err) => ctx.link().send_message(Msg::Deleted(Err(err))),
+                }
+                self.in_progress = true;
+                self.error = None;
+                true
+            },
+            Msg::Deleted(Ok(())) => {
+                self.in_progress = false;
+                if let Some((dispatcher, _)) = ctx.link().context::<AuthDispatcher>(Callback::noop()) {
+                    dispatcher.emit(AuthAction::SignOut);
+                }
+                if let Some(history) = ctx.link().history() {
+                    history.push(Route::Home);
+                }
+                false
+            },
+            Msg::Deleted(Err(StoreError::ReauthenticationNeeded)) => {
+                self.in_progress = false;
+                self.needs_reauth = true;
+                true
+            },
+            Msg::Deleted(Err(err)) => {
+                log::error!("Failed to delete account: {}", err);
+                self.in_progress = false;
+                if let StoreError::AccountNotDeleted(FirebaseAuthError::CredentialTooOldLoginAgain) = err {
+                    self.needs_reauth = true;
+                }
+                self.error = Some(format!("{}", err));
+                true
+            },
+            Msg::SetPassword(password) => {
+                self.password = password;
+                true
+            },
+            Msg::Reauthenticate => {
+                let email = match &self.user_auth.user_info {
+                    Some(user_info) if !self.in_progress => user_info.email.clone(),
+                    _ => return false,
+                };
+                match ctx.link().context::<AuthDispatcher>(Callback::noop()) {
+                    Some((dispatcher, _)) => dispatcher.emit(AuthAction::SignIn {
+                        email,
+                        password: std::mem::take(&mut self.password),
+                        on_done: ctx.link().callback(Msg::Reauthenticated),
+                    }),
+                    None => return false,
+                }
+                self.in_progress = true;
+                self.error = None;
+                true
+            },
+            Msg::Reauthenticated(result) => {
+                self.in_progress = false;
+                match result {
+                    // The user submits what they were doing once more, now with a fresh sign in.
+                    Ok(()) => {
+                        self.needs_reauth = false;
+                        self.form_status = None;
+                    },
+                    Err(err) => self.error = Some(format!("{}", err)),
+                }
+                true
+            },
+            Msg::SetNewEmail(email) => {
+                self.new_email = email;
+                true
+            },
+            Msg::SetNewPassword(password) => {
+                self.new_password = password;
+                true
+            },
+            Msg::LinkEmailPassword => {
+                if self.in_progress {
+                    return false;
+                }
+                match ctx.link().context::<AuthDispatcher>(Callback::noop()) {
+                    Some((dispatcher, _)) => dispatcher.emit(AuthAction::LinkEmailPassword {
+                        email: self.new_email.trim().to_string(),
+                        password: self.new_password.clone(),
+                        on_done: ctx.link().callback(Msg::Linked),
+                    }),
+                    None => return false,
+                }
+                self.in_progress = true;
+                self.link_error = None;
+                true
+            },
+            Msg::Linked(result) => {
+                self.in_progress = false;
+                match result {
+                    Ok(()) => {
+                        self.new_email.clear();
+                        self.new_password.clear();
+                        self.load(ctx);
+                    },
+                    Err(err) => self.link_error = Some(format!("{}", err)),
+                }
+                true
+            },
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let crumbs = vec![
+            BreadcrumbElement::Link { text: "Home".to_string(), destination: Route::Home },
+            BreadcrumbElement::Active { text: "Profile".to_string() },
+        ];
+        let content = match &self.user_auth.user_info {
+            Some(user_info) => {
+                let photo_url = self.account_info.as_ref().and_then(|info| info.photo_url.clone());
+                html! {
+                    <>
+                        <div class="media">
+                          if let Some(photo_url) = photo_url {
+                            <figure class="media-left image is-64x64">
+                              <img class="is-rounded" src={photo_url} alt="Profile photo"/>
+                            </figure>
+                          }
+                          <div class="media-content">
+                            <h1 class="title">{ user_info.display_name.clone() }</h1>
+                            if !user_info.is_anonymous {
+                              <p class="subtitle">{ user_info.email.clone() }</p>
+                            }
+                          </div>
+                        </div>
+                        if self.needs_reauth {
+                          { self.view_reauth_form(ctx) }
+                        }
+                        if user_info.is_anonymous {
+                          { self.view_upgrade_form(ctx) }
+                        }
+                        if self.account_info.is_some() {
+                          { self.view_profile_form(ctx) }
+                          if !user_info.is_anonymous {
+                            { self.view_email_form(ctx) }
+                            { self.view_password_form(ctx) }
+                          }
+                        } else if self.error.is_none() {
+                          <progress class="progress is-small is-primary"/>
+                        }
+                        { self.view_danger_zone(ctx) }
+                    </>
+                }
+            },
+            // Only for a moment after signing out; see RequireAuth.
+            None => html! {},
+        };
+        html! {
+            <>
+                <Breadcrumbs {crumbs}/>
+                <section class="section">
+                  <div class="container">
+                    { content }
+                  </div>
+                </section>
+            </>
+        }
+    }
+}
+
+impl Profile {
+
+    fn load(&self, ctx: &Context<Self>) {
+        match Store::from_context(ctx.link()) {
+            Ok(mut store) => ctx.link().send_future(async move {
+                Msg::AccountInfoLoaded(store.get_account_info().await)
+            }),
+            Err(err) => ctx.link().send_message(Msg::AccountInfoLoaded(Err(err))),
+        }
+    }
+
+    // The change the form asks for, or what is wrong with its inputs.
+    fn validate(&self, form: Form) -> Result<AccountChange, String> {
+        match form {
+            Form::Profile => {
+                let mut update = ProfileUpdate::default();
+                let display_name = self.display_name.trim();
+                if display_name.is_empty() {
+                    update.delete.push(ProfileAttribute::DisplayName);
+                } else if display_name.chars().count() > MAX_DISPLAY_NAME_LENGTH {
+                    return Err(format!(
+                        "The display name can be at most {} characters long.", MAX_DISPLAY_NAME_LENGTH));
+                } else {
+                    update.display_name = Some(display_name.to_string());
+                }
+                let photo_url = self.photo_url.trim();
+                if photo_url.is_empty() {
+                    update.delete.push(ProfileAttribute::PhotoUrl);
+                } else {
+                    match Url::parse(photo_url) {
+                        Ok(url) if url.scheme() == "https" || url.scheme() == "http" =>
+                            update.photo_url = Some(url.to_string()),
+                        _ => return Err("The photo URL must be a http or https address.".to_string()),
+                    }
+                }
+                Ok(AccountChange::Profile(update))
+            },
+            Form::Email => {
+                let email = self.email.trim();
+                let well_formed = match email.split_once('@') {
+                    Some((local, domain)) => !local.is_empty() && domain.contains('.')
+                        && !domain.starts_with('.') && !domain.ends_with('.'),
+                    None => false,
+                };
+                let current_email = self.account_info.as_ref().and_then(|info| info.email.as_deref());
+                if !well_formed {
+                    Err(format!("{}", FirebaseAuthError::InvalidEmail))
+                } else if current_email == Some(email) {
+                    Err("This already is your email.".to_string())
+                } else {
+                    Ok(AccountChange::Email(email.to_string()))
+                }
+            },
+            Form::Password => {
+                if self.new_account_password.chars().count() < MIN_PASSWORD_LENGTH {
+                    Err(format!("{}", FirebaseAuthError::WeakPassword))
+                } else if self.new_account_password != self.password_confirmation {
+                    Err("The passwords do not match.".to_string())
+                } else {
+                    Ok(AccountChange::Password(self.new_account_password.clone()))
+                }
+            },
+        }
+    }
+
+    // Box with a form that saves with Msg::Save(form).
+    fn view_form(&self, ctx: &Context<Self>, form: Form, title: &str, fields: Html) -> Html {
+        let on_submit = ctx.link().callback(move |e: FocusEvent| {
+            e.prevent_default();
+            Msg::Save(form)
+        });
+        let mut submit_classes = classes!("button", "is-primary");
+        if self.saving == Some(form) {
+            submit_classes.push("is-loading");
+        }
+        let status = match &self.form_status {
+            Some((status_form, Ok(message))) if *status_form == form =>
+                html! { <p class="help is-success mb-3">{ message }</p> },
+            Some((status_form, Err(message))) if *status_form == form =>
+                html! { <p class="help is-danger mb-3">{ message }</p> },
+            _ => html! {},
+        };
+        html! {
+            <form class="box" onsubmit={on_submit}>
+              <h2 class="title is-5">{ title }</h2>
+              { fields }
+              { status }
+              <button class={submit_classes} type="submit" disabled={self.saving.is_some()}>
+                { "Save" }
+              </button>
+            </form>
+        }
+    }
+
+    fn view_input(&self, ctx: &Context<Self>, input: Input, label: &str, input_type: &'static str,
+                  autocomplete: &'static str, value: &str) -> Html
+    {
+        let on_input = ctx.link().callback(move |e: InputEvent| {
+            let element: HtmlInputElement = e.target_unchecked_into();
+            Msg::SetInput(input, element.value())
+        });
+        html! {
+            <div class="field">
+              <label class="label">{ label }</label>
+              <div class="control">
+                <input class="input" type={input_type} {autocomplete} value={value.to_string()}
+                    oninput={on_input}/>
+              </div>
+            </div>
+        }
+    }
+
+    fn view_profile_form(&self, ctx: &Context<Self>) -> Html {
+        let fields = html! {
+            <>
+                { self.view_input(ctx, Input::DisplayName, "Display name", "text", "nickname", &self.display_name) }
+                { self.view_input(ctx, Input::PhotoUrl, "Photo URL", "url", "photo", &self.photo_url) }
+                <p class="help mb-3">{ "Leave a field empty to remove it from your profile." }</p>
+            </>
+        };
+        self.view_form(ctx, Form::Profile, "Profile", fields)
+    }
+
+    fn view_email_form(&self, ctx: &Context<Self>) -> Html {
+        let fields = self.view_input(ctx, Input::Email, "Email", "email", "email", &self.email);
+        self.view_form(ctx, Form::Email, "Email", fields)
+    }
+
+    fn view_password_form(&self, ctx: &Context<Self>) -> Html {
+        let fields = html! {
+            <>
+                { self.view_input(ctx, Input::Password, "New password", "password", "new-password",
+                                  &self.new_account_password) }
+                { self.view_input(ctx, Input::PasswordConfirmation, "Repeat new password", "password",
+                                  "new-password", &self.password_confirmation) }
+            </>
+        };
+        self.view_form(ctx, Form::Password, "Password", fields)
+    }
+
+    fn view_reauth_form(&self, ctx: &Context<Self>) -> Html {
+        let link = ctx.link();
+        let on_password_input = link.callback(|e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            Msg::SetPassword(input.value())
+        });
+        let on_submit = link.callback(|e: FocusEvent| {
+            e.prevent_default();
+            Msg::Reauthenticate
+        });
+        let mut submit_classes = classes!("button", "is-warning");
+        if self.in_progress {
+            submit_classes.push("is-loading");
+        }
+        html! {
+            <form class="notification is-warning is-light" onsubmit={on_submit}>
+              <p class="mb-3">{ "For security reasons, enter your password again and then retry." }</p>
+              <div class="field has-addons">
+                <div class="control is-expanded">
+                  <input class="input" type="password" autocomplete="current-password"
+                      value={self.password.clone()} oninput={on_password_input}/>
+                </div>
+                <div class="control">
+                  <button class={submit_classes} type="submit" disabled={self.password.is_empty()}>
+                    { "Log in" }
+                  </button>
+                </div>
+              </div>
+            </form>
+        }
+    }
+
+    fn view_upgrade_form(&self, ctx: &Context<Self>) -> Html {
+        let link = ctx.link();
+        let on_email_input = link.callback(|e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            Msg::SetNewEmail(input.value())
+        });
+        let on_password_input = link.callback(|e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            Msg::SetNewPassword(input.value())
+        });
+        let on_submit = link.callback(|e: FocusEvent| {
+            e.prevent_default();
+            Msg::LinkEmailPassword
+        });
+        let mut submit_classes = classes!("button", "is-primary");
+        if self.in_progress {
+            submit_classes.push("is-loading");
+        }
+        let can_submit = !self.new_email.trim().is_empty() && !self.new_password.is_empty();
+        html! {
+            <form class="box" onsubmit={on_submit}>
+              <h2 class="title is-5">{ "Create an account" }</h2>
+              <p class="mb-3">
+                { "You are using the app as a guest. Choose an email and password to keep your trips \
+                   and templates, and to use them on other devices." }
+              </p>
+              <div class="field">
+                <label class="label">{ "Email" }</label>
+                <div class="control">
+                  <input class="input" type="email" autocomplete="email"
+                      value={self.new_email.clone()} oninput={on_email_input}/>
+                </div>
+              </div>
+              <div class="field">
+                <label class="label">{ "Password" }</label>
+                <div class="control">
+                  <input class="input" type="password" autocomplete="new-password"
+                      value={self.new_password.clone()} oninput={on_password_input}/>
+                </div>
+              </div>
+              if let Some(error) = &self.link_error {
+                <p class="help is-danger mb-3">{ error }</p>
+              }
+              <button class={submit_classes} type="submit" disabled={!can_submit}>
+                { "Create account" }
+              </button>
+            </form>
+        }
+    }
+
+    fn view_danger_zone(&self, ctx: &Context<Self>) -> Html {
+        let link = ctx.link();
+        let mut delete_classes = classes!("button", "is-danger");
+        if self.in_progress {
+            delete_classes.push("is-loading");
+        }
+        let content = if self.confirming_delete {
+            html! {
+                <>
+                  <p class="mb-3">
+                    { "This permanently deletes your account together with all of your trips and templates." }
+                  </p>
+                  <div class="buttons">
+                    <button class={delete_classes} disabled={self.needs_reauth}
+                        onclick={link.callback(|_: MouseEvent| Msg::DeleteAccount)}>
+                      { "Delete everything" }
+                    </button>
+                    <button class="button" disabled={self.in_progress}
+                        onclick={link.callback(|_: MouseEvent| Msg::SetConfirmingDelete(false))}>
+                      { "Cancel" }
+                    </button>
+                  </div>
+                </>
+            }
+        } else {
+            html! {
+                <button class="button is-danger is-outlined"
+                    onclick={link.callback(|_: MouseEvent| Msg::SetConfirmingDelete(true))}>
+                  { "Delete account" }
+                </button>
+            }
+        };
+        html! {
+            <div class="box mt-5">
+              <h2 class="title is-5 has-text-danger">{ "Delete account" }</h2>
+              { content }
+              if let Some(error) = &self.error {
+                <p class="help is-danger mt-3">{ error }</p>
+              }
+            </div>
+        }
+    }
+}
@@ -0,0 +1,245 @@
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+use crate::components::*;
+use crate::routes::{Route};
+use crate::store::{Store, StoreError};
+use crate::types::composition::{Templates};
+use crate::types::{Template, TemplateCategory, TemplateId, TripAttributes, TripId};
+
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub template_id: TemplateId,
+}
+
+pub enum Msg {
+    // All templates are loaded, so that the included ones can be shown by name.
+    Loaded(Result<Templates, StoreError>),
+    OpenCreationForm,
+    CloseCreationForm,
+    CreateTrip(Vec<TemplateId>, String, TripAttributes),
+    TripCreated(Result<TripId, StoreError>),
+}
+
+// Read-only view of a template: its categories and items, with their quantity formulas and
+// conditions, and the templates it includes.
+pub struct TemplateView {
+    templates: Option<Templates>,
+    creation_form_open: bool,
+    error: Option<String>,
+}
+
+impl Component for TemplateView {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let mut template_view = Self {
+            templates: None,
+            creation_form_open: false,
+            error: None,
+        };
+        template_view.load(ctx);
+        template_view
+    }
+
+    fn changed(&mut self, ctx: &Context<Self>) -> bool {
+        self.templates = None;
+        self.creation_form_open = false;
+        self.load(ctx);
+        true
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::Loaded(Ok(templates)) => {
+                if !templates.contains_key(&ctx.props().template_id) {
+                    self.error = Some(format!("{}", StoreError::TemplateNotFound(ctx.props().template_id)));
+                }
+                self.templates = Some(templates);
+                true
+            },
+            Msg::Loaded(Err(err)) => {
+                log::error!("Failed to load template {}: {}", ctx.props().template_id, err);
+                self.error = Some(format!("{}", err));
+                true
+            },
+            Msg::OpenCreationForm => {
+                self.creation_form_open = true;
+                true
+            },
+            Msg::CloseCreationForm => {
+                self.creation_form_open = false;
+                true
+            },
+            Msg::CreateTrip(stack, name, attributes) => {
+                self.creation_form_open = false;
+                match Store::from_context(ctx.link()) {
+                    Ok(mut store) => ctx.link().send_future(async move {
+                        Msg::TripCreated(store.create_trip_from_templates(&stack, &name, &attributes).await)
+                    }),
+                    Err(err) => ctx.link().send_message(Msg::TripCreated(Err(err))),
+                }
+                true
+            },
+            Msg::TripCreated(Ok(trip_id)) => {
+                if let Some(history) = ctx.link().history() {
+                    history.push(Route::TripEdit { id: trip_id });
+                }
+                false
+            },
+            Msg::TripCreated(Err(err)) => {
+                log::error!("Failed to create trip from template {}: {}", ctx.props().template_id, err);
+                self.error = Some(format!("{}", err));
+                true
+            },
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let template_id = ctx.props().template_id;
+        let template = self.template(ctx);
+        let crumbs = vec![
+            BreadcrumbElement::Link { text: "Home".to_string(), destination: Route::Home },
+            BreadcrumbElement::Link { text: "Templates".to_string(), destination: Route::Templates },
+            BreadcrumbElement::Active {
+                text: template.map(|template| template.name.clone()).unwrap_or_default()
+            },
+        ];
+        let content = match template {
+            Some(template) => self.view_template(template),
+            None if self.error.is_none() => html! { <progress class="progress is-small is-primary"/> },
+            None => html! {},
+        };
+        html! {
+            <>
+                <Breadcrumbs {crumbs}/>
+                <section class="container px-2">
+                  <div class="buttons is-pulled-right">
+                    <button class="button is-link is-light" disabled={template.is_none()}
+                        onclick={ctx.link().callback(|_: MouseEvent| Msg::OpenCreationForm)}>
+                      { "Create trip" }
+                    </button>
+                    <Link<Route> to={Route::TemplateEdit { template_id }}
+                        classes={classes!("button", "is-primary", "is-light")}>
+                      { "Edit" }
+                    </Link<Route>>
+                  </div>
+                </section>
+                <section class="section">
+                  <div class="container">
+                    if let Some(error) = &self.error {
+                      <div class="notification is-danger is-light">{ error }</div>
+                    }
+                    { content }
+                  </div>
+                </section>
+                { self.creation_form(ctx) }
+            </>
+        }
+    }
+}
+
+impl TemplateView {
+
+    fn load(&mut self, ctx: &Context<Self>) {
+        self.error = None;
+        match Store::from_context(ctx.link()) {
+            Ok(mut store) => ctx.link().send_future(async move {
+                Msg::Loaded(store.get_templates().await)
+            }),
+            Err(err) => ctx.link().send_message(Msg::Loaded(Err(err))),
+        }
+    }
+
+    fn template(&self, ctx: &Context<Self>) -> Option<&Template> {
+        self.templates.as_ref()?.get(&ctx.props().template_id)
+    }
+
+    fn template_name(&self, template_id: TemplateId) -> String {
+        self.templates.as_ref()
+            .and_then(|templates| templates.get(&template_id))
+            .map(|template| template.name.clone())
+            .unwrap_or_else(|| format!("Missing template {}", template_id))
+    }
+
+    fn view_template(&self, template: &Template) -> Html {
+        let includes = template.includes.iter().map(|included_id| html! {
+            <Link<Route> to={Route::Template { template_id: *included_id }}
+                classes={classes!("tag", "is-link", "is-light")}>
+              { self.template_name(*included_id) }
+            </Link<Route>>
+        }).collect::<Html>();
+        let categories = template.categories.iter().enumerate()
+            .map(|(category_index, category)| view_category(category_index, category))
+            .collect::<Html>();
+        html! {
+            <>
+                <h1 class="title">{ template.name.clone() }</h1>
+                if !template.includes.is_empty() {
+                  <div class="tags">
+                    <span class="tag">{ "Includes" }</span>
+                    { includes }
+                  </div>
+                }
+                <div class="columns is-multiline">
+                  { categories }
+                </div>
+            </>
+        }
+    }
+
+    fn creation_form(&self, ctx: &Context<Self>) -> Html {
+        let template_id = ctx.props().template_id;
+        let (templates, template) = match (&self.templates, self.template(ctx)) {
+            (Some(templates), Some(template)) if self.creation_form_open => (templates, template),
+            _ => return html! {},
+        };
+        let other_templates = templates.iter()
+            .filter(|(other_id, _)| **other_id != template_id)
+            .map(|(other_id, other)| (*other_id, other.name.clone()))
+            .collect::<Vec<_>>();
+        let on_submit = ctx.link().callback(
+            move |(name, stacked, attributes): (String, Vec<TemplateId>, TripAttributes)| {
+                let mut stack = vec![template_id];
+                stack.extend(stacked);
+                Msg::CreateTrip(stack, name, attributes)
+            });
+        html! {
+          <TripCreationForm
+              template_name={template.name.clone()}
+              {other_templates}
+              {on_submit}
+              on_cancel={ctx.link().callback(|_: ()| Msg::CloseCreationForm)}/>
+        }
+    }
+}
+
+fn view_category(category_index: usize, category: &TemplateCategory) -> Html {
+    let items = category.items.iter().enumerate().map(|(item_index, item)| {
+        let conditions = item.conditions.iter().map(|condition| html! {
+            <span class="tag is-info is-light">{ condition.to_string() }</span>
+        }).collect::<Html>();
+        html! {
+            <div class="panel-block is-block" key={item_index}>
+              <div class="is-flex is-justify-content-space-between">
+                <span>{ item.name.clone() }</span>
+                if let Some(quantity) = &item.quantity {
+                  <code>{ quantity.clone() }</code>
+                }
+              </div>
+              if !item.conditions.is_empty() {
+                <div class="tags mt-1">{ conditions }</div>
+              }
+            </div>
+        }
+    }).collect::<Html>();
+    html! {
+        <div class="column is-half-tablet is-one-quarter-desktop" key={category_index}>
+          <div class="panel is-primary">
+            <p class="panel-heading">{ category.name.clone() }</p>
+            { items }
+          </div>
+        </div>
+    }
+}
@@ -0,0 +1,381 @@
+use yew::prelude::*;
+
+use crate::components::*;
+use crate::routes::{Route};
+use crate::routes::editing::{element_controls, move_element, remove_element, Direction};
+use crate::store::{Store, StoreError};
+use crate::types::composition::{check_includes, Templates};
+use crate::types::{Condition, Template, TemplateCategory, TemplateId, TemplateItem};
+
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub template_id: TemplateId,
+}
+
+// Category and item indices within the template.
+type ItemPosition = (usize, usize);
+
+pub enum Change {
+    RenameTemplate(String),
+    SetIncluded(TemplateId, bool),
+    AddCategory(String),
+    RenameCategory(usize, String),
+    MoveCategory(usize, Direction),
+    DeleteCategory(usize),
+    AddItem(usize, String),
+    RenameItem(ItemPosition, String),
+    MoveItem(ItemPosition, Direction),
+    DeleteItem(ItemPosition),
+    SetQuantity(ItemPosition, Option<String>),
+    AddCondition(ItemPosition, Condition),
+    // Position of the item and index of the condition.
+    RemoveCondition(ItemPosition, usize),
+}
+
+pub enum Msg {
+    // All templates are loaded, as they are needed to check the includes.
+    Loaded(Result<Templates, StoreError>),
+    Edit(Change),
+    // Generation of the save, the template that was saved and the result.
+    Saved(u64, Template, Result<(), StoreError>),
+}
+
+// Editor of the template's name, includes, categories and items, with their quantity formulas and
+// conditions. Works like TripEdit - changes are saved in the background and reverted if that fails.
+// Changes that would make the template invalid (includes forming a cycle, broken formulas) are
+// rejected before they are saved.
+pub struct TemplateEdit {
+    template: Option<Template>,
+    // Last version of the template known to be in the database.
+    saved_template: Option<Template>,
+    // All the other templates, to pick includes from and to check them against.
+    templates: Templates,
+    save_generation: u64,
+    saved_generation: u64,
+    error: Option<String>,
+}
+
+impl Component for TemplateEdit {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let mut template_edit = Self {
+            template: None,
+            saved_template: None,
+            templates: Templates::new(),
+            save_generation: 0,
+            saved_generation: 0,
+            error: None,
+        };
+        template_edit.load(ctx);
+        template_edit
+    }
+
+    fn changed(&mut self, ctx: &Context<Self>) -> bool {
+        self.template = None;
+        self.saved_template = None;
+        self.load(ctx);
+        true
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::Loaded(Ok(mut templates)) => {
+                match templates.remove(&ctx.props().template_id) {
+                    Some(template) => {
+                        self.saved_template = Some(template.clone());
+                        self.template = Some(template);
+                    },
+                    None => {
+                        self.error = Some(format!("{}", StoreError::TemplateNotFound(ctx.props().template_id)));
+                    },
+                }
+                self.templates = templates;
+                true
+            },
+            Msg::Loaded(Err(err)) => {
+                log::error!("Failed to load template {}: {}", ctx.props().template_id, err);
+                self.error = Some(format!("{}", err));
+                true
+            },
+            Msg::Edit(change) => {
+                let mut template = match self.template.clone() {
+                    Some(template) => template,
+                    None => return false,
+                };
+                if !apply(&mut template, change) {
+                    return false;
+                }
+                if let Err(error) = self.validate(ctx, &template) {
+                    self.error = Some(error);
+                    return true;
+                }
+                template.touch();
+                self.template = Some(template.clone());
+                self.save(ctx, template);
+                true
+            },
+            Msg::Saved(generation, template, Ok(())) => {
+                if generation > self.saved_generation {
+                    self.saved_generation = generation;
+                    self.saved_template = Some(template);
+                }
+                true
+            },
+            Msg::Saved(generation, _, Err(err)) => {
+                log::error!("Failed to save template {}: {}", ctx.props().template_id, err);
+                // Older saves failing do not matter - the latest one includes their changes.
+                if generation == self.save_generation {
+                    self.template = self.saved_template.clone();
+                    self.saved_generation = generation;
+                    self.error = Some(format!("Your last change was not saved. {}", err));
+                }
+                true
+            },
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let template_id = ctx.props().template_id;
+        let template_name = self.template.as_ref().map(|template| template.name.clone()).unwrap_or_default();
+        let crumbs = vec![
+            BreadcrumbElement::Link { text: "Home".to_string(), destination: Route::Home },
+            BreadcrumbElement::Link { text: "Templates".to_string(), destination: Route::Templates },
+            BreadcrumbElement::Link { text: template_name, destination: Route::Template { template_id } },
+            BreadcrumbElement::Active { text: "Edit".to_string() },
+        ];
+        let content = match &self.template {
+            Some(template) => self.view_template(ctx, template),
+            None if self.error.is_none() => html! { <progress class="progress is-small is-primary"/> },
+            None => html! {},
+        };
+        html! {
+            <>
+                <Breadcrumbs {crumbs}/>
+                <section class="section pt-2">
+                  <div class="container">
+                    if let Some(error) = &self.error {
+                      <div class="notification is-danger is-light">{ error }</div>
+                    }
+                    { content }
+                  </div>
+                </section>
+            </>
+        }
+    }
+}
+
+impl TemplateEdit {
+
+    fn load(&mut self, ctx: &Context<Self>) {
+        self.error = None;
+        match Store::from_context(ctx.link()) {
+            Ok(mut store) => ctx.link().send_future(async move {
+                Msg::Loaded(store.get_templates().await)
+            }),
+            Err(err) => ctx.link().send_message(Msg::Loaded(Err(err))),
+        }
+    }
+
+    fn save(&mut self, ctx: &Context<Self>, template: Template) {
+        self.save_generation += 1;
+        self.error = None;
+        let generation = self.save_generation;
+        let template_id = ctx.props().template_id;
+        match Store::from_context(ctx.link()) {
+            Ok(mut store) => ctx.link().send_future(async move {
+                let result = store.put_template(template_id, &template).await;
+                Msg::Saved(generation, template, result)
+            }),
+            Err(err) => ctx.link().send_message(Msg::Saved(generation, template, Err(err))),
+        }
+    }
+
+    // Checks the edited template before it is saved, returning the message to show if it is invalid.
+    fn validate(&self, ctx: &Context<Self>, template: &Template) -> Result<(), String> {
+        let mut templates = self.templates.clone();
+        templates.insert(ctx.props().template_id, template.clone());
+        check_includes(ctx.props().template_id, &templates)
+            .map_err(|err| format!("{}", StoreError::from(err)))?;
+        match template.formula_errors().first() {
+            Some((item_name, err)) => Err(format!("Invalid quantity of '{}': {}", item_name, err)),
+            None => Ok(()),
+        }
+    }
+
+    fn view_template(&self, ctx: &Context<Self>, template: &Template) -> Html {
+        let link = ctx.link();
+        let categories = template.categories.iter().enumerate().map(|(category_index, category)| {
+            self.view_category(ctx, category_index, category)
+        }).collect::<Html>();
+        html! {
+            <>
+                <div class="is-flex is-justify-content-space-between is-align-items-center mb-4">
+                  <h1 class="title mb-0">
+                    <EditableText value={template.name.clone()}
+                        on_change={link.callback(|name: String| Msg::Edit(Change::RenameTemplate(name)))}/>
+                  </h1>
+                  if self.saved_generation < self.save_generation {
+                    <span class="tag is-light">{ "Saving..." }</span>
+                  }
+                </div>
+                { self.view_includes(ctx, template) }
+                <div class="columns is-multiline">
+                  { categories }
+                  <div class="column is-half-tablet is-one-third-desktop">
+                    <div class="panel is-primary">
+                      <AddInput label={"Add new category"}
+                          on_add={link.callback(|name: String| Msg::Edit(Change::AddCategory(name)))}/>
+                    </div>
+                  </div>
+                </div>
+            </>
+        }
+    }
+
+    fn view_includes(&self, ctx: &Context<Self>, template: &Template) -> Html {
+        if self.templates.is_empty() {
+            return html! {};
+        }
+        let checkboxes = self.templates.iter().map(|(other_id, other)| {
+            let other_id = *other_id;
+            let included = template.includes.contains(&other_id);
+            html! {
+                <label class="b-checkbox checkbox mr-4" key={other_id}>
+                  <input type="checkbox" checked={included}
+                      onchange={ctx.link().callback(move |_: Event| Msg::Edit(Change::SetIncluded(other_id, !included)))}/>
+                  <span class="check"></span>
+                  <span class="control-label">{ other.name.clone() }</span>
+                </label>
+            }
+        }).collect::<Html>();
+        html! {
+            <div class="field mb-4">
+              <label class="label">{ "Includes" }</label>
+              <div class="control">{ checkboxes }</div>
+            </div>
+        }
+    }
+
+    fn view_category(&self, ctx: &Context<Self>, category_index: usize, category: &TemplateCategory) -> Html {
+        let link = ctx.link();
+        let items = category.items.iter().enumerate().map(|(item_index, item)| {
+            self.view_item(ctx, (category_index, item_index), item)
+        }).collect::<Html>();
+        html! {
+            <div class="column is-half-tablet is-one-third-desktop" key={category_index}>
+              <div class="panel is-primary">
+                <div class="panel-heading is-flex is-justify-content-space-between">
+                  <EditableText value={category.name.clone()}
+                      on_change={link.callback(move |name: String| Msg::Edit(Change::RenameCategory(category_index, name)))}/>
+                  { element_controls(
+                      link.callback(move |_| Msg::Edit(Change::MoveCategory(category_index, Direction::Up))),
+                      link.callback(move |_| Msg::Edit(Change::MoveCategory(category_index, Direction::Down))),
+                      link.callback(move |_| Msg::Edit(Change::DeleteCategory(category_index)))) }
+                </div>
+                { items }
+                <AddInput label={"Add new item"}
+                    on_add={link.callback(move |name: String| Msg::Edit(Change::AddItem(category_index, name)))}/>
+              </div>
+            </div>
+        }
+    }
+
+    fn view_item(&self, ctx: &Context<Self>, position: ItemPosition, item: &TemplateItem) -> Html {
+        let link = ctx.link();
+        let conditions = item.conditions.iter().enumerate().map(|(condition_index, condition)| html! {
+            <span class="tag is-info is-light" key={condition_index}>
+              { condition.to_string() }
+              <button class="delete is-small"
+                  onclick={link.callback(move |_: MouseEvent| Msg::Edit(Change::RemoveCondition(position, condition_index)))}>
+              </button>
+            </span>
+        }).collect::<Html>();
+        html! {
+            <div class="panel-block is-block" key={position.1}>
+              <div class="is-flex is-justify-content-space-between mb-2">
+                <EditableText value={item.name.clone()}
+                    on_change={link.callback(move |name: String| Msg::Edit(Change::RenameItem(position, name)))}/>
+                { element_controls(
+                    link.callback(move |_| Msg::Edit(Change::MoveItem(position, Direction::Up))),
+                    link.callback(move |_| Msg::Edit(Change::MoveItem(position, Direction::Down))),
+                    link.callback(move |_| Msg::Edit(Change::DeleteItem(position)))) }
+              </div>
+              <FormulaInput value={item.quantity.clone()}
+                  on_change={link.callback(move |quantity: Option<String>| Msg::Edit(Change::SetQuantity(position, quantity)))}/>
+              if !item.conditions.is_empty() {
+                <div class="tags mb-2">{ conditions }</div>
+              }
+              <ConditionInput
+                  on_add={link.callback(move |condition: Condition| Msg::Edit(Change::AddCondition(position, condition)))}/>
+            </div>
+        }
+    }
+}
+
+// Applies the change to the template. Returns false if it did not change anything, e.g. because it
+// refers to a category or item that does not exist.
+fn apply(template: &mut Template, change: Change) -> bool {
+    match change {
+        Change::RenameTemplate(name) => template.name = name,
+        Change::SetIncluded(template_id, true) => {
+            if template.includes.contains(&template_id) {
+                return false;
+            }
+            template.includes.push(template_id);
+        },
+        Change::SetIncluded(template_id, false) => {
+            let before = template.includes.len();
+            template.includes.retain(|included_id| *included_id != template_id);
+            return template.includes.len() != before;
+        },
+        Change::AddCategory(name) => template.categories.push(TemplateCategory::new(&name)),
+        Change::RenameCategory(index, name) => match template.categories.get_mut(index) {
+            Some(category) => category.name = name,
+            None => return false,
+        },
+        Change::MoveCategory(index, direction) =>
+            return move_element(&mut template.categories, index, direction),
+        Change::DeleteCategory(index) => return remove_element(&mut template.categories, index),
+        Change::AddItem(index, name) => match template.categories.get_mut(index) {
+            Some(category) => category.items.push(TemplateItem::new(&name)),
+            None => return false,
+        },
+        Change::MoveItem((category_index, item_index), direction) => {
+            match template.categories.get_mut(category_index) {
+                Some(category) => return move_element(&mut category.items, item_index, direction),
+                None => return false,
+            }
+        },
+        Change::DeleteItem((category_index, item_index)) => {
+            match template.categories.get_mut(category_index) {
+                Some(category) => return remove_element(&mut category.items, item_index),
+                None => return false,
+            }
+        },
+        Change::RenameItem(position, name) => match item_at(template, position) {
+            Some(item) => item.name = name,
+            None => return false,
+        },
+        Change::SetQuantity(position, quantity) => match item_at(template, position) {
+            Some(item) => item.quantity = quantity,
+            None => return false,
+        },
+        Change::AddCondition(position, condition) => match item_at(template, position) {
+            Some(item) => item.conditions.push(condition),
+            None => return false,
+        },
+        Change::RemoveCondition(position, condition_index) => match item_at(template, position) {
+            Some(item) => return remove_element(&mut item.conditions, condition_index),
+            None => return false,
+        },
+    }
+    true
+}
+
+fn item_at(template: &mut Template, (category_index, item_index): ItemPosition)
+    -> Option<&mut TemplateItem>
+{
+    template.categories.get_mut(category_index)?.items.get_mut(item_index)
+}
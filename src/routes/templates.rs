@@ -0,0 +1,26 @@
+use yew::prelude::*;
+
+use crate::components::*;
+use crate::routes::{Route};
+
+#[function_component(Templates)]
+pub fn templates_view() -> Html {
+    let crumbs = vec![
+        BreadcrumbElement::Link { text: "Home".to_string(), destination: Route::Home },
+        BreadcrumbElement::Active { text: "Templates".to_string() },
+    ];
+    html! {
+        <>
+            <Breadcrumbs {crumbs}/>
+            <section class="section">
+              <div class="container">
+                <div class="columns is-centered">
+                  <div class="column is-three-quarters">
+                    <TemplatesWidget />
+                  </div>
+                </div>
+              </div>
+           </section>
+        </>
+    }
+}
@@ -0,0 +1,223 @@
+use std::collections::HashSet;
+
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+use crate::components::*;
+use crate::routes::{Route};
+use crate::store::{Store, StoreError};
+use crate::types::{Category, Item, Trip, TripId};
+
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub id: TripId,
+}
+
+// Category and item indices within the trip.
+type ItemPosition = (usize, usize);
+
+pub enum Msg {
+    Loaded(Result<Trip, StoreError>),
+    TogglePacked(ItemPosition),
+    Saved(ItemPosition, Result<(), StoreError>),
+}
+
+// The packing checklist of a single trip. Ticking an item saves it right away.
+pub struct TripView {
+    trip: Option<Trip>,
+    // Items whose packed state is being saved.
+    saving: HashSet<ItemPosition>,
+    error: Option<String>,
+}
+
+impl Component for TripView {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let mut trip_view = Self {
+            trip: None,
+            saving: HashSet::new(),
+            error: None,
+        };
+        trip_view.load(ctx);
+        trip_view
+    }
+
+    fn changed(&mut self, ctx: &Context<Self>) -> bool {
+        self.trip = None;
+        self.saving.clear();
+        self.load(ctx);
+        true
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::Loaded(Ok(trip)) => {
+                self.trip = Some(trip);
+                true
+            },
+            Msg::Loaded(Err(err)) => {
+                log::error!("Failed to load trip {}: {}", ctx.props().id, err);
+                self.error = Some(format!("{}", err));
+                true
+            },
+            Msg::TogglePacked(position) => {
+                if self.saving.contains(&position) {
+                    return false;
+                }
+                let (item_id, packed) = match self.item(position) {
+                    Some(item) => {
+                        item.packed = !item.packed;
+                        (item.id, item.packed)
+                    },
+                    None => return false,
+                };
+                match Store::from_context(ctx.link()) {
+                    Ok(mut store) => {
+                        let trip_id = ctx.props().id;
+                        let (category_index, item_index) = position;
+                        self.saving.insert(position);
+                        ctx.link().send_future(async move {
+                            Msg::Saved(
+                                position,
+                                store.set_item_packed(
+                                    trip_id, category_index, item_index, item_id, packed).await)
+                        });
+                    },
+                    Err(err) => {
+                        ctx.link().send_message(Msg::Saved(position, Err(err)));
+                    },
+                }
+                true
+            },
+            Msg::Saved(position, Ok(())) => {
+                self.saving.remove(&position);
+                true
+            },
+            Msg::Saved(position, Err(err)) => {
+                log::error!("Failed to save trip {}: {}", ctx.props().id, err);
+                self.saving.remove(&position);
+                // The change did not make it to the database, so it should not be visible either.
+                if let Some(item) = self.item(position) {
+                    item.packed = !item.packed;
+                }
+                // What is shown is out of date, so the trip is shown as it is now.
+                if let StoreError::TripChanged(_) = err {
+                    self.load(ctx);
+                }
+                self.error = Some(format!("Your change was not saved. {}", err));
+                true
+            },
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let trip_name = self.trip.as_ref().map(|trip| trip.name.clone()).unwrap_or_default();
+        let crumbs = vec![
+            BreadcrumbElement::Link { text: "Home".to_string(), destination: Route::Home },
+            BreadcrumbElement::Link { text: "Trips".to_string(), destination: Route::Trips },
+            BreadcrumbElement::Active { text: trip_name },
+        ];
+        let content = match &self.trip {
+            Some(trip) => self.view_trip(ctx, trip),
+            None if self.error.is_none() => html! { <progress class="progress is-small is-primary"/> },
+            None => html! {},
+        };
+        html! {
+            <>
+                <Breadcrumbs {crumbs}/>
+                <section class="container px-2">
+                  <Link<Route> to={Route::TripEdit { id: ctx.props().id }}
+                      classes={classes!("button", "is-pulled-right", "is-primary", "is-light")}>
+                    { "Edit" }
+                  </Link<Route>>
+                </section>
+                <section class="section">
+                  <div class="container">
+                    if let Some(error) = &self.error {
+                      <div class="notification is-danger is-light">{ error }</div>
+                    }
+                    { content }
+                  </div>
+                </section>
+            </>
+        }
+    }
+}
+
+impl TripView {
+
+    fn load(&mut self, ctx: &Context<Self>) {
+        self.error = None;
+        let trip_id = ctx.props().id;
+        match Store::from_context(ctx.link()) {
+            Ok(mut store) => ctx.link().send_future(async move {
+                Msg::Loaded(store.get_trip(trip_id).await)
+            }),
+            Err(err) => ctx.link().send_message(Msg::Loaded(Err(err))),
+        }
+    }
+
+    fn item(&mut self, (category_index, item_index): ItemPosition) -> Option<&mut Item> {
+        self.trip.as_mut()?
+            .categories.get_mut(category_index)?
+            .items.get_mut(item_index)
+    }
+
+    fn view_trip(&self, ctx: &Context<Self>, trip: &Trip) -> Html {
+        let (packed, all) = trip.progress();
+        let categories = trip.categories.iter().enumerate().map(|(category_index, category)| {
+            self.view_category(ctx, category_index, category)
+        }).collect::<Html>();
+        html! {
+            <>
+                <p class="has-text-centered">{ format!("Packed {} of {} items", packed, all) }</p>
+                <progress class="progress is-primary" value={packed.to_string()} max={all.max(1).to_string()}/>
+                <div class="columns is-multiline">
+                  { categories }
+                </div>
+            </>
+        }
+    }
+
+    fn view_category(&self, ctx: &Context<Self>, category_index: usize, category: &Category) -> Html {
+        let (packed, all) = category.progress();
+        let items = category.items.iter().enumerate().map(|(item_index, item)| {
+            let position = (category_index, item_index);
+            let mut label_classes = classes!("b-checkbox", "checkbox");
+            if item.packed {
+                label_classes.push("item-done");
+            }
+            let label = if item.quantity > 1 {
+                format!("{} × {}", item.quantity, item.name)
+            } else {
+                item.name.clone()
+            };
+            html! {
+                <div class="panel-block is-justify-content-space-between" key={item.id}>
+                  <label class={label_classes}>
+                    <input type="checkbox" checked={item.packed}
+                        onchange={ctx.link().callback(move |_: Event| Msg::TogglePacked(position))}/>
+                    <span class="check"></span>
+                    <span class="control-label">{ label }</span>
+                  </label>
+                  if self.saving.contains(&position) {
+                    <span class="loader"></span>
+                  }
+                </div>
+            }
+        }).collect::<Html>();
+        html! {
+            <div class="column is-half-tablet is-one-quarter-desktop" key={category.id}>
+              <div class="panel is-primary">
+                <p class="panel-heading is-flex is-justify-content-space-between">
+                  <span>{ category.name.clone() }</span>
+                  <span>{ format!("{}/{}", packed, all) }</span>
+                </p>
+                { items }
+              </div>
+            </div>
+        }
+    }
+}
@@ -0,0 +1,353 @@
+use yew::prelude::*;
+
+use crate::components::*;
+use crate::routes::{Route};
+use crate::routes::editing::{element_controls, move_element, remove_element, Direction};
+use crate::store::{Store, StoreError};
+use crate::types::{Category, Item, Trip, TripId};
+
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    pub id: TripId,
+}
+
+// Category and item indices within the trip.
+type ItemPosition = (usize, usize);
+
+pub enum Change {
+    RenameTrip(String),
+    AddCategory(String),
+    RenameCategory(usize, String),
+    MoveCategory(usize, Direction),
+    DeleteCategory(usize),
+    AddItem(usize, String),
+    RenameItem(ItemPosition, String),
+    MoveItem(ItemPosition, Direction),
+    DeleteItem(ItemPosition),
+}
+
+pub enum Msg {
+    Loaded(Result<Trip, StoreError>),
+    Edit(Change),
+    // Generation of the save, the trip that was saved and the result.
+    Saved(u64, Trip, Result<(), StoreError>),
+}
+
+// Editor of the trip's name, categories and items. Changes are shown right away and saved in the
+// background; if saving fails, the trip goes back to its last saved state.
+pub struct TripEdit {
+    trip: Option<Trip>,
+    // Last version of the trip known to be in the database.
+    saved_trip: Option<Trip>,
+    // Incremented with every save, so that responses to older saves can be told apart from the
+    // response to the latest one.
+    save_generation: u64,
+    saved_generation: u64,
+    error: Option<String>,
+}
+
+impl Component for TripEdit {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let mut trip_edit = Self {
+            trip: None,
+            saved_trip: None,
+            save_generation: 0,
+            saved_generation: 0,
+            error: None,
+        };
+        trip_edit.load(ctx);
+        trip_edit
+    }
+
+    fn changed(&mut self, ctx: &Context<Self>) -> bool {
+        self.trip = None;
+        self.saved_trip = None;
+        self.load(ctx);
+        true
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::Loaded(Ok(trip)) => {
+                self.saved_trip = Some(trip.clone());
+                self.trip = Some(trip);
+                true
+            },
+            Msg::Loaded(Err(err)) => {
+                log::error!("Failed to load trip {}: {}", ctx.props().id, err);
+                self.error = Some(format!("{}", err));
+                true
+            },
+            Msg::Edit(change) => {
+                let trip = match self.trip.as_mut() {
+                    Some(trip) => trip,
+                    None => return false,
+                };
+                if !apply(trip, change) {
+                    return false;
+                }
+                trip.touch();
+                let trip = trip.clone();
+                self.save(ctx, trip);
+                true
+            },
+            Msg::Saved(generation, trip, Ok(())) => {
+                if generation > self.saved_generation {
+                    self.saved_generation = generation;
+                    self.saved_trip = Some(trip);
+                }
+                true
+            },
+            Msg::Saved(generation, _, Err(err)) => {
+                log::error!("Failed to save trip {}: {}", ctx.props().id, err);
+                // Older saves failing do not matter - the latest one includes their changes.
+                if generation == self.save_generation {
+                    self.trip = self.saved_trip.clone();
+                    self.saved_generation = generation;
+                    self.error = Some(format!("Your last change was not saved. {}", err));
+                }
+                true
+            },
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let trip_name = self.trip.as_ref().map(|trip| trip.name.clone()).unwrap_or_default();
+        let crumbs = vec![
+            BreadcrumbElement::Link { text: "Home".to_string(), destination: Route::Home },
+            BreadcrumbElement::Link { text: "Trips".to_string(), destination: Route::Trips },
+            BreadcrumbElement::Link { text: trip_name.clone(), destination: Route::Trip { id: ctx.props().id } },
+            BreadcrumbElement::Active { text: "Edit".to_string() },
+        ];
+        let content = match &self.trip {
+            Some(trip) => self.view_trip(ctx, trip),
+            None if self.error.is_none() => html! { <progress class="progress is-small is-primary"/> },
+            None => html! {},
+        };
+        html! {
+            <>
+                <Breadcrumbs {crumbs}/>
+                <section class="section pt-2">
+                  <div class="container">
+                    if let Some(error) = &self.error {
+                      <div class="notification is-danger is-light">{ error }</div>
+                    }
+                    { content }
+                  </div>
+                </section>
+            </>
+        }
+    }
+}
+
+impl TripEdit {
+
+    fn load(&mut self, ctx: &Context<Self>) {
+        self.error = None;
+        let trip_id = ctx.props().id;
+        match Store::from_context(ctx.link()) {
+            Ok(mut store) => ctx.link().send_future(async move {
+                Msg::Loaded(store.get_trip(trip_id).await)
+            }),
+            Err(err) => ctx.link().send_message(Msg::Loaded(Err(err))),
+        }
+    }
+
+    fn save(&mut self, ctx: &Context<Self>, trip: Trip) {
+        self.save_generation += 1;
+        self.error = None;
+        let generation = self.save_generation;
+        let trip_id = ctx.props().id;
+        match Store::from_context(ctx.link()) {
+            Ok(mut store) => ctx.link().send_future(async move {
+                let result = store.put_trip(trip_id, &trip).await;
+                Msg::Saved(generation, trip, result)
+            }),
+            Err(err) => ctx.link().send_message(Msg::Saved(generation, trip, Err(err))),
+        }
+    }
+
+    fn view_trip(&self, ctx: &Context<Self>, trip: &Trip) -> Html {
+        let link = ctx.link();
+        let categories = trip.categories.iter().enumerate().map(|(category_index, category)| {
+            self.view_category(ctx, category_index, category)
+        }).collect::<Html>();
+        html! {
+            <>
+                <div class="is-flex is-justify-content-space-between is-align-items-center mb-4">
+                  <h1 class="title mb-0">
+                    <EditableText value={trip.name.clone()}
+                        on_change={link.callback(|name: String| Msg::Edit(Change::RenameTrip(name)))}/>
+                  </h1>
+                  if self.saved_generation < self.save_generation {
+                    <span class="tag is-light">{ "Saving..." }</span>
+                  }
+                </div>
+                <div class="columns is-multiline">
+                  { categories }
+                  <div class="column is-half-tablet is-one-quarter-desktop">
+                    <div class="panel is-primary">
+                      <AddInput label={"Add new category"}
+                          on_add={link.callback(|name: String| Msg::Edit(Change::AddCategory(name)))}/>
+                    </div>
+                  </div>
+                </div>
+            </>
+        }
+    }
+
+    fn view_category(&self, ctx: &Context<Self>, category_index: usize, category: &Category) -> Html {
+        let link = ctx.link();
+        let items = category.items.iter().enumerate().map(|(item_index, item)| {
+            let position = (category_index, item_index);
+            html! {
+                <div class="panel-block is-justify-content-space-between" key={item.id}>
+                  <EditableText value={item.name.clone()}
+                      on_change={link.callback(move |name: String| Msg::Edit(Change::RenameItem(position, name)))}/>
+                  { element_controls(
+                      link.callback(move |_| Msg::Edit(Change::MoveItem(position, Direction::Up))),
+                      link.callback(move |_| Msg::Edit(Change::MoveItem(position, Direction::Down))),
+                      link.callback(move |_| Msg::Edit(Change::DeleteItem(position)))) }
+                </div>
+            }
+        }).collect::<Html>();
+        html! {
+            <div class="column is-half-tablet is-one-quarter-desktop" key={category.id}>
+              <div class="panel is-primary">
+                <div class="panel-heading is-flex is-justify-content-space-between">
+                  <EditableText value={category.name.clone()}
+                      on_change={link.callback(move |name: String| Msg::Edit(Change::RenameCategory(category_index, name)))}/>
+                  { element_controls(
+                      link.callback(move |_| Msg::Edit(Change::MoveCategory(category_index, Direction::Up))),
+                      link.callback(move |_| Msg::Edit(Change::MoveCategory(category_index, Direction::Down))),
+                      link.callback(move |_| Msg::Edit(Change::DeleteCategory(category_index)))) }
+                </div>
+                { items }
+                <AddInput label={"Add new item"}
+                    on_add={link.callback(move |name: String| Msg::Edit(Change::AddItem(category_index, name)))}/>
+              </div>
+            </div>
+        }
+    }
+}
+
+// Applies the change to the trip. Returns false if it did not change anything, e.g. because it
+// refers to a category or item that does not exist.
+fn apply(trip: &mut Trip, change: Change) -> bool {
+    match change {
+        Change::RenameTrip(name) => trip.name = name,
+        Change::AddCategory(name) => {
+            trip.categories.push(Category::new(&name));
+            trip.assign_missing_ids();
+        },
+        Change::RenameCategory(index, name) => match trip.categories.get_mut(index) {
+            Some(category) => category.name = name,
+            None => return false,
+        },
+        Change::MoveCategory(index, direction) =>
+            return move_element(&mut trip.categories, index, direction),
+        Change::DeleteCategory(index) => return remove_element(&mut trip.categories, index),
+        Change::AddItem(index, name) => match trip.categories.get_mut(index) {
+            Some(category) => {
+                category.items.push(Item::new(&name));
+                trip.assign_missing_ids();
+            },
+            None => return false,
+        },
+        Change::RenameItem((category_index, item_index), name) => {
+            match trip.categories.get_mut(category_index).and_then(|c| c.items.get_mut(item_index)) {
+                Some(item) => item.name = name,
+                None => return false,
+            }
+        },
+        Change::MoveItem((category_index, item_index), direction) => {
+            match trip.categories.get_mut(category_index) {
+                Some(category) => return move_element(&mut category.items, item_index, direction),
+                None => return false,
+            }
+        },
+        Change::DeleteItem((category_index, item_index)) => {
+            match trip.categories.get_mut(category_index) {
+                Some(category) => return remove_element(&mut category.items, item_index),
+                None => return false,
+            }
+        },
+    }
+    true
+}
+
+#[cfg(test)]
+mod trip_edit_tests {
+    use super::*;
+
+    fn test_trip() -> Trip {
+        let mut trip = Trip::new("Skiing", &"owner".to_string());
+        trip.categories = vec![
+            Category { items: vec![Item::new("Gloves"), Item::new("Hat")], ..Category::new("Clothes") },
+            Category { items: vec![Item::new("ID card")], ..Category::new("Documents") },
+        ];
+        trip.assign_missing_ids();
+        trip
+    }
+
+    fn names(trip: &Trip) -> Vec<(String, Vec<String>)> {
+        trip.categories.iter()
+            .map(|c| (c.name.clone(), c.items.iter().map(|i| i.name.clone()).collect()))
+            .collect()
+    }
+
+    fn strings(strs: &[&str]) -> Vec<String> {
+        strs.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn adds_and_renames() {
+        let mut trip = test_trip();
+        assert!(apply(&mut trip, Change::RenameTrip("Alps".to_string())));
+        assert!(apply(&mut trip, Change::AddCategory("Food".to_string())));
+        assert!(apply(&mut trip, Change::AddItem(2, "Apples".to_string())));
+        assert!(apply(&mut trip, Change::RenameCategory(1, "Papers".to_string())));
+        assert!(apply(&mut trip, Change::RenameItem((0, 1), "Cap".to_string())));
+        assert_eq!(trip.name, "Alps");
+        assert_eq!(names(&trip), vec![
+            ("Clothes".to_string(), strings(&["Gloves", "Cap"])),
+            ("Papers".to_string(), strings(&["ID card"])),
+            ("Food".to_string(), strings(&["Apples"])),
+        ]);
+        // The new elements get ids following the ones the trip had: 1 to 5.
+        assert_eq!((trip.categories[2].id, trip.categories[2].items[0].id), (6, 7));
+    }
+
+    #[test]
+    fn moves_and_deletes_keeping_ids() {
+        let mut trip = test_trip();
+        let hat_id = trip.categories[0].items[1].id;
+        assert!(apply(&mut trip, Change::MoveItem((0, 1), Direction::Up)));
+        assert!(apply(&mut trip, Change::MoveCategory(1, Direction::Up)));
+        assert!(apply(&mut trip, Change::DeleteItem((1, 1))));
+        assert_eq!(names(&trip), vec![
+            ("Documents".to_string(), strings(&["ID card"])),
+            ("Clothes".to_string(), strings(&["Hat"])),
+        ]);
+        assert_eq!(trip.categories[1].items[0].id, hat_id);
+        assert!(apply(&mut trip, Change::DeleteCategory(0)));
+        assert_eq!(names(&trip), vec![("Clothes".to_string(), strings(&["Hat"]))]);
+    }
+
+    #[test]
+    fn ignores_changes_of_missing_elements() {
+        let mut trip = test_trip();
+        let original = trip.clone();
+        assert!(!apply(&mut trip, Change::RenameCategory(2, "Food".to_string())));
+        assert!(!apply(&mut trip, Change::AddItem(2, "Apples".to_string())));
+        assert!(!apply(&mut trip, Change::RenameItem((1, 1), "Passport".to_string())));
+        assert!(!apply(&mut trip, Change::MoveItem((1, 0), Direction::Down)));
+        assert!(!apply(&mut trip, Change::MoveCategory(0, Direction::Up)));
+        assert!(!apply(&mut trip, Change::DeleteItem((5, 0))));
+        assert!(!apply(&mut trip, Change::DeleteCategory(2)));
+        assert_eq!(trip, original);
+    }
+}
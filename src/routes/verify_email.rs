@@ -0,0 +1,101 @@
+use serde::{Deserialize};
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+use crate::components::*;
+use crate::config::{Config};
+use crate::firebase::auth::{FirebaseAuth, FirebaseAuthError};
+use crate::routes::{Route};
+use crate::types::{UserAuthStatus};
+
+// Query of the link in the verification email, e.g. ?mode=verifyEmail&oobCode=...
+#[derive(Deserialize)]
+struct VerificationLinkQuery {
+    #[serde(rename = "oobCode")]
+    oob_code: String,
+}
+
+pub enum Msg {
+    // Verified email or the reason it could not be verified.
+    Verified(Result<String, FirebaseAuthError>),
+}
+
+// Target of the link in the verification email.
+pub struct VerifyEmail {
+    result: Option<Result<String, FirebaseAuthError>>,
+}
+
+impl Component for VerifyEmail {
+    type Message = Msg;
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let link = ctx.link();
+        let auth = link.context::<Config>(Callback::noop())
+            .ok_or(FirebaseAuthError::Internal("Config is not available.".to_string()))
+            .and_then(|(config, _)| FirebaseAuth::new_custom_url_base(&config.api_key, &config.auth_host));
+        let oob_code = link.location()
+            .and_then(|location| location.query::<VerificationLinkQuery>().ok())
+            .map(|query| query.oob_code)
+            .ok_or(FirebaseAuthError::InvalidOobCode);
+        match (auth, oob_code) {
+            (Ok(auth), Ok(oob_code)) => link.send_future(async move {
+                Msg::Verified(auth.confirm_email_verification(&oob_code).await)
+            }),
+            (Err(err), _) | (_, Err(err)) => link.send_message(Msg::Verified(Err(err))),
+        }
+        Self { result: None }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::Verified(result) => {
+                // The signed in user's tokens still say the email is not verified.
+                let signed_in = ctx.link().context::<UserAuthStatus>(Callback::noop())
+                    .is_some_and(|(user_auth, _)| user_auth.user_info.is_some());
+                let dispatcher = ctx.link().context::<AuthDispatcher>(Callback::noop());
+                if let (Ok(_), true, Some((dispatcher, _))) = (&result, signed_in, dispatcher) {
+                    dispatcher.emit(AuthAction::Refresh { on_done: Callback::noop() });
+                }
+                if let Err(err) = &result {
+                    log::warn!("Failed to verify email: {}", err);
+                }
+                self.result = Some(result);
+                true
+            },
+        }
+    }
+
+    fn view(&self, _ctx: &Context<Self>) -> Html {
+        let crumbs = vec![
+            BreadcrumbElement::Link { text: "Home".to_string(), destination: Route::Home },
+            BreadcrumbElement::Active { text: "Verify email".to_string() },
+        ];
+        let content = match &self.result {
+            None => html! { <progress class="progress is-small is-primary"/> },
+            Some(Ok(email)) => html! {
+                <div class="notification is-success is-light">
+                  { format!("Your email {} is verified. ", email) }
+                  <Link<Route> to={Route::Home}>{ "Continue" }</Link<Route>>
+                </div>
+            },
+            Some(Err(err)) => html! {
+                <div class="notification is-danger is-light">{ err.to_string() }</div>
+            },
+        };
+        html! {
+            <>
+                <Breadcrumbs {crumbs}/>
+                <section class="section">
+                  <div class="container">
+                    <div class="columns is-centered">
+                      <div class="column is-half-tablet is-one-third-desktop">
+                        { content }
+                      </div>
+                    </div>
+                  </div>
+                </section>
+            </>
+        }
+    }
+}
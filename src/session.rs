@@ -0,0 +1,113 @@
+// Persistence of the authenticated session across page reloads. Only what is needed to get a new id
+// token is stored - the id token itself expires within an hour anyway.
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::{UserId, UserInfo};
+
+static SESSION_KEY: &str = "packingChecklistSession";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StoredSession {
+    pub user_id: UserId,
+    pub email: String,
+    pub display_name: String,
+    pub refresh_token: String,
+}
+
+impl From<&UserInfo> for StoredSession {
+    fn from(user_info: &UserInfo) -> Self {
+        Self {
+            user_id: user_info.user_id.clone(),
+            email: user_info.email.clone(),
+            display_name: user_info.display_name.clone(),
+            refresh_token: user_info.refresh_token.clone(),
+        }
+    }
+}
+
+// Session stored by the last save, unless it was cleared since. A session that cannot be parsed
+// (e.g. stored by an older version of the app) is treated as no session.
+pub fn load() -> Option<StoredSession> {
+    let stored = storage::get(SESSION_KEY)?;
+    match serde_json::from_str::<StoredSession>(&stored) {
+        Ok(session) => Some(session),
+        Err(err) => {
+            log::warn!("Ignoring unparsable stored session: {}", err);
+            clear();
+            None
+        },
+    }
+}
+
+pub fn save(session: &StoredSession) {
+    match serde_json::to_string(session) {
+        Ok(serialized) => storage::set(SESSION_KEY, &serialized),
+        Err(err) => log::error!("Failed to serialize session: {}", err),
+    }
+}
+
+pub fn clear() {
+    storage::remove(SESSION_KEY);
+}
+
+// The browser's localStorage. It may be unavailable (e.g. disabled by the user), in which case
+// sessions simply do not survive a reload.
+#[cfg(target_arch = "wasm32")]
+mod storage {
+    fn local_storage() -> Option<web_sys::Storage> {
+        web_sys::window()?.local_storage().ok().flatten()
+    }
+
+    pub fn get(key: &str) -> Option<String> {
+        local_storage()?.get_item(key).ok().flatten()
+    }
+
+    pub fn set(key: &str, value: &str) {
+        if let Some(storage) = local_storage() {
+            if storage.set_item(key, value).is_err() {
+                log::warn!("Failed to store {} in localStorage", key);
+            }
+        }
+    }
+
+    pub fn remove(key: &str) {
+        if let Some(storage) = local_storage() {
+            let _ = storage.remove_item(key);
+        }
+    }
+}
+
+// There is no browser storage outside of the browser, so nothing is persisted there.
+#[cfg(not(target_arch = "wasm32"))]
+mod storage {
+    pub fn get(_key: &str) -> Option<String> { None }
+
+    pub fn set(_key: &str, _value: &str) {}
+
+    pub fn remove(_key: &str) {}
+}
+
+#[cfg(test)]
+mod session_tests {
+    use super::*;
+
+    #[test]
+    fn stored_session_round_trip() {
+        let session = StoredSession::from(&UserInfo {
+            user_id: "user".to_string(),
+            display_name: "Name".to_string(),
+            email: "user@example.com".to_string(),
+            email_verified: true,
+            is_anonymous: false,
+            id_token: "id_token".to_string(),
+            id_token_expires_at: 1234,
+            refresh_token: "refresh_token".to_string(),
+        });
+        let serialized = serde_json::to_string(&session).unwrap();
+        assert!(serialized.contains("\"refreshToken\":\"refresh_token\""));
+        assert!(!serialized.contains("id_token"));
+        assert_eq!(serde_json::from_str::<StoredSession>(&serialized).unwrap(), session);
+    }
+}
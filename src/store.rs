@@ -0,0 +1,320 @@
+// Access to the data of a single, authenticated user. Knows where everything lives in the
+// database (see types::db_paths), so components only deal with the domain types.
+
+use std::collections::BTreeMap;
+use std::future::Future;
+
+use yew::html::Scope;
+use yew::prelude::*;
+
+use crate::config::{Config};
+use crate::firebase::auth::{AccountInfo, FirebaseAuthError};
+use crate::firebase::auth_backend::{self, AuthBackend};
+use crate::firebase::database::{FirebaseDatabase, FirebaseDatabaseError};
+use crate::types::composition::{CompositionError, Templates};
+use crate::types::{
+    db_paths, instantiate_trip, new_id, now, ElementId, Item, Template, TemplateId, Trip,
+    TripAttributes, TripId, UserAuthStatus, UserId, UserInfo};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum StoreError {
+    NoConfig,
+    Unauthenticated,
+    TripNotFound(TripId),
+    TemplateNotFound(TemplateId),
+    // The template can't be deleted, because the template with the given name includes it.
+    TemplateIncluded(String),
+    Composition(CompositionError),
+    Database(FirebaseDatabaseError),
+    // The user has to log in again before the operation is allowed.
+    ReauthenticationNeeded,
+    // The user's trips and templates were deleted, but deleting the account itself failed.
+    AccountNotDeleted(FirebaseAuthError),
+    // The trip was modified elsewhere since it was loaded, so the change was not made.
+    TripChanged(TripId),
+    // Auth is faked (Config::offline_auth), so the database would reject every request.
+    OfflineAuth,
+}
+
+impl std::fmt::Display for StoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            StoreError::NoConfig => write!(f, "Config is not available."),
+            StoreError::Unauthenticated => write!(f, "Log in to access your trips and templates."),
+            StoreError::TripNotFound(id) => write!(f, "Trip {} does not exist.", id),
+            StoreError::TemplateNotFound(id) => write!(f, "Template {} does not exist.", id),
+            StoreError::TemplateIncluded(name) =>
+                write!(f, "The template is included by template \"{}\". Remove it from there first.", name),
+            StoreError::Composition(err) => write!(f, "{}", err),
+            StoreError::Database(err) => write!(f, "{}", err),
+            StoreError::ReauthenticationNeeded =>
+                write!(f, "{}", FirebaseAuthError::CredentialTooOldLoginAgain),
+            StoreError::AccountNotDeleted(err) => write!(f,
+                "Your trips and templates were deleted, but your account was not: {} \
+                 Try deleting it again.", err),
+            StoreError::TripChanged(_) =>
+                write!(f, "The trip was changed elsewhere in the meantime. Reload it to see the changes."),
+            StoreError::OfflineAuth =>
+                write!(f, "Trips and templates are not available when working offline."),
+        }
+    }
+}
+
+impl From<CompositionError> for StoreError {
+    fn from(err: CompositionError) -> Self {
+        match err {
+            CompositionError::MissingTemplate(template_id) => Self::TemplateNotFound(template_id),
+            err => Self::Composition(err),
+        }
+    }
+}
+
+impl From<FirebaseDatabaseError> for StoreError {
+    fn from(err: FirebaseDatabaseError) -> Self {
+        Self::Database(err)
+    }
+}
+
+impl From<FirebaseAuthError> for StoreError {
+    fn from(err: FirebaseAuthError) -> Self {
+        Self::Database(err.into())
+    }
+}
+
+pub type Result<T> = std::result::Result<T, StoreError>;
+
+// How a Store tells the rest of the app what it found out about the session while making requests.
+// UserContextProvider puts one in the context, next to UserAuthStatus.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SessionHooks {
+    // The store refreshed the user's tokens, which should replace the ones used elsewhere.
+    pub on_tokens_refreshed: Callback<UserInfo>,
+    // The refresh token of the given user no longer works.
+    pub on_session_expired: Callback<UserId>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Store {
+    // Has no id token of its own - every request gets a fresh one from auth.
+    db: FirebaseDatabase,
+    auth: Box<dyn AuthBackend>,
+    user_info: UserInfo,
+    // Told about the tokens the store refreshes, so that the rest of the app uses them as well.
+    session_hooks: Option<SessionHooks>,
+    // Auth is faked, see Config::offline_auth.
+    offline_auth: bool,
+}
+
+impl Store {
+    pub fn new(config: &Config, user_info: &UserInfo) -> Result<Self> {
+        let db = FirebaseDatabase::new_custom_url_base(
+            &config.database_namespace, &config.database_host)?;
+        let mut auth = auth_backend::from_config(config)?;
+        auth.set_refresh_token(Some(user_info.refresh_token.clone()));
+        auth.set_id_token(user_info.id_token.clone(), user_info.id_token_expires_at);
+        Ok(Self {
+            db,
+            auth,
+            user_info: user_info.clone(),
+            session_hooks: None,
+            offline_auth: config.offline_auth,
+        })
+    }
+
+    // Store for the currently logged in user, based on what ConfigProvider and UserContextProvider
+    // put in the context.
+    pub fn from_context<COMP: Component>(link: &Scope<COMP>) -> Result<Self> {
+        let (config, _) = link.context::<Config>(Callback::noop()).ok_or(StoreError::NoConfig)?;
+        let (user_auth, _) = link.context::<UserAuthStatus>(Callback::noop())
+            .ok_or(StoreError::Unauthenticated)?;
+        let user_info = user_auth.user_info.ok_or(StoreError::Unauthenticated)?;
+        let mut store = Store::new(&config, &user_info)?;
+        store.session_hooks = link.context::<SessionHooks>(Callback::noop()).map(|(hooks, _)| hooks);
+        Ok(store)
+    }
+
+    // Trips stored before categories and items had ids get them on their first load, so that the
+    // ids stay the same for every later load.
+    pub async fn get_trip(&mut self, trip_id: TripId) -> Result<Trip> {
+        let path = &db_paths::trip(&self.user_info.user_id, trip_id);
+        let mut trip = self.authorized(|db| async move { db.get::<Trip>(path).await }).await?
+            .ok_or(StoreError::TripNotFound(trip_id))?;
+        if trip.assign_missing_ids() {
+            self.put_trip(trip_id, &trip).await?;
+        }
+        Ok(trip)
+    }
+
+    // All of the user's trips, for listing them; their elements are not given missing ids here.
+    pub async fn get_trips(&mut self) -> Result<BTreeMap<TripId, Trip>> {
+        let path = &db_paths::trips(&self.user_info.user_id);
+        Ok(self.authorized(|db| async move { db.get::<BTreeMap<TripId, Trip>>(path).await }).await?
+            .unwrap_or_default())
+    }
+
+    pub async fn put_trip(&mut self, trip_id: TripId, trip: &Trip) -> Result<()> {
+        let path = &db_paths::trip(&self.user_info.user_id, trip_id);
+        self.authorized(|db| async move { db.put(path, trip).await }).await
+    }
+
+    // Only the single item is written, so that ticking items in two browser tabs at once does not
+    // make one of them overwrite the other's changes. Items are stored by position, so the write
+    // is guarded by the item's ETag and id: if the trip was edited elsewhere in the meantime, the
+    // position may hold a different item now, and TripChanged is returned instead.
+    pub async fn set_item_packed(
+        &mut self, trip_id: TripId, category_index: usize, item_index: usize, item_id: ElementId,
+        packed: bool)
+        -> Result<()>
+    {
+        let path = &db_paths::trip_item(&self.user_info.user_id, trip_id, category_index, item_index);
+        let (item, etag) = self.authorized(|db| async move { db.get_with_etag::<Item>(path).await })
+            .await?;
+        let item = match item {
+            Some(item) if item.id == item_id => Item { packed, ..item },
+            _ => return Err(StoreError::TripChanged(trip_id)),
+        };
+        let (item, etag) = (&item, etag.as_str());
+        match self.authorized(|db| async move { db.put_if_match(path, item, etag).await }).await {
+            Err(StoreError::Database(err))
+                if *err.last_attempt() == FirebaseDatabaseError::PreconditionFailed =>
+                return Err(StoreError::TripChanged(trip_id)),
+            result => result?,
+        }
+        let path = &format!("{}/updatedAt", db_paths::trip(&self.user_info.user_id, trip_id));
+        self.authorized(|db| async move { db.put(path, &now()).await }).await
+    }
+
+    pub async fn get_template(&mut self, template_id: TemplateId) -> Result<Template> {
+        let path = &db_paths::template(&self.user_info.user_id, template_id);
+        self.authorized(|db| async move { db.get::<Template>(path).await }).await?
+            .ok_or(StoreError::TemplateNotFound(template_id))
+    }
+
+    // All of the user's templates. Needed whenever includes have to be resolved.
+    pub async fn get_templates(&mut self) -> Result<Templates> {
+        let path = &db_paths::templates(&self.user_info.user_id);
+        Ok(self.authorized(|db| async move { db.get::<Templates>(path).await }).await?
+            .unwrap_or_default())
+    }
+
+    pub async fn put_template(&mut self, template_id: TemplateId, template: &Template) -> Result<()> {
+        let path = &db_paths::template(&self.user_info.user_id, template_id);
+        self.authorized(|db| async move { db.put(path, template).await }).await
+    }
+
+    // Creates and stores a new, empty template. Returns its id.
+    pub async fn create_template(&mut self, name: &str) -> Result<TemplateId> {
+        let template_id = new_id();
+        let template = Template::new(name, &self.user_info.user_id);
+        self.put_template(template_id, &template).await?;
+        Ok(template_id)
+    }
+
+    // Stores a copy of the template under a new id. Returns the id of the copy.
+    pub async fn duplicate_template(&mut self, template_id: TemplateId) -> Result<TemplateId> {
+        let original = self.get_template(template_id).await?;
+        let mut copy = Template::new(&format!("{} (copy)", original.name), &self.user_info.user_id);
+        copy.includes = original.includes;
+        copy.categories = original.categories;
+        let copy_id = new_id();
+        self.put_template(copy_id, &copy).await?;
+        Ok(copy_id)
+    }
+
+    // Deletes the template, unless other templates still include it. Trips created from it are
+    // not affected.
+    pub async fn delete_template(&mut self, template_id: TemplateId) -> Result<()> {
+        let templates = self.get_templates().await?;
+        if let Some(including) = templates.values().find(|t| t.includes.contains(&template_id)) {
+            return Err(StoreError::TemplateIncluded(including.name.clone()));
+        }
+        let path = &db_paths::template(&self.user_info.user_id, template_id);
+        self.authorized(|db| async move { db.delete(path).await }).await
+    }
+
+    // Creates and stores a new trip based on the current versions of the templates in the stack.
+    // Returns the id of the new trip. An empty name means the trip is named after the templates.
+    pub async fn create_trip_from_templates(
+        &mut self, stack: &[TemplateId], name: &str, attributes: &TripAttributes)
+        -> Result<TripId>
+    {
+        let templates = self.get_templates().await?;
+        let mut trip = instantiate_trip(stack, &templates, attributes, &self.user_info.user_id)?;
+        if !name.trim().is_empty() {
+            trip.name = name.trim().to_string();
+        }
+        let trip_id = new_id();
+        self.put_trip(trip_id, &trip).await?;
+        Ok(trip_id)
+    }
+
+    pub async fn get_account_info(&mut self) -> Result<AccountInfo> {
+        let result = self.auth.get_account_info().await;
+        self.share_refreshed_tokens();
+        if let Err(err) = &result {
+            self.report_auth_error(err);
+        }
+        Ok(result?)
+    }
+
+    // Deletes all of the user's trips and templates, and then their account. A recent sign in is
+    // checked up front, so that the data is not deleted when Firebase would refuse to delete the
+    // account. Guests cannot sign in again, so they are not asked to.
+    pub async fn delete_account(&mut self) -> Result<()> {
+        if !self.user_info.is_anonymous && self.auth.sign_in_too_old() {
+            return Err(StoreError::ReauthenticationNeeded);
+        }
+        let path = &db_paths::user(&self.user_info.user_id);
+        self.authorized(|db| async move { db.delete(path).await }).await?;
+        // The data is gone at this point, so the user has to know if the account is still there.
+        self.auth.delete_account().await.map_err(StoreError::AccountNotDeleted)
+    }
+
+    // Runs the request against a copy of the database with an id token that is not about to
+    // expire, see auth_backend::with_fresh_id_token.
+    async fn authorized<T, F, Fut>(&mut self, request: F) -> Result<T>
+        where F: Fn(FirebaseDatabase) -> Fut,
+              Fut: Future<Output = std::result::Result<T, FirebaseDatabaseError>>
+    {
+        if self.offline_auth {
+            return Err(StoreError::OfflineAuth);
+        }
+        let db = &self.db;
+        let result = auth_backend::with_fresh_id_token(self.auth.as_mut(), |id_token| {
+            let mut db = db.clone();
+            db.set_id_token(Some(id_token));
+            request(db)
+        }).await;
+        self.share_refreshed_tokens();
+        if let Err(FirebaseDatabaseError::Auth(err)) = &result {
+            self.report_auth_error(err);
+        }
+        Ok(result?)
+    }
+
+    // Errors that end the session sign the user out of the whole app, not just fail the request.
+    fn report_auth_error(&self, err: &FirebaseAuthError) {
+        if let (true, Some(hooks)) = (err.ends_session(), &self.session_hooks) {
+            hooks.on_session_expired.emit(self.user_info.user_id.clone());
+        }
+    }
+
+    fn share_refreshed_tokens(&mut self) {
+        let (id_token, expires_at, refresh_token) = match (
+            self.auth.id_token(), self.auth.id_token_expires_at(), self.auth.refresh_token())
+        {
+            (Some(id_token), Some(expires_at), Some(refresh_token))
+                if *id_token != self.user_info.id_token => (id_token, expires_at, refresh_token),
+            _ => return,
+        };
+        self.user_info.id_token = id_token.clone();
+        self.user_info.id_token_expires_at = expires_at;
+        self.user_info.refresh_token = refresh_token.clone();
+        if let Ok(claims) = self.auth.claims() {
+            self.user_info.email_verified = claims.email_verified;
+        }
+        if let Some(hooks) = &self.session_hooks {
+            hooks.on_tokens_refreshed.emit(self.user_info.clone());
+        }
+    }
+}
@@ -1,27 +1,61 @@
 use std::option::{Option};
 
+use super::{Timestamp, UserId};
+
 // Empty user_info means user is unauthenticated.
 // Non-empty user_info has the authentication data for the user from the last communication
 // with the backend.
 #[derive(Clone, PartialEq)]
 pub struct UserAuthStatus {
     pub user_info: Option<UserInfo>,
+    // The session stored by an earlier visit is being restored, so the user might turn out to be
+    // authenticated after all.
+    pub restoring: bool,
 }
 
 impl UserAuthStatus {
     pub fn new_empty() -> Self {
         Self {
             user_info: None,
+            restoring: false,
+        }
+    }
+
+    pub fn new_restoring() -> Self {
+        Self {
+            user_info: None,
+            restoring: true,
+        }
+    }
+
+    pub fn new_authenticated(user_info: UserInfo) -> Self {
+        Self {
+            user_info: Some(user_info),
+            restoring: false,
         }
     }
 
     //pub fn is_authenticated(&self) -> bool { self.user_info != Option::None }
+
+    // None if the user is not authenticated or is a guest, who has no email.
+    pub fn email_verified(&self) -> Option<bool> {
+        self.user_info.as_ref()
+            .filter(|user_info| !user_info.is_anonymous)
+            .map(|user_info| user_info.email_verified)
+    }
 }
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct UserInfo {
+    // user_id, email, email_verified and is_anonymous come from the id token's claims.
+    pub user_id: UserId,
     pub display_name: String,
+    // Empty for guests.
     pub email: String,
+    pub email_verified: bool,
+    // Guests signed in anonymously, until they link an email and password to their account.
+    pub is_anonymous: bool,
     pub id_token: String,
+    pub id_token_expires_at: Timestamp,
     pub refresh_token: String,
 }
@@ -0,0 +1,195 @@
+// Templates can include other templates, e.g. "Skiing" includes "Base". Includes are resolved
+// whenever a trip is created, so changes to an included template show up in every template that
+// includes it without copying anything.
+
+use std::collections::BTreeMap;
+
+use super::{Template, TemplateCategory, TemplateId};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CompositionError {
+    // Ids of the templates forming the cycle, starting and ending with the same one.
+    Cycle(Vec<TemplateId>),
+    MissingTemplate(TemplateId),
+}
+
+impl std::fmt::Display for CompositionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CompositionError::Cycle(ids) => {
+                let ids = ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+                write!(f, "Templates include each other: {}", ids.join(" -> "))
+            },
+            CompositionError::MissingTemplate(id) =>
+                write!(f, "Included template {} does not exist.", id),
+        }
+    }
+}
+
+pub type Templates = BTreeMap<TemplateId, Template>;
+
+// Ids of all the templates the stack consists of, with everything they include, each listed once.
+// Included templates come before the ones including them, and the stack is processed in order, so
+// the list goes from the most general template to the most specific one.
+pub fn resolve_stack(stack: &[TemplateId], templates: &Templates)
+    -> Result<Vec<TemplateId>, CompositionError>
+{
+    let mut resolved = Vec::new();
+    for template_id in stack {
+        visit(*template_id, templates, &mut Vec::new(), &mut resolved)?;
+    }
+    Ok(resolved)
+}
+
+// Checks that the template can be resolved, i.e. all its includes exist and there are no cycles.
+// Meant to be called before saving a template with modified includes.
+pub fn check_includes(template_id: TemplateId, templates: &Templates)
+    -> Result<(), CompositionError>
+{
+    resolve_stack(&[template_id], templates).map(|_| ())
+}
+
+// Categories of all the templates in the stack merged together. Categories with the same name
+// become one; items with the same name within a category are deduplicated, with the most specific
+// template's version of the item replacing the more general ones in place.
+pub fn merged_categories(stack: &[TemplateId], templates: &Templates)
+    -> Result<Vec<TemplateCategory>, CompositionError>
+{
+    let mut merged: Vec<TemplateCategory> = Vec::new();
+    for template_id in resolve_stack(stack, templates)? {
+        for category in &templates[&template_id].categories {
+            let existing_position = merged.iter()
+                .position(|existing| same_name(&existing.name, &category.name));
+            let merged_category = match existing_position {
+                Some(position) => &mut merged[position],
+                None => {
+                    merged.push(TemplateCategory::new(&category.name));
+                    merged.last_mut().unwrap()
+                },
+            };
+            for item in &category.items {
+                let existing_position = merged_category.items.iter()
+                    .position(|existing| same_name(&existing.name, &item.name));
+                match existing_position {
+                    Some(position) => merged_category.items[position] = item.clone(),
+                    None => merged_category.items.push(item.clone()),
+                }
+            }
+        }
+    }
+    Ok(merged)
+}
+
+fn visit(
+    template_id: TemplateId, templates: &Templates, path: &mut Vec<TemplateId>,
+    resolved: &mut Vec<TemplateId>)
+    -> Result<(), CompositionError>
+{
+    if let Some(position) = path.iter().position(|id| *id == template_id) {
+        let mut cycle = path[position..].to_vec();
+        cycle.push(template_id);
+        return Err(CompositionError::Cycle(cycle));
+    }
+    if resolved.contains(&template_id) {
+        return Ok(());
+    }
+    let template = templates.get(&template_id)
+        .ok_or(CompositionError::MissingTemplate(template_id))?;
+    path.push(template_id);
+    for included_id in &template.includes {
+        visit(*included_id, templates, path, resolved)?;
+    }
+    path.pop();
+    resolved.push(template_id);
+    Ok(())
+}
+
+fn same_name(a: &str, b: &str) -> bool {
+    a.trim().to_lowercase() == b.trim().to_lowercase()
+}
+
+#[cfg(test)]
+mod composition_tests {
+    use super::*;
+    use crate::types::TemplateItem;
+    use galvanic_assert::matchers::*;
+    use galvanic_assert::matchers::variant::*;
+
+    fn template(includes: Vec<TemplateId>, categories: Vec<(&str, Vec<TemplateItem>)>) -> Template {
+        let mut template = Template::new("Test", &"owner".to_string());
+        template.includes = includes;
+        template.categories = categories.into_iter().map(|(name, items)| TemplateCategory {
+            name: name.to_string(),
+            items,
+        }).collect();
+        template
+    }
+
+    fn test_templates() -> Templates {
+        let mut templates = Templates::new();
+        templates.insert(1, template(vec![], vec![
+            ("Clothes", vec![TemplateItem::new("Socks"), TemplateItem::new("Shirt")]),
+            ("Toiletries", vec![TemplateItem::new("Toothbrush")]),
+        ]));
+        templates.insert(2, template(vec![1], vec![
+            ("clothes", vec![
+                TemplateItem { quantity: Some("days".to_string()), ..TemplateItem::new("Socks") },
+                TemplateItem::new("Ski pants"),
+            ]),
+            ("Equipment", vec![TemplateItem::new("Goggles")]),
+        ]));
+        templates.insert(3, template(vec![1], vec![
+            ("Documents", vec![TemplateItem::new("Business cards")]),
+        ]));
+        templates
+    }
+
+    #[test]
+    fn resolves_includes_before_including_templates() {
+        assert_eq!(resolve_stack(&[2], &test_templates()).unwrap(), vec![1, 2]);
+        assert_eq!(resolve_stack(&[3, 2], &test_templates()).unwrap(), vec![1, 3, 2]);
+    }
+
+    #[test]
+    fn merges_and_deduplicates() {
+        let categories = merged_categories(&[2, 3], &test_templates()).unwrap();
+        assert_eq!(
+            categories.iter().map(|category| category.name.as_str()).collect::<Vec<_>>(),
+            vec!["Clothes", "Toiletries", "Equipment", "Documents"]
+        );
+        assert_eq!(
+            categories[0].items,
+            vec![
+                TemplateItem { quantity: Some("days".to_string()), ..TemplateItem::new("Socks") },
+                TemplateItem::new("Shirt"),
+                TemplateItem::new("Ski pants"),
+            ]
+        );
+    }
+
+    #[test]
+    fn detects_cycles() {
+        let mut templates = test_templates();
+        templates.get_mut(&1).unwrap().includes = vec![3];
+        assert_that!(
+            &check_includes(2, &templates),
+            maybe_err(eq(CompositionError::Cycle(vec![1, 3, 1])))
+        );
+
+        templates.get_mut(&1).unwrap().includes = vec![1];
+        assert_that!(
+            &check_includes(1, &templates),
+            maybe_err(eq(CompositionError::Cycle(vec![1, 1])))
+        );
+    }
+
+    #[test]
+    fn detects_missing_templates() {
+        let mut templates = test_templates();
+        templates.get_mut(&3).unwrap().includes = vec![42];
+        assert_that!(
+            &merged_categories(&[3], &templates),
+            maybe_err(eq(CompositionError::MissingTemplate(42)))
+        );
+    }
+}
@@ -0,0 +1,200 @@
+use serde::{Deserialize, Serialize};
+
+// Properties of a trip that decide which of the template's items end up in it. Asked for when a
+// trip is created from a template and stored with the trip.
+// They are also the variables available in quantity formulas, see QuantityFormula.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TripAttributes {
+    pub tags: Vec<String>,
+    pub destination_country: Option<String>,
+    // Length of the trip in days, counting both the first and the last one.
+    pub days: u32,
+    pub travellers: u32,
+    pub season: Option<Season>,
+}
+
+impl Default for TripAttributes {
+    fn default() -> Self {
+        Self {
+            tags: Vec::new(),
+            destination_country: None,
+            days: 1,
+            travellers: 1,
+            season: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Season {
+    Spring,
+    Summer,
+    Autumn,
+    Winter,
+}
+
+impl Season {
+    pub const ALL: [Season; 4] = [Season::Spring, Season::Summer, Season::Autumn, Season::Winter];
+}
+
+impl std::fmt::Display for Season {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+// A condition a template item can be guarded with. Stored as e.g. {"hasTag": "beach"} or
+// {"destinationNotIn": ["Poland"]}.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum Condition {
+    HasTag(String),
+    DestinationIn(Vec<String>),
+    // Useful for "abroad" - destination not in the home country. Holds when no destination is
+    // given, to err on the side of packing more.
+    DestinationNotIn(Vec<String>),
+    MinDays(u32),
+    MaxDays(u32),
+    SeasonIn(Vec<Season>),
+}
+
+impl Condition {
+    pub fn holds(&self, attributes: &TripAttributes) -> bool {
+        match self {
+            Condition::HasTag(tag) =>
+                attributes.tags.iter().any(|trip_tag| same_text(trip_tag, tag)),
+            Condition::DestinationIn(countries) => match &attributes.destination_country {
+                Some(destination) => countries.iter().any(|country| same_text(country, destination)),
+                None => false,
+            },
+            Condition::DestinationNotIn(countries) => match &attributes.destination_country {
+                Some(destination) => !countries.iter().any(|country| same_text(country, destination)),
+                None => true,
+            },
+            Condition::MinDays(min) => attributes.days >= *min,
+            Condition::MaxDays(max) => attributes.days <= *max,
+            Condition::SeasonIn(seasons) => match attributes.season {
+                Some(season) => seasons.contains(&season),
+                None => false,
+            },
+        }
+    }
+}
+
+// Short description shown next to the item in templates, e.g. "destination not in Poland, Czechia".
+impl std::fmt::Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Condition::HasTag(tag) => write!(f, "tagged {}", tag),
+            Condition::DestinationIn(countries) =>
+                write!(f, "destination in {}", countries.join(", ")),
+            Condition::DestinationNotIn(countries) =>
+                write!(f, "destination not in {}", countries.join(", ")),
+            Condition::MinDays(min) => write!(f, "at least {} days", min),
+            Condition::MaxDays(max) => write!(f, "at most {} days", max),
+            Condition::SeasonIn(seasons) => {
+                let seasons = seasons.iter().map(|season| season.to_string()).collect::<Vec<_>>();
+                write!(f, "in {}", seasons.join(", "))
+            },
+        }
+    }
+}
+
+// Items without conditions always apply; otherwise all of the conditions have to hold.
+pub fn all_hold(conditions: &[Condition], attributes: &TripAttributes) -> bool {
+    conditions.iter().all(|condition| condition.holds(attributes))
+}
+
+fn same_text(a: &str, b: &str) -> bool {
+    a.trim().to_lowercase() == b.trim().to_lowercase()
+}
+
+#[cfg(test)]
+mod conditions_tests {
+    use super::*;
+
+    fn beach_holiday() -> TripAttributes {
+        TripAttributes {
+            tags: vec!["Beach".to_string(), "holiday".to_string()],
+            destination_country: Some("Spain".to_string()),
+            days: 7,
+            travellers: 2,
+            season: Some(Season::Summer),
+        }
+    }
+
+    #[test]
+    fn tags_are_case_insensitive() {
+        assert!(Condition::HasTag("beach".to_string()).holds(&beach_holiday()));
+        assert!(!Condition::HasTag("business".to_string()).holds(&beach_holiday()));
+    }
+
+    #[test]
+    fn destination_conditions() {
+        let home = vec!["Poland".to_string()];
+        assert!(Condition::DestinationNotIn(home.clone()).holds(&beach_holiday()));
+        assert!(!Condition::DestinationIn(home.clone()).holds(&beach_holiday()));
+        assert!(Condition::DestinationIn(vec![" spain".to_string()]).holds(&beach_holiday()));
+
+        let unknown_destination = TripAttributes::default();
+        assert!(Condition::DestinationNotIn(home.clone()).holds(&unknown_destination));
+        assert!(!Condition::DestinationIn(home).holds(&unknown_destination));
+    }
+
+    #[test]
+    fn duration_and_season_conditions() {
+        assert!(Condition::MinDays(7).holds(&beach_holiday()));
+        assert!(!Condition::MinDays(8).holds(&beach_holiday()));
+        assert!(Condition::MaxDays(7).holds(&beach_holiday()));
+        assert!(!Condition::MaxDays(3).holds(&beach_holiday()));
+        assert!(Condition::SeasonIn(vec![Season::Spring, Season::Summer]).holds(&beach_holiday()));
+        assert!(!Condition::SeasonIn(vec![Season::Winter]).holds(&beach_holiday()));
+    }
+
+    #[test]
+    fn all_conditions_have_to_hold() {
+        assert!(all_hold(&[], &beach_holiday()));
+        assert!(all_hold(
+            &[Condition::HasTag("beach".to_string()), Condition::MinDays(3)],
+            &beach_holiday()
+        ));
+        assert!(!all_hold(
+            &[Condition::HasTag("beach".to_string()), Condition::SeasonIn(vec![Season::Winter])],
+            &beach_holiday()
+        ));
+    }
+
+    #[test]
+    fn parses_conditions() {
+        assert_eq!(
+            serde_json::from_str::<Vec<Condition>>(r#"
+                    [
+                        { "hasTag": "beach" },
+                        { "destinationNotIn": ["Poland"] },
+                        { "minDays": 3 },
+                        { "seasonIn": ["summer", "spring"] }
+                    ]"#).unwrap(),
+            vec![
+                Condition::HasTag("beach".to_string()),
+                Condition::DestinationNotIn(vec!["Poland".to_string()]),
+                Condition::MinDays(3),
+                Condition::SeasonIn(vec![Season::Summer, Season::Spring]),
+            ]
+        );
+    }
+
+    #[test]
+    fn describes_conditions() {
+        assert_eq!(
+            Condition::DestinationNotIn(vec!["Poland".to_string(), "Czechia".to_string()]).to_string(),
+            "destination not in Poland, Czechia"
+        );
+        assert_eq!(Condition::MinDays(3).to_string(), "at least 3 days");
+        assert_eq!(
+            Condition::SeasonIn(vec![Season::Summer, Season::Spring]).to_string(),
+            "in Summer, Spring"
+        );
+    }
+}
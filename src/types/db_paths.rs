@@ -0,0 +1,40 @@
+// Paths of the data in Firebase Realtime Database. Everything a user owns is kept under their
+// user id, so that database rules can simply restrict access to `users/$uid` to that user:
+//
+// users/
+//   <UserId>/
+//     trips/
+//       <TripId>: Trip
+//     templates/
+//       <TemplateId>: Template
+//
+// Ids are numbers, but since they are used as keys of JSON objects they are stored as strings.
+// They are sparse (see new_id), so Firebase never turns the collections into JSON arrays.
+
+use super::{TemplateId, TripId, UserId};
+
+pub fn user(user_id: &UserId) -> String {
+    format!("users/{}", user_id)
+}
+
+pub fn trips(user_id: &UserId) -> String {
+    format!("{}/trips", user(user_id))
+}
+
+pub fn trip(user_id: &UserId, trip_id: TripId) -> String {
+    format!("{}/{}", trips(user_id), trip_id)
+}
+
+pub fn templates(user_id: &UserId) -> String {
+    format!("{}/templates", user(user_id))
+}
+
+pub fn template(user_id: &UserId, template_id: TemplateId) -> String {
+    format!("{}/{}", templates(user_id), template_id)
+}
+
+pub fn trip_item(user_id: &UserId, trip_id: TripId, category_index: usize, item_index: usize)
+    -> String
+{
+    format!("{}/categories/{}/items/{}", trip(user_id, trip_id), category_index, item_index)
+}
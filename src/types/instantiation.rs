@@ -0,0 +1,204 @@
+use super::composition::{merged_categories, resolve_stack, CompositionError, Templates};
+use super::{
+    all_hold, Category, Item, SourceTemplate, TemplateId, TemplateItem, Trip, TripAttributes,
+    UserId};
+
+// Creates a new trip from a stack of templates (usually just one), with the merged categories and
+// items of all of them and everything they include, none of the items packed yet.
+// Items whose conditions do not hold for the given attributes are skipped, as are the ones whose
+// quantity formula evaluates to 0; categories are kept even if that leaves them empty, so the user
+// can still add their own items there.
+// The trip is named after the templates in the stack; it can be renamed later.
+pub fn instantiate_trip(
+    stack: &[TemplateId], templates: &Templates, attributes: &TripAttributes, owner: &UserId)
+    -> Result<Trip, CompositionError>
+{
+    let categories = merged_categories(stack, templates)?;
+    let name = stack.iter()
+        .map(|template_id| templates[template_id].name.as_str())
+        .collect::<Vec<_>>()
+        .join(" + ");
+
+    let mut trip = Trip::new(&name, owner);
+    trip.source_templates = resolve_stack(stack, templates)?.into_iter()
+        .map(|template_id| SourceTemplate {
+            id: template_id,
+            version: templates[&template_id].version,
+        })
+        .collect();
+    trip.attributes = attributes.clone();
+    trip.categories = categories.iter().map(|template_category| {
+        Category {
+            items: template_category.items.iter()
+                .filter(|template_item| all_hold(&template_item.conditions, attributes))
+                .map(|template_item| Item {
+                    quantity: item_quantity(template_item, attributes),
+                    ..Item::new(&template_item.name)
+                })
+                .filter(|item| item.quantity > 0)
+                .collect(),
+            ..Category::new(&template_category.name)
+        }
+    }).collect();
+    trip.assign_missing_ids();
+    Ok(trip)
+}
+
+// Formulas are validated when the template is edited, so a broken one should never get here. If
+// it does anyway, it is better to still create the trip with a single item than to fail.
+fn item_quantity(template_item: &TemplateItem, attributes: &TripAttributes) -> u32 {
+    match template_item.quantity_formula() {
+        None => 1,
+        Some(Ok(formula)) => formula.evaluate(attributes),
+        Some(Err(err)) => {
+            log::warn!("Invalid quantity formula of '{}': {}", template_item.name, err);
+            1
+        },
+    }
+}
+
+#[cfg(test)]
+mod instantiation_tests {
+    use super::*;
+    use crate::types::{Condition, ElementId, Template, TemplateCategory};
+
+    fn test_templates() -> Templates {
+        let mut skiing = Template::new("Skiing", &"owner".to_string());
+        skiing.version = 4;
+        skiing.categories = vec![
+            TemplateCategory {
+                name: "Clothes".to_string(),
+                items: vec![TemplateItem::new("Gloves"), TemplateItem::new("Hat")],
+            },
+            TemplateCategory::new("Documents"),
+        ];
+        let mut base = Template::new("Base", &"owner".to_string());
+        base.categories = vec![
+            TemplateCategory {
+                name: "Documents".to_string(),
+                items: vec![TemplateItem::new("ID card")],
+            },
+        ];
+        let mut templates = Templates::new();
+        templates.insert(7, skiing);
+        templates.insert(8, base);
+        templates
+    }
+
+    fn instantiate(stack: &[TemplateId], templates: &Templates, attributes: &TripAttributes)
+        -> Trip
+    {
+        instantiate_trip(stack, templates, attributes, &"owner".to_string()).unwrap()
+    }
+
+    fn category(id: ElementId, name: &str, items: Vec<Item>) -> Category {
+        Category { id, items, ..Category::new(name) }
+    }
+
+    fn item(id: ElementId, name: &str) -> Item {
+        Item { id, ..Item::new(name) }
+    }
+
+    #[test]
+    fn copies_categories_and_items() {
+        let trip = instantiate(&[7], &test_templates(), &TripAttributes::default());
+        assert_eq!(trip.name, "Skiing");
+        assert_eq!(trip.owner, "owner");
+        assert_eq!(
+            trip.categories,
+            vec![
+                category(1, "Clothes", vec![item(2, "Gloves"), item(3, "Hat")]),
+                category(4, "Documents", vec![]),
+            ]
+        );
+        assert!(trip.categories.iter().flat_map(|c| c.items.iter()).all(|item| !item.packed));
+    }
+
+    #[test]
+    fn records_source_templates_and_attributes() {
+        let mut templates = test_templates();
+        templates.get_mut(&7).unwrap().includes = vec![8];
+        let attributes = TripAttributes {
+            tags: vec!["mountains".to_string()],
+            ..TripAttributes::default()
+        };
+        let trip = instantiate_trip(&[7], &templates, &attributes, &"other_user".to_string())
+            .unwrap();
+        assert_eq!(
+            trip.source_templates,
+            vec![SourceTemplate { id: 8, version: 1 }, SourceTemplate { id: 7, version: 4 }]
+        );
+        assert_eq!(trip.attributes, attributes);
+        assert_eq!(trip.owner, "other_user");
+    }
+
+    #[test]
+    fn merges_template_stack() {
+        let trip = instantiate(&[8, 7], &test_templates(), &TripAttributes::default());
+        assert_eq!(trip.name, "Base + Skiing");
+        assert_eq!(
+            trip.categories,
+            vec![
+                category(1, "Documents", vec![item(2, "ID card")]),
+                category(3, "Clothes", vec![item(4, "Gloves"), item(5, "Hat")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_items_with_unmet_conditions() {
+        let mut templates = test_templates();
+        templates.get_mut(&7).unwrap().categories[1].items = vec![
+            TemplateItem::new("Passport"),
+            TemplateItem {
+                name: "Charger adapter".to_string(),
+                conditions: vec![Condition::DestinationNotIn(vec!["Poland".to_string()])],
+                quantity: None,
+            },
+        ];
+        let at_home = TripAttributes {
+            destination_country: Some("Poland".to_string()),
+            ..TripAttributes::default()
+        };
+        let abroad = TripAttributes {
+            destination_country: Some("Italy".to_string()),
+            ..TripAttributes::default()
+        };
+
+        assert_eq!(
+            instantiate(&[7], &templates, &at_home).categories[1].items,
+            vec![item(5, "Passport")]
+        );
+        assert_eq!(
+            instantiate(&[7], &templates, &abroad).categories[1].items,
+            vec![item(5, "Passport"), item(6, "Charger adapter")]
+        );
+    }
+
+    #[test]
+    fn evaluates_quantity_formulas() {
+        let mut templates = test_templates();
+        templates.get_mut(&7).unwrap().categories[0].items = vec![
+            TemplateItem { quantity: Some("nights + 1".to_string()), ..TemplateItem::new("Socks") },
+            TemplateItem { quantity: Some("days - 7".to_string()), ..TemplateItem::new("Detergent") },
+            TemplateItem { quantity: Some("days *".to_string()), ..TemplateItem::new("Broken") },
+        ];
+        let attributes = TripAttributes { days: 4, ..TripAttributes::default() };
+
+        assert_eq!(
+            instantiate(&[7], &templates, &attributes).categories[0].items,
+            vec![
+                Item { quantity: 4, ..item(2, "Socks") },
+                item(3, "Broken"),
+            ]
+        );
+    }
+
+    #[test]
+    fn fails_on_missing_template() {
+        assert_eq!(
+            instantiate_trip(&[42], &test_templates(), &TripAttributes::default(), &"owner".to_string()),
+            Err(CompositionError::MissingTemplate(42))
+        );
+    }
+}
@@ -1,5 +1,45 @@
 mod auth;
+pub mod composition;
+mod conditions;
+pub mod db_paths;
+mod instantiation;
+mod quantity;
+mod template;
+mod time;
+mod trip;
 
 pub use auth::{UserAuthStatus, UserInfo};
+pub use conditions::{Condition, Season, TripAttributes, all_hold};
+pub use instantiation::instantiate_trip;
+pub use quantity::{FormulaError, QuantityFormula};
+pub use template::{Template, TemplateCategory, TemplateItem};
+pub use time::{Timestamp, now, sleep};
+pub use trip::{Category, Item, SourceTemplate, Trip};
+
+pub use crate::firebase::auth::UserId;
 
 pub type TripId = u64;
+pub type TemplateId = u64;
+// Identifies a category or item within its trip, wherever it is moved.
+pub type ElementId = u64;
+
+// Generates an id for a new trip or template. Ids are based on the current time, with a random
+// part to avoid collisions between ids generated within the same millisecond. They stay below
+// 2^53, so they survive a round trip through JS numbers.
+pub fn new_id() -> u64 {
+    now() * 1000 + random_below(1000)
+}
+
+// A random number in 0..max. Not suitable for anything security related.
+#[cfg(target_arch = "wasm32")]
+pub fn random_below(max: u64) -> u64 {
+    (js_sys::Math::random() * max as f64) as u64
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn random_below(max: u64) -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.subsec_nanos() as u64 % max)
+        .unwrap_or(0)
+}
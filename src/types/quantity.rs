@@ -0,0 +1,304 @@
+// A tiny expression language for item quantities in templates, e.g. "nights + 1" or
+// "days * 2 * travellers". Supported are non-negative integers, the variables listed in Variable,
+// the operators + - * / and parentheses. All arithmetic is done on integers; division rounds up,
+// since it is better to pack one too many than one too few.
+
+use super::TripAttributes;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Variable {
+    Days,
+    Nights,
+    Travellers,
+}
+
+impl Variable {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "days" => Some(Variable::Days),
+            "nights" => Some(Variable::Nights),
+            "travellers" => Some(Variable::Travellers),
+            _ => None,
+        }
+    }
+
+    fn value(&self, attributes: &TripAttributes) -> i64 {
+        match self {
+            Variable::Days => attributes.days as i64,
+            Variable::Nights => attributes.days.saturating_sub(1) as i64,
+            Variable::Travellers => attributes.travellers as i64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(i64),
+    Variable(Variable),
+    Binary(Box<Expr>, Operator, Box<Expr>),
+}
+
+// Position is the index of the character (not byte) in the formula where the problem was found.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormulaError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for FormulaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} (at character {})", self.message, self.position + 1)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuantityFormula {
+    expr: Expr,
+}
+
+impl QuantityFormula {
+    pub fn parse(formula: &str) -> Result<Self, FormulaError> {
+        let mut parser = Parser { chars: formula.chars().collect(), position: 0, depth: 0 };
+        let expr = parser.expression()?;
+        parser.skip_whitespace();
+        if parser.position < parser.chars.len() {
+            return Err(parser.error("Unexpected character"));
+        }
+        Ok(Self { expr })
+    }
+
+    // Negative results are treated as 0, as is anything divided by 0.
+    pub fn evaluate(&self, attributes: &TripAttributes) -> u32 {
+        let value = evaluate(&self.expr, attributes);
+        value.clamp(0, u32::MAX as i64) as u32
+    }
+}
+
+fn evaluate(expr: &Expr, attributes: &TripAttributes) -> i64 {
+    match expr {
+        Expr::Number(value) => *value,
+        Expr::Variable(variable) => variable.value(attributes),
+        Expr::Binary(lhs, operator, rhs) => {
+            let (lhs, rhs) = (evaluate(lhs, attributes), evaluate(rhs, attributes));
+            match operator {
+                Operator::Add => lhs.saturating_add(rhs),
+                Operator::Subtract => lhs.saturating_sub(rhs),
+                Operator::Multiply => lhs.saturating_mul(rhs),
+                Operator::Divide if rhs == 0 => 0,
+                Operator::Divide => match (lhs.checked_div(rhs), lhs.checked_rem(rhs)) {
+                    (Some(quotient), Some(remainder)) =>
+                        if remainder != 0 && (lhs < 0) == (rhs < 0) { quotient + 1 } else { quotient },
+                    // Only i64::MIN / -1 overflows; saturates like the other operators.
+                    _ => i64::MAX,
+                },
+            }
+        },
+    }
+}
+
+// Recursive descent parser for:
+//   expression := term (("+" | "-") term)*
+//   term       := factor (("*" | "/") factor)*
+//   factor     := number | variable | "(" expression ")"
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+    // Parentheses open at the current position. Each level is a few stack frames, so it is
+    // limited to MAX_NESTING - formulas come from users.
+    depth: usize,
+}
+
+const MAX_NESTING: usize = 32;
+
+impl Parser {
+    fn expression(&mut self) -> Result<Expr, FormulaError> {
+        let mut expr = self.term()?;
+        while let Some(operator) = self.operator(&[('+', Operator::Add), ('-', Operator::Subtract)]) {
+            expr = Expr::Binary(Box::new(expr), operator, Box::new(self.term()?));
+        }
+        Ok(expr)
+    }
+
+    fn term(&mut self) -> Result<Expr, FormulaError> {
+        let mut expr = self.factor()?;
+        while let Some(operator) =
+            self.operator(&[('*', Operator::Multiply), ('/', Operator::Divide)])
+        {
+            expr = Expr::Binary(Box::new(expr), operator, Box::new(self.factor()?));
+        }
+        Ok(expr)
+    }
+
+    fn factor(&mut self) -> Result<Expr, FormulaError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('(') => {
+                if self.depth == MAX_NESTING {
+                    return Err(self.error("Too many nested parentheses"));
+                }
+                self.position += 1;
+                self.depth += 1;
+                let expr = self.expression()?;
+                self.depth -= 1;
+                self.skip_whitespace();
+                if self.peek() != Some(')') {
+                    return Err(self.error("Expected ')'"));
+                }
+                self.position += 1;
+                Ok(expr)
+            },
+            Some(c) if c.is_ascii_digit() => {
+                let start = self.position;
+                let digits = self.take_while(|c| c.is_ascii_digit());
+                digits.parse::<i64>().map(Expr::Number).map_err(|_| FormulaError {
+                    position: start,
+                    message: "Number is too big".to_string(),
+                })
+            },
+            Some(c) if c.is_alphabetic() => {
+                let start = self.position;
+                let name = self.take_while(|c| c.is_alphanumeric() || c == '_');
+                Variable::from_name(&name.to_lowercase()).map(Expr::Variable).ok_or(FormulaError {
+                    position: start,
+                    message: format!(
+                        "Unknown variable '{}', expected one of: days, nights, travellers", name),
+                })
+            },
+            Some(_) => Err(self.error("Expected a number, a variable or '('")),
+            None => Err(self.error("Unexpected end of the formula")),
+        }
+    }
+
+    fn operator(&mut self, operators: &[(char, Operator)]) -> Option<Operator> {
+        self.skip_whitespace();
+        let c = self.peek()?;
+        let (_, operator) = operators.iter().find(|(symbol, _)| *symbol == c)?;
+        self.position += 1;
+        Some(*operator)
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> String {
+        let start = self.position;
+        while self.peek().is_some_and(&predicate) {
+            self.position += 1;
+        }
+        self.chars[start..self.position].iter().collect()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn error(&self, message: &str) -> FormulaError {
+        FormulaError {
+            position: self.position,
+            message: message.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod quantity_tests {
+    use super::*;
+    use galvanic_assert::matchers::*;
+    use galvanic_assert::matchers::variant::*;
+
+    fn week_for_two() -> TripAttributes {
+        TripAttributes {
+            days: 7,
+            travellers: 2,
+            ..TripAttributes::default()
+        }
+    }
+
+    fn evaluate(formula: &str) -> u32 {
+        QuantityFormula::parse(formula).unwrap().evaluate(&week_for_two())
+    }
+
+    #[test]
+    fn evaluates_formulas() {
+        assert_eq!(evaluate("3"), 3);
+        assert_eq!(evaluate("nights + 1"), 7);
+        assert_eq!(evaluate("days * 2"), 14);
+        assert_eq!(evaluate("Days*2*travellers"), 28);
+        assert_eq!(evaluate("(nights + 1) * travellers"), 14);
+        assert_eq!(evaluate("1 + 2 * 3"), 7);
+        assert_eq!(evaluate("10 - 2 - 3"), 5);
+    }
+
+    #[test]
+    fn division_rounds_up() {
+        assert_eq!(evaluate("days / 3"), 3);
+        assert_eq!(evaluate("days / 7"), 1);
+        assert_eq!(evaluate("days / 0"), 0);
+    }
+
+    #[test]
+    fn arithmetic_saturates() {
+        assert_eq!(evaluate("9223372036854775807 + 1"), u32::MAX);
+        assert_eq!(evaluate("9223372036854775807 * travellers"), u32::MAX);
+        // i64::MIN / -1 would overflow.
+        assert_eq!(evaluate("(0 - 9223372036854775807 - 1) / (0 - 1)"), u32::MAX);
+        assert_eq!(evaluate("(0 - 9223372036854775807 - 1) / days"), 0);
+    }
+
+    #[test]
+    fn negative_results_are_zero() {
+        assert_eq!(evaluate("days - 10"), 0);
+    }
+
+    #[test]
+    fn reports_parse_errors() {
+        assert_that!(
+            &QuantityFormula::parse("weeks * 2"),
+            maybe_err(eq(FormulaError {
+                position: 0,
+                message: "Unknown variable 'weeks', expected one of: days, nights, travellers"
+                    .to_string(),
+            }))
+        );
+        assert_that!(
+            &QuantityFormula::parse("(days + 1"),
+            maybe_err(eq(FormulaError { position: 9, message: "Expected ')'".to_string() }))
+        );
+        assert_that!(
+            &QuantityFormula::parse("days 2"),
+            maybe_err(eq(FormulaError { position: 5, message: "Unexpected character".to_string() }))
+        );
+        assert_that!(
+            &QuantityFormula::parse("days *"),
+            maybe_err(eq(FormulaError {
+                position: 6,
+                message: "Unexpected end of the formula".to_string(),
+            }))
+        );
+    }
+
+    #[test]
+    fn limits_nesting() {
+        let nested = |depth: usize| format!("{}days{}", "(".repeat(depth), ")".repeat(depth));
+        assert_eq!(evaluate(&nested(32)), 7);
+        assert_that!(
+            &QuantityFormula::parse(&nested(33)),
+            maybe_err(eq(FormulaError {
+                position: 32,
+                message: "Too many nested parentheses".to_string(),
+            }))
+        );
+        assert!(QuantityFormula::parse(&nested(100_000)).is_err());
+    }
+}
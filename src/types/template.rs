@@ -0,0 +1,157 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Condition, FormulaError, QuantityFormula, TemplateId, Timestamp, UserId, now};
+
+// A reusable set of categories and items that trips are created from. Like for trips, the id is
+// the key under which the template is stored, see db_paths.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Template {
+    pub name: String,
+    pub owner: UserId,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+    // Incremented on every modification, so that trips can tell which revision of the template
+    // they were created from.
+    #[serde(default)]
+    pub version: u32,
+    // Templates whose categories and items are part of this one as well, see composition.
+    #[serde(default)]
+    pub includes: Vec<TemplateId>,
+    #[serde(default)]
+    pub categories: Vec<TemplateCategory>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateCategory {
+    pub name: String,
+    #[serde(default)]
+    pub items: Vec<TemplateItem>,
+}
+
+// Unlike trip's Item, it has no packed state - it only describes what should be packed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateItem {
+    pub name: String,
+    // The item is added to a trip only if all of these hold for the trip's attributes.
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+    // Source of a QuantityFormula, e.g. "nights + 1". No formula means a single item.
+    #[serde(default)]
+    pub quantity: Option<String>,
+}
+
+impl Template {
+    pub fn new(name: &str, owner: &UserId) -> Self {
+        let created_at = now();
+        Self {
+            name: name.to_string(),
+            owner: owner.clone(),
+            created_at,
+            updated_at: created_at,
+            version: 1,
+            includes: Vec::new(),
+            categories: Vec::new(),
+        }
+    }
+
+    // Names of the items whose quantity formulas do not parse, together with the errors. The
+    // editor should not let a template with any of those be saved.
+    pub fn formula_errors(&self) -> Vec<(String, FormulaError)> {
+        self.categories.iter()
+            .flat_map(|category| category.items.iter())
+            .filter_map(|item| match item.quantity_formula() {
+                Some(Err(err)) => Some((item.name.clone(), err)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // Marks the template as modified just now and bumps its version. Should be called before
+    // every write to the database.
+    pub fn touch(&mut self) {
+        self.updated_at = now();
+        self.version += 1;
+    }
+}
+
+impl TemplateCategory {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            items: Vec::new(),
+        }
+    }
+}
+
+impl TemplateItem {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            conditions: Vec::new(),
+            quantity: None,
+        }
+    }
+
+    pub fn quantity_formula(&self) -> Option<Result<QuantityFormula, FormulaError>> {
+        self.quantity.as_ref().map(|formula| QuantityFormula::parse(formula))
+    }
+}
+
+#[cfg(test)]
+mod template_tests {
+    use super::*;
+
+    #[test]
+    fn parses_template() {
+        assert_eq!(
+            serde_json::from_str::<Template>(r#"
+                    {
+                        "name": "Base",
+                        "owner": "user_id",
+                        "createdAt": 1000,
+                        "updatedAt": 1000,
+                        "version": 3,
+                        "includes": [1, 2],
+                        "categories": [
+                            {
+                                "name": "Toiletries",
+                                "items": [ { "name": "Toothbrush" } ]
+                            }
+                        ]
+                    }"#).unwrap(),
+            Template {
+                name: "Base".to_string(),
+                owner: "user_id".to_string(),
+                created_at: 1000,
+                updated_at: 1000,
+                version: 3,
+                includes: vec![1, 2],
+                categories: vec![
+                    TemplateCategory {
+                        name: "Toiletries".to_string(),
+                        items: vec![TemplateItem::new("Toothbrush")],
+                    },
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn reports_invalid_formulas() {
+        let mut template = Template::new("Base", &"user_id".to_string());
+        let mut category = TemplateCategory::new("Clothes");
+        category.items = vec![
+            TemplateItem { quantity: Some("nights + 1".to_string()), ..TemplateItem::new("Socks") },
+            TemplateItem { quantity: Some("weeks".to_string()), ..TemplateItem::new("Shirts") },
+            TemplateItem::new("Hat"),
+        ];
+        template.categories.push(category);
+
+        let errors = template.formula_errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, "Shirts");
+    }
+}
@@ -0,0 +1,40 @@
+use std::time::Duration;
+
+// Milliseconds since the Unix epoch. This is the same representation Firebase uses for its server
+// timestamps, so it can be stored in the database as is.
+pub type Timestamp = u64;
+
+// std::time::SystemTime is not available in the browser, so there we have to ask JS for the time.
+#[cfg(target_arch = "wasm32")]
+pub fn now() -> Timestamp {
+    js_sys::Date::now() as Timestamp
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn now() -> Timestamp {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as Timestamp)
+        .unwrap_or(0)
+}
+
+// Waits without blocking the thread. The browser has no threads to block, so it uses a timer there.
+#[cfg(target_arch = "wasm32")]
+pub async fn sleep(duration: Duration) {
+    let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+        let timer_started = web_sys::window()
+            .map(|window| window.set_timeout_with_callback_and_timeout_and_arguments_0(
+                &resolve, duration.as_millis() as i32).is_ok())
+            .unwrap_or(false);
+        // Better not to wait at all than to wait forever.
+        if !timer_started {
+            let _ = resolve.call0(&js_sys::Object::new());
+        }
+    });
+    let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await
+}
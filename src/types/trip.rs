@@ -0,0 +1,293 @@
+use serde::{Deserialize, Serialize};
+
+use super::{ElementId, TemplateId, Timestamp, TripAttributes, UserId, now};
+
+// A single trip's checklist. Its id is not stored here - it is the key under which the trip is
+// kept in the database, see db_paths.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Trip {
+    pub name: String,
+    pub owner: UserId,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+    // Templates (including the ones included by them) the trip was instantiated from, if any.
+    // Trips created before templates could include others have a single sourceTemplate instead.
+    #[serde(default, alias = "sourceTemplate", deserialize_with = "one_or_many")]
+    pub source_templates: Vec<SourceTemplate>,
+    #[serde(default)]
+    pub attributes: TripAttributes,
+    // Realtime Database does not store empty arrays at all, hence the defaults here and below.
+    #[serde(default)]
+    pub categories: Vec<Category>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceTemplate {
+    pub id: TemplateId,
+    pub version: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Category {
+    // 0 until the trip assigns it one, see Trip::assign_missing_ids.
+    #[serde(default)]
+    pub id: ElementId,
+    pub name: String,
+    #[serde(default)]
+    pub items: Vec<Item>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Item {
+    // 0 until the trip assigns it one, see Trip::assign_missing_ids.
+    #[serde(default)]
+    pub id: ElementId,
+    pub name: String,
+    #[serde(default = "default_quantity")]
+    pub quantity: u32,
+    #[serde(default)]
+    pub packed: bool,
+}
+
+fn default_quantity() -> u32 {
+    1
+}
+
+fn one_or_many<'de, D>(deserializer: D) -> Result<Vec<SourceTemplate>, D::Error>
+    where D: serde::Deserializer<'de>
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(SourceTemplate),
+        Many(Vec<SourceTemplate>),
+    }
+    Ok(match Option::<OneOrMany>::deserialize(deserializer)? {
+        Some(OneOrMany::One(source_template)) => vec![source_template],
+        Some(OneOrMany::Many(source_templates)) => source_templates,
+        None => Vec::new(),
+    })
+}
+
+impl Trip {
+    pub fn new(name: &str, owner: &UserId) -> Self {
+        let created_at = now();
+        Self {
+            name: name.to_string(),
+            owner: owner.clone(),
+            created_at,
+            updated_at: created_at,
+            source_templates: Vec::new(),
+            attributes: TripAttributes::default(),
+            categories: Vec::new(),
+        }
+    }
+
+    // Marks the trip as modified just now. Should be called before every write to the database.
+    pub fn touch(&mut self) {
+        self.updated_at = now();
+    }
+
+    // Gives the categories and items without an id the next ids not used in the trip yet. Should
+    // be called whenever elements are added, and on trips stored before elements had ids. Returns
+    // whether any id was assigned, i.e. whether the trip has to be written back.
+    pub fn assign_missing_ids(&mut self) -> bool {
+        let max_id = self.categories.iter()
+            .flat_map(|category| {
+                std::iter::once(category.id).chain(category.items.iter().map(|item| item.id))
+            })
+            .max()
+            .unwrap_or(0);
+        let mut next_id = max_id + 1;
+        let mut assign = |id: &mut ElementId| {
+            if *id == 0 {
+                *id = next_id;
+                next_id += 1;
+            }
+        };
+        for category in &mut self.categories {
+            assign(&mut category.id);
+            for item in &mut category.items {
+                assign(&mut item.id);
+            }
+        }
+        next_id != max_id + 1
+    }
+
+    // Number of packed items and number of all the items.
+    pub fn progress(&self) -> (usize, usize) {
+        self.categories.iter()
+            .map(Category::progress)
+            .fold((0, 0), |(packed, all), (category_packed, category_all)| {
+                (packed + category_packed, all + category_all)
+            })
+    }
+}
+
+impl Category {
+    pub fn new(name: &str) -> Self {
+        Self {
+            id: 0,
+            name: name.to_string(),
+            items: Vec::new(),
+        }
+    }
+
+    // Number of packed items and number of all the items.
+    pub fn progress(&self) -> (usize, usize) {
+        (self.items.iter().filter(|item| item.packed).count(), self.items.len())
+    }
+}
+
+impl Item {
+    pub fn new(name: &str) -> Self {
+        Self {
+            id: 0,
+            name: name.to_string(),
+            quantity: default_quantity(),
+            packed: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod trip_tests {
+    use super::*;
+
+    #[test]
+    fn parses_trip_without_categories() {
+        assert_eq!(
+            serde_json::from_str::<Trip>(r#"
+                    {
+                        "name": "Skiing",
+                        "owner": "user_id",
+                        "createdAt": 1000,
+                        "updatedAt": 2000
+                    }"#).unwrap(),
+            Trip {
+                name: "Skiing".to_string(),
+                owner: "user_id".to_string(),
+                created_at: 1000,
+                updated_at: 2000,
+                source_templates: Vec::new(),
+                attributes: TripAttributes::default(),
+                categories: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn parses_full_trip() {
+        assert_eq!(
+            serde_json::from_str::<Trip>(r#"
+                    {
+                        "name": "Skiing",
+                        "owner": "user_id",
+                        "createdAt": 1000,
+                        "updatedAt": 2000,
+                        "sourceTemplates": [ { "id": 42, "version": 7 } ],
+                        "attributes": { "tags": ["mountains"], "days": 5 },
+                        "categories": [
+                            {
+                                "id": 1,
+                                "name": "Clothes",
+                                "items": [
+                                    { "id": 2, "name": "Gloves", "quantity": 2, "packed": true },
+                                    { "id": 3, "name": "Hat" }
+                                ]
+                            },
+                            { "id": 4, "name": "Empty" }
+                        ]
+                    }"#).unwrap(),
+            Trip {
+                name: "Skiing".to_string(),
+                owner: "user_id".to_string(),
+                created_at: 1000,
+                updated_at: 2000,
+                source_templates: vec![SourceTemplate { id: 42, version: 7 }],
+                attributes: TripAttributes {
+                    tags: vec!["mountains".to_string()],
+                    days: 5,
+                    ..TripAttributes::default()
+                },
+                categories: vec![
+                    Category {
+                        id: 1,
+                        name: "Clothes".to_string(),
+                        items: vec![
+                            Item { id: 2, name: "Gloves".to_string(), quantity: 2, packed: true },
+                            Item { id: 3, name: "Hat".to_string(), quantity: 1, packed: false },
+                        ],
+                    },
+                    Category { id: 4, ..Category::new("Empty") },
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn parses_trip_with_single_source_template() {
+        let trip = serde_json::from_str::<Trip>(r#"
+                {
+                    "name": "Skiing",
+                    "owner": "user_id",
+                    "createdAt": 1000,
+                    "updatedAt": 2000,
+                    "sourceTemplate": { "id": 42, "version": 7 }
+                }"#).unwrap();
+        assert_eq!(trip.source_templates, vec![SourceTemplate { id: 42, version: 7 }]);
+        assert!(serde_json::to_string(&trip).unwrap()
+            .contains(r#""sourceTemplates":[{"id":42,"version":7}]"#));
+    }
+
+    #[test]
+    fn counts_progress() {
+        let mut trip = Trip::new("Skiing", &"user_id".to_string());
+        trip.categories = vec![
+            Category {
+                items: vec![
+                    Item { packed: true, ..Item::new("Gloves") },
+                    Item::new("Hat"),
+                ],
+                ..Category::new("Clothes")
+            },
+            Category {
+                items: vec![Item { packed: true, ..Item::new("Passport") }],
+                ..Category::new("Documents")
+            },
+            Category::new("Empty"),
+        ];
+        assert_eq!(trip.categories[0].progress(), (1, 2));
+        assert_eq!(trip.categories[2].progress(), (0, 0));
+        assert_eq!(trip.progress(), (2, 3));
+    }
+
+    #[test]
+    fn assigns_missing_ids() {
+        let mut trip = serde_json::from_str::<Trip>(r#"
+                {
+                    "name": "Skiing",
+                    "owner": "user_id",
+                    "createdAt": 1000,
+                    "updatedAt": 2000,
+                    "categories": [
+                        { "name": "Clothes", "items": [ { "name": "Gloves" }, { "id": 5, "name": "Hat" } ] },
+                        { "id": 2, "name": "Documents", "items": [ { "name": "Passport" } ] }
+                    ]
+                }"#).unwrap();
+        assert!(trip.assign_missing_ids());
+        let ids = |trip: &Trip| trip.categories.iter()
+            .map(|category| (category.id, category.items.iter().map(|item| item.id).collect()))
+            .collect::<Vec<(ElementId, Vec<ElementId>)>>();
+        assert_eq!(ids(&trip), vec![(6, vec![7, 5]), (2, vec![8])]);
+
+        assert!(!trip.assign_missing_ids());
+        trip.categories[1].items.push(Item::new("ID card"));
+        assert!(trip.assign_missing_ids());
+        assert_eq!(ids(&trip), vec![(6, vec![7, 5]), (2, vec![8, 9])]);
+    }
+}